@@ -0,0 +1,88 @@
+//! Procedural macros for `embedded-static-ref-cell`
+//!
+//! These are re-exported by `embedded-static-ref-cell` when its `derive` feature is enabled, and
+//! should be used through that crate rather than depending on this one directly.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Generates a `<Name>Cells` struct holding one `StaticRefCell` per field of the annotated struct
+///
+/// See the documentation of `embedded_static_ref_cell::SplitIntoCells` for details.
+#[proc_macro_derive(SplitIntoCells)]
+pub fn derive_split_into_cells(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    split_into_cells(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn split_into_cells(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "SplitIntoCells cannot be derived for generic structs, since the cells are used in statics",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "SplitIntoCells can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "SplitIntoCells can only be derived for structs",
+            ))
+        }
+    };
+
+    let krate = quote!(::embedded_static_ref_cell);
+    let vis = &input.vis;
+    let name = &input.ident;
+    let cells_name = format_ident!("{}Cells", name);
+    let field_names: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let field_vis = fields.iter().map(|field| &field.vis);
+    let field_types = fields.iter().map(|field| &field.ty);
+    let cells_doc = format!(
+        "One `StaticRefCell` per field of `{}`, generated by `#[derive(SplitIntoCells)]`",
+        name
+    );
+
+    Ok(quote! {
+        #[doc = #cells_doc]
+        #vis struct #cells_name {
+            #(#field_vis #field_names: #krate::StaticRefCell<#field_types>,)*
+        }
+
+        impl #cells_name {
+            /// Creates a new object with every cell uninitialized
+            pub const fn new() -> Self {
+                Self {
+                    #(#field_names: #krate::StaticRefCell::new(),)*
+                }
+            }
+
+            /// Moves each field of `value` into its own cell
+            pub fn split_into_statics(
+                &self,
+                cs: #krate::__private::critical_section::CriticalSection,
+                value: #name,
+            ) {
+                #(self.#field_names.init(cs, value.#field_names);)*
+            }
+        }
+
+        impl ::core::default::Default for #cells_name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    })
+}
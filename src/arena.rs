@@ -0,0 +1,146 @@
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+/// Refers to a value inserted in an `Arena`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArenaHandle(usize);
+
+impl ArenaHandle {
+    /// Returns the position of the value in insertion order
+    pub const fn index(self) -> usize {
+        self.0
+    }
+}
+
+struct Slots<T, const N: usize> {
+    values: [Option<T>; N],
+    len: usize,
+}
+
+/// A static registry of up to `N` values, such as the state of each instance of a driver, addressed
+/// by the handle returned when each one is inserted
+///
+/// Values are never removed, so handles stay valid for as long as the arena exists, and can be
+/// stored in the driver instances (or handed to ISRs) instead of declaring one static per
+/// instance.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::Arena;
+/// #
+/// struct Channel {
+///     pin: u8,
+///     pulses: u32,
+/// }
+///
+/// static CHANNELS: Arena<Channel, 4> = Arena::new();
+///
+/// let left = critical_section::with(|cs| CHANNELS.insert(cs, Channel { pin: 2, pulses: 0 }));
+/// let left = left.ok().unwrap();
+///
+/// // in the pin-change ISR
+/// critical_section::with(|cs| CHANNELS.with_mut(cs, left, |channel| channel.pulses += 1));
+///
+/// // in the main loop
+/// critical_section::with(|cs| {
+///     assert_eq!(CHANNELS.with(cs, left, |channel| (channel.pin, channel.pulses)), (2, 1));
+/// });
+/// ```
+pub struct Arena<T, const N: usize>(Mutex<RefCell<Slots<T, N>>>);
+
+impl<T, const N: usize> Arena<T, N> {
+    /// Creates a new empty arena
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(Slots {
+            values: [const { None }; N],
+            len: 0,
+        })))
+    }
+
+    /// Returns the maximum number of values this arena can hold, `N`
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Adds `value` to the arena and returns its handle, or returns the value back if the arena is
+    /// full
+    pub fn insert(&self, cs: CriticalSection, value: T) -> Result<ArenaHandle, T> {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        let index = slots.len;
+        match slots.values.get_mut(index) {
+            Some(slot) => {
+                *slot = Some(value);
+                slots.len += 1;
+                Ok(ArenaHandle(index))
+            }
+            None => Err(value),
+        }
+    }
+
+    /// Returns the number of values in the arena
+    pub fn len(&self, cs: CriticalSection) -> usize {
+        self.0.borrow_ref(cs).len
+    }
+
+    /// Returns true if no values were inserted
+    pub fn is_empty(&self, cs: CriticalSection) -> bool {
+        self.len(cs) == 0
+    }
+
+    /// Passes an immutable reference to the value referred to by `handle` in `func` and returns
+    /// the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena is currently mutably borrowed, or if `handle` came from another arena
+    /// and doesn't refer to a value in this one.
+    pub fn with<Y, F: FnOnce(&T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        handle: ArenaHandle,
+        func: F,
+    ) -> Y {
+        let slots = self.0.borrow_ref(cs);
+        func(slots.values[..slots.len][handle.0].as_ref().unwrap())
+    }
+
+    /// Passes a mutable reference to the value referred to by `handle` in `func` and returns the
+    /// result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena is currently borrowed, or if `handle` came from another arena and
+    /// doesn't refer to a value in this one.
+    pub fn with_mut<Y, F: FnOnce(&mut T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        handle: ArenaHandle,
+        func: F,
+    ) -> Y {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        let len = slots.len;
+        func(slots.values[..len][handle.0].as_mut().unwrap())
+    }
+
+    /// Calls `func` with the handle of and a mutable reference to each value, in insertion order
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena is currently borrowed.
+    pub fn for_each_mut<F: FnMut(ArenaHandle, &mut T)>(&self, cs: CriticalSection, mut func: F) {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        let len = slots.len;
+        for (index, value) in slots.values[..len].iter_mut().enumerate() {
+            if let Some(value) = value {
+                func(ArenaHandle(index), value);
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Default for Arena<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
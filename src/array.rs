@@ -0,0 +1,207 @@
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+/// A fixed-size array of individually initialized and borrowed values in one static
+///
+/// Each element behaves like its own `StaticRefCell`, so an array of per-channel states can be
+/// accessed element-wise (for example, borrowing one channel while iterating over the others)
+/// without declaring `N` separate statics.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::StaticRefCellArray;
+/// #
+/// static CHANNELS: StaticRefCellArray<u16, 4> = StaticRefCellArray::new();
+///
+/// critical_section::with(|cs| {
+///     CHANNELS.init_index(cs, 0, 100);
+///     CHANNELS.init_index(cs, 2, 300);
+///     CHANNELS.borrow_index_mut(cs, 2, |level| *level += 1, || {});
+///
+///     assert_eq!(CHANNELS.borrow_index(cs, 2, |level| *level, || 0), 301);
+///     assert_eq!(CHANNELS.borrow_index(cs, 1, |level| *level, || 0), 0);
+///
+///     let mut total = 0;
+///     CHANNELS.for_each(cs, |_, level| total += level);
+///     assert_eq!(total, 401);
+/// });
+/// ```
+pub struct StaticRefCellArray<T, const N: usize>(Mutex<[RefCell<Option<T>>; N]>);
+
+impl<T, const N: usize> StaticRefCellArray<T, N> {
+    /// Creates a new object with every element uninitialized (stored value as None)
+    pub const fn new() -> Self {
+        Self(Mutex::new([const { RefCell::new(None) }; N]))
+    }
+
+    /// Returns the number of elements, `N`
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if `N` is zero
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Sets the stored value of the element at `index`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds, or if the element is currently borrowed.
+    pub fn init_index(&self, cs: CriticalSection, index: usize, value: T) {
+        *self.0.borrow(cs)[index].borrow_mut() = Some(value);
+    }
+
+    /// Moves the stored value of the element at `index` out, leaving it uninitialized
+    ///
+    /// Returns None if the element was not initialized or `index` is out of bounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element is currently borrowed.
+    pub fn take_index(&self, cs: CriticalSection, index: usize) -> Option<T> {
+        self.0.borrow(cs).get(index)?.borrow_mut().take()
+    }
+
+    /// Returns true if the element at `index` is initialized
+    ///
+    /// Returns false if `index` is out of bounds. If the element is currently mutably borrowed,
+    /// it is initialized, so this returns true.
+    pub fn is_initialized_index(&self, cs: CriticalSection, index: usize) -> bool {
+        match self.0.borrow(cs).get(index) {
+            Some(element) => element.try_borrow().map_or(true, |value| value.is_some()),
+            None => false,
+        }
+    }
+
+    /// Passes an immutable reference to the element at `index` in `func` and returns the result,
+    /// or returns the result of `none_func` if the element is uninitialized or `index` is out of
+    /// bounds
+    ///
+    /// The element is no longer borrowed when `none_func` is called, so it can initialize it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element is currently mutably borrowed.
+    pub fn borrow_index<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        index: usize,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        if let Some(element) = self.0.borrow(cs).get(index) {
+            if let Some(value) = element.borrow().as_ref() {
+                return func(value);
+            }
+        }
+        none_func()
+    }
+
+    /// Passes a mutable reference to the element at `index` in `func` and returns the result, or
+    /// returns the result of `none_func` if the element is uninitialized or `index` is out of
+    /// bounds
+    ///
+    /// The element is no longer borrowed when `none_func` is called, so it can initialize it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element is currently borrowed.
+    pub fn borrow_index_mut<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        index: usize,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        if let Some(element) = self.0.borrow(cs).get(index) {
+            if let Some(value) = element.borrow_mut().as_mut() {
+                return func(value);
+            }
+        }
+        none_func()
+    }
+
+    /// Calls `func` with the index and an immutable reference to each initialized element, in order
+    ///
+    /// Each element is only borrowed while `func` is called for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an initialized element is currently mutably borrowed.
+    pub fn for_each<F: FnMut(usize, &T)>(&self, cs: CriticalSection, mut func: F) {
+        for (index, element) in self.0.borrow(cs).iter().enumerate() {
+            if let Some(value) = element.borrow().as_ref() {
+                func(index, value);
+            }
+        }
+    }
+
+    /// Calls `func` with the index and a mutable reference to each initialized element, in order
+    ///
+    /// Each element is only borrowed while `func` is called for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an initialized element is currently borrowed.
+    pub fn for_each_mut<F: FnMut(usize, &mut T)>(&self, cs: CriticalSection, mut func: F) {
+        for (index, element) in self.0.borrow(cs).iter().enumerate() {
+            if let Some(value) = element.borrow_mut().as_mut() {
+                func(index, value);
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Default for StaticRefCellArray<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn none_func_can_init_index() {
+        let array: StaticRefCellArray<u8, 2> = StaticRefCellArray::new();
+        critical_section::with(|cs| {
+            array.borrow_index_mut(cs, 1, |_| {}, || array.init_index(cs, 1, 7));
+            assert_eq!(array.borrow_index(cs, 1, |value| *value, || 0), 7);
+            array.borrow_index(cs, 0, |_| {}, || array.init_index(cs, 0, 3));
+            assert_eq!(array.borrow_index(cs, 0, |value| *value, || 0), 3);
+        });
+    }
+
+    #[test]
+    fn mutably_borrowed_element_is_initialized() {
+        let array: StaticRefCellArray<u8, 1> = StaticRefCellArray::new();
+        critical_section::with(|cs| {
+            array.init_index(cs, 0, 1);
+            array.borrow_index_mut(
+                cs,
+                0,
+                |_| assert!(array.is_initialized_index(cs, 0)),
+                || panic!(),
+            );
+        });
+    }
+
+    #[test]
+    fn none_func_sees_element_uninitialized() {
+        let array: StaticRefCellArray<u8, 1> = StaticRefCellArray::new();
+        critical_section::with(|cs| {
+            array.borrow_index_mut(
+                cs,
+                0,
+                |_| {},
+                || assert!(!array.is_initialized_index(cs, 0)),
+            );
+            array.init_index(cs, 0, 1);
+            assert!(array.is_initialized_index(cs, 0));
+        });
+    }
+}
@@ -0,0 +1,497 @@
+//! Pluggable locking strategies for `StaticRefCell`
+//!
+//! By default, a `StaticRefCell` locks by entering a global critical section, which is what its
+//! `CriticalSection` tokens prove. Its backend `M` can instead mask a single interrupt or claim a
+//! hardware spinlock on a dual-core part, chosen in the type of the static rather than at every
+//! call site.
+//!
+//! Because a backend other than `CriticalSectionBackend` doesn't produce `CriticalSection`
+//! tokens, a cell using one has token-free methods that take the lock themselves instead, and the
+//! `hooks`, `debug-borrows`, `instrument`, `profile`, `poison`, `dirty`, and `async` features
+//! only apply to cells using critical sections. Code written against `Shared` works with either.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::backend::{CriticalSectionBackend, IrqMaskBackend};
+//! use embedded_static_ref_cell::{Shared, StaticRefCell};
+//!
+//! // the backend defaults to `CriticalSectionBackend`
+//! static TICKS: StaticRefCell<u32, CriticalSectionBackend> = StaticRefCell::new_with(0);
+//!
+//! fn tick(ticks: &impl Shared<u32>) {
+//!     ticks.with_mut(|ticks| *ticks += 1);
+//! }
+//!
+//! tick(&TICKS);
+//! assert_eq!(critical_section::with(|cs| TICKS.get(cs)), Some(1));
+//!
+//! // stands in for an interrupt mask register
+//! let mut mask_register: u8 = 0b0000_0001;
+//! let register = core::ptr::addr_of_mut!(mask_register);
+//!
+//! // SAFETY: the cell is only shared with the ISR enabled by bit 0
+//! let masked = StaticRefCell::new_with_backend(0u32, unsafe { IrqMaskBackend::new(register, 1) });
+//! tick(&masked);
+//! assert_eq!(masked.get(), Some(1));
+//! ```
+
+use crate::{BorrowError, Shared, StaticRefCell};
+use core::cell::RefCell;
+#[cfg(feature = "cortex-m")]
+use core::sync::atomic::{compiler_fence, Ordering};
+#[cfg(feature = "rp2040")]
+use core::sync::atomic::{fence, AtomicU8};
+use critical_section::CriticalSection;
+
+/// A way of locking that excludes every other context that could access the same cell
+///
+/// # Safety
+///
+/// While `lock` runs `func`, no other context (ISR, core, or thread) that could lock the same
+/// backend may run. Nested calls to `lock` from within `func` must be allowed; the cell detects
+/// conflicting borrows from them on its own.
+pub unsafe trait RawMutexBackend {
+    /// Runs `func` with the lock held and returns its result
+    fn lock<R>(&self, func: impl FnOnce() -> R) -> R;
+}
+
+/// A `RawMutexBackend` other than `CriticalSectionBackend`, whose cells take the lock in each
+/// method instead of taking a `CriticalSection` token
+///
+/// Implementing this for a backend gives the `StaticRefCell`s using it the token-free methods of
+/// this module.
+pub trait LockBackend: RawMutexBackend {}
+
+/// Locks by entering a global critical section, the default backend of `StaticRefCell`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CriticalSectionBackend;
+
+// SAFETY: a critical section excludes every other context, and critical sections nest
+unsafe impl RawMutexBackend for CriticalSectionBackend {
+    fn lock<R>(&self, func: impl FnOnce() -> R) -> R {
+        critical_section::with(|_| func())
+    }
+}
+
+impl<T, M: LockBackend> StaticRefCell<T, M> {
+    /// Runs `func` on the stored `Option` with the backend's lock held
+    fn locked<Y>(&self, func: impl FnOnce(&RefCell<Option<T>>) -> Y) -> Y {
+        self.backend.lock(|| {
+            // SAFETY: the backend's lock excludes every other context that could access this
+            // object, as a critical section would, and the token doesn't outlive the lock
+            let cs = unsafe { CriticalSection::new() };
+            func(self.data.borrow(cs))
+        })
+    }
+
+    /// Sets the stored value for this object
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored value is currently borrowed.
+    pub fn init(&self, value: T) {
+        self.locked(|data| *data.borrow_mut() = Some(value));
+    }
+
+    /// Returns true if the stored value for this object is not None
+    pub fn is_initialized(&self) -> bool {
+        self.locked(|data| data.borrow().is_some())
+    }
+
+    /// Moves the stored value out of this object, leaving it uninitialized (stored value as None)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored value is currently borrowed.
+    pub fn take(&self) -> Option<T> {
+        self.locked(|data| data.borrow_mut().take())
+    }
+
+    /// Sets the stored value for this object and returns the previously stored value, or None if
+    /// the object was not yet initialized
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored value is currently borrowed.
+    pub fn replace(&self, value: T) -> Option<T> {
+        self.locked(|data| data.borrow_mut().replace(value))
+    }
+
+    /// Passes an immutable reference to the stored data in `func` with the lock held and returns
+    /// the result, or returns the result of `none_func` if the stored data is still None
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently mutably borrowed.
+    pub fn borrow<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(&self, func: F1, none_func: F2) -> Y {
+        self.locked(|data| match data.borrow().as_ref() {
+            Some(value) => func(value),
+            None => none_func(),
+        })
+    }
+
+    /// Passes a mutable reference to the stored data in `func` with the lock held and returns the
+    /// result, or returns the result of `none_func` if the stored data is still None
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently borrowed.
+    pub fn borrow_mut<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        self.locked(|data| match data.borrow_mut().as_mut() {
+            Some(value) => func(value),
+            None => none_func(),
+        })
+    }
+
+    /// Passes an immutable reference to the stored data in `func` with the lock held, returning
+    /// the result, or None if the stored data is still None
+    pub fn with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Option<Y> {
+        self.borrow(|value| Some(func(value)), || None)
+    }
+
+    /// Passes a mutable reference to the stored data in `func` with the lock held, returning the
+    /// result, or None if the stored data is still None
+    pub fn with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Option<Y> {
+        self.borrow_mut(|value| Some(func(value)), || None)
+    }
+}
+
+impl<T, M: LockBackend> Shared<T> for StaticRefCell<T, M> {
+    fn try_with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        self.locked(|data| {
+            let stored = data
+                .try_borrow()
+                .map_err(|_| BorrowError::AlreadyBorrowed)?;
+            stored.as_ref().map(func).ok_or(BorrowError::Uninitialized)
+        })
+    }
+
+    fn try_with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        self.locked(|data| {
+            let mut stored = data
+                .try_borrow_mut()
+                .map_err(|_| BorrowError::AlreadyBorrowed)?;
+            stored.as_mut().map(func).ok_or(BorrowError::Uninitialized)
+        })
+    }
+
+    fn with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Option<Y> {
+        StaticRefCell::<T, M>::with(self, func)
+    }
+
+    fn with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Option<Y> {
+        StaticRefCell::<T, M>::with_mut(self, func)
+    }
+}
+
+impl<T: Copy, M: LockBackend> StaticRefCell<T, M> {
+    /// Returns a copy of the stored value, or None if the stored value is still None
+    pub fn get(&self) -> Option<T> {
+        self.borrow(|value| Some(*value), || None)
+    }
+
+    /// Sets the stored value for this object
+    pub fn set(&self, value: T) {
+        self.init(value);
+    }
+}
+
+/// Locks by clearing interrupt enable bits in a memory-mapped register, masking only the
+/// interrupts that share a cell instead of all of them
+///
+/// This suits a cell shared only between the main program and one ISR (such as a timer ISR), so
+/// unrelated ISRs keep their latency while the cell is locked. The register is briefly updated
+/// inside a critical section, since other code may modify its other bits, but the cell itself is
+/// then accessed with only the given interrupts masked. Unlocking restores the bits that were set
+/// before, so locks nest, and a pending interrupt fires as soon as it is unmasked.
+///
+/// # Examples
+///
+/// ```
+/// use embedded_static_ref_cell::backend::IrqMaskBackend;
+/// use embedded_static_ref_cell::StaticRefCell;
+///
+/// // stands in for an interrupt mask register, such as TIMSK1 on an ATmega328P
+/// let mut timsk1: u8 = 0b0000_0011;
+/// let register = core::ptr::addr_of_mut!(timsk1);
+///
+/// // SAFETY: the cell is only shared with the ISR enabled by bit 1
+/// let cell = StaticRefCell::with_backend(unsafe { IrqMaskBackend::new(register, 0b0000_0010) });
+/// cell.init(0u16);
+///
+/// cell.borrow_mut(
+///     |count| {
+///         // only the shared ISR is masked while the cell is locked
+///         assert_eq!(unsafe { register.read_volatile() }, 0b0000_0001);
+///         *count += 1;
+///     },
+///     || {},
+/// );
+/// assert_eq!(unsafe { register.read_volatile() }, 0b0000_0011);
+/// ```
+pub struct IrqMaskBackend {
+    register: *mut u8,
+    mask: u8,
+}
+
+// SAFETY: the register is only accessed with volatile operations inside critical sections
+unsafe impl Send for IrqMaskBackend {}
+unsafe impl Sync for IrqMaskBackend {}
+
+/// Restores the masked interrupt enable bits when dropped, including when the locked closure
+/// panics
+struct Unmask<'a> {
+    backend: &'a IrqMaskBackend,
+    enabled: u8,
+}
+
+impl Drop for Unmask<'_> {
+    fn drop(&mut self) {
+        let register = self.backend.register;
+        // SAFETY: the creator of the backend guarantees that the register is valid
+        critical_section::with(|_| unsafe {
+            register.write_volatile(register.read_volatile() | self.enabled)
+        });
+    }
+}
+
+impl IrqMaskBackend {
+    /// Creates a backend that masks the interrupts enabled by the bits in `mask` of the 8-bit
+    /// enable register at `register`
+    ///
+    /// # Safety
+    ///
+    /// `register` must be valid for volatile reads and writes for as long as the backend is used,
+    /// and clearing the bits in `mask` must stop the interrupts they enable from firing. The cells
+    /// using this backend may only be accessed by the main program and by the ISRs of those
+    /// interrupts, and those ISRs must not preempt each other.
+    pub const unsafe fn new(register: *mut u8, mask: u8) -> Self {
+        Self { register, mask }
+    }
+}
+
+// SAFETY: while the lock is held, the only contexts that may access the cell are masked (as
+// guaranteed by the creator of the backend), and the critical sections around the register
+// updates act as compiler fences for the accesses in between
+unsafe impl RawMutexBackend for IrqMaskBackend {
+    fn lock<R>(&self, func: impl FnOnce() -> R) -> R {
+        let register = self.register;
+        // SAFETY: the creator of the backend guarantees that the register is valid
+        let enabled = critical_section::with(|_| unsafe {
+            let value = register.read_volatile();
+            register.write_volatile(value & !self.mask);
+            value & self.mask
+        });
+        let _unmask = Unmask {
+            backend: self,
+            enabled,
+        };
+        func()
+    }
+}
+
+impl LockBackend for IrqMaskBackend {}
+
+/// Locks by masking one interrupt in the NVIC of an ARM Cortex-M core
+///
+/// This is the NVIC counterpart of `IrqMaskBackend`, and requires the `cortex-m` feature.
+#[cfg(feature = "cortex-m")]
+pub struct NvicMaskBackend<I> {
+    interrupt: I,
+}
+
+#[cfg(feature = "cortex-m")]
+impl<I: cortex_m::interrupt::InterruptNumber> NvicMaskBackend<I> {
+    /// Creates a backend that masks `interrupt`
+    ///
+    /// # Safety
+    ///
+    /// The cells using this backend may only be accessed by the main program and by the ISR of
+    /// `interrupt`.
+    pub const unsafe fn new(interrupt: I) -> Self {
+        Self { interrupt }
+    }
+}
+
+// SAFETY: while the lock is held, the only ISR that may access the cell is masked (as guaranteed
+// by the creator of the backend), and it is only unmasked again if it was enabled before
+#[cfg(feature = "cortex-m")]
+unsafe impl<I: cortex_m::interrupt::InterruptNumber> RawMutexBackend for NvicMaskBackend<I> {
+    fn lock<R>(&self, func: impl FnOnce() -> R) -> R {
+        use cortex_m::peripheral::NVIC;
+
+        let enabled = NVIC::is_enabled(self.interrupt);
+        NVIC::mask(self.interrupt);
+        cortex_m::asm::dsb();
+        cortex_m::asm::isb();
+        let result = func();
+        // keep the accesses made by `func` from moving past the unmasking
+        compiler_fence(Ordering::SeqCst);
+        if enabled {
+            // SAFETY: the interrupt was enabled before it was masked above
+            unsafe { NVIC::unmask(self.interrupt) };
+        }
+        result
+    }
+}
+
+#[cfg(feature = "cortex-m")]
+impl<I: cortex_m::interrupt::InterruptNumber> LockBackend for NvicMaskBackend<I> {}
+
+/// Locks by raising BASEPRI to a priority ceiling on an ARM Cortex-M core
+///
+/// Instead of disabling every interrupt, this only masks the interrupts whose priority is at or
+/// below the ceiling, so higher-priority ISRs that don't use the cell keep running while it is
+/// locked (the priority ceiling protocol). BASEPRI is only raised, never lowered, so locks nest.
+///
+/// This requires the `cortex-m` feature and a core with BASEPRI (ARMv7-M or ARMv8-M Mainline,
+/// such as a Cortex-M3, M4, M7, or M33).
+#[cfg(all(feature = "cortex-m", target_arch = "arm", target_feature = "v7"))]
+pub struct BasepriBackend {
+    ceiling: u8,
+}
+
+#[cfg(all(feature = "cortex-m", target_arch = "arm", target_feature = "v7"))]
+impl BasepriBackend {
+    /// Creates a backend that raises BASEPRI to `ceiling`
+    ///
+    /// `ceiling` is in the hardware encoding that BASEPRI uses, where the priority sits in the
+    /// upper bits (so with 4 priority bits, logical priority 2 is `2 << 4`), and lower values mean
+    /// higher priorities.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ceiling` is zero, which would leave BASEPRI disabled.
+    ///
+    /// # Safety
+    ///
+    /// Every ISR that accesses the cells using this backend must have a priority at or below
+    /// `ceiling` (a numerically equal or greater priority value), so it is masked while a cell is
+    /// locked.
+    pub const unsafe fn new(ceiling: u8) -> Self {
+        assert!(ceiling != 0, "a BASEPRI ceiling of zero masks nothing");
+        Self { ceiling }
+    }
+
+    /// Returns the ceiling, in the hardware encoding
+    pub const fn ceiling(&self) -> u8 {
+        self.ceiling
+    }
+}
+
+// SAFETY: while the lock is held, every ISR that may access the cell is masked by BASEPRI (as
+// guaranteed by the creator of the backend), and the previous BASEPRI is only restored afterwards
+#[cfg(all(feature = "cortex-m", target_arch = "arm", target_feature = "v7"))]
+unsafe impl RawMutexBackend for BasepriBackend {
+    fn lock<R>(&self, func: impl FnOnce() -> R) -> R {
+        use cortex_m::register::{basepri, basepri_max};
+
+        let previous = basepri::read();
+        basepri_max::write(self.ceiling);
+        // the register accesses don't order memory accesses on their own
+        compiler_fence(Ordering::SeqCst);
+        let result = func();
+        compiler_fence(Ordering::SeqCst);
+        // SAFETY: this restores the value read above, ending this lock only
+        unsafe { basepri::write(previous) };
+        result
+    }
+}
+
+#[cfg(all(feature = "cortex-m", target_arch = "arm", target_feature = "v7"))]
+impl LockBackend for BasepriBackend {}
+
+/// Locks with an RP2040/RP2350 hardware spinlock plus interrupt masking, so cells can be shared
+/// between both cores
+///
+/// Locking disables interrupts on the current core and then claims the spinlock, so neither the
+/// other core nor an ISR on this core can access the cell. Nested locks on the same core are
+/// detected and don't touch the spinlock again.
+///
+/// This requires the `rp2040` feature.
+#[cfg(feature = "rp2040")]
+pub struct SpinlockBackend {
+    spinlock: *mut u32,
+    /// The core holding the spinlock, or `NO_OWNER`
+    owner: AtomicU8,
+}
+
+/// The SIO register block, shared by RP2040 and RP2350
+#[cfg(feature = "rp2040")]
+const SIO_BASE: usize = 0xd000_0000;
+
+/// The value of `SpinlockBackend::owner` while the spinlock is free
+#[cfg(feature = "rp2040")]
+const NO_OWNER: u8 = u8::MAX;
+
+// SAFETY: the spinlock register is only accessed with volatile operations, and the hardware
+// arbitrates between the cores
+#[cfg(feature = "rp2040")]
+unsafe impl Send for SpinlockBackend {}
+#[cfg(feature = "rp2040")]
+unsafe impl Sync for SpinlockBackend {}
+
+#[cfg(feature = "rp2040")]
+impl SpinlockBackend {
+    /// Creates a backend that uses hardware spinlock `index` (0 to 31)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below 32.
+    ///
+    /// # Safety
+    ///
+    /// Nothing else may use the spinlock, including a HAL's `critical-section` implementation
+    /// (rp2040-hal uses spinlock 31, for example), and this must only run on an RP2040 or RP2350.
+    pub const unsafe fn new(index: u8) -> Self {
+        assert!(index < 32, "there are only 32 hardware spinlocks");
+        Self {
+            spinlock: (SIO_BASE + 0x100 + 4 * index as usize) as *mut u32,
+            owner: AtomicU8::new(NO_OWNER),
+        }
+    }
+}
+
+// SAFETY: while the lock is held, interrupts are disabled on this core and the other core is
+// kept out by the hardware spinlock, which is only released after the accesses made by `func`
+#[cfg(feature = "rp2040")]
+unsafe impl RawMutexBackend for SpinlockBackend {
+    fn lock<R>(&self, func: impl FnOnce() -> R) -> R {
+        // SAFETY: the creator of the backend guarantees that this runs on an RP2040 or RP2350,
+        // where the CPUID register is at the start of the SIO block
+        let core = unsafe { (SIO_BASE as *const u32).read_volatile() } as u8;
+        if self.owner.load(Ordering::Relaxed) == core {
+            // this core already holds the lock, with interrupts disabled
+            return func();
+        }
+
+        let interrupts = cortex_m::register::primask::read();
+        cortex_m::interrupt::disable();
+        // SAFETY: reading the spinlock register claims it, returning zero if it is already claimed
+        while unsafe { self.spinlock.read_volatile() } == 0 {
+            core::hint::spin_loop();
+        }
+        fence(Ordering::SeqCst);
+        self.owner.store(core, Ordering::Relaxed);
+
+        let result = func();
+
+        self.owner.store(NO_OWNER, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        // SAFETY: writing any value releases the spinlock claimed above
+        unsafe { self.spinlock.write_volatile(1) };
+        if interrupts.is_active() {
+            // SAFETY: interrupts were enabled before this lock disabled them
+            unsafe { cortex_m::interrupt::enable() };
+        }
+        result
+    }
+}
+
+#[cfg(feature = "rp2040")]
+impl LockBackend for SpinlockBackend {}
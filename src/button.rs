@@ -0,0 +1,187 @@
+use crate::spsc::Queue;
+use core::cell::Cell;
+use critical_section::{CriticalSection, Mutex};
+
+/// A debounced event of a button, returned by `ButtonEvents::poll`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// The button was pressed
+    Press,
+    /// The button was released
+    Release,
+    /// The button has been held down for the long-press time
+    LongPress,
+    /// The button was released before the long-press time, following its `Release`
+    Click,
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    /// The debounced level
+    pressed: bool,
+    /// The last raw edge, until it has been stable for the debounce time
+    pending: Option<(bool, u32)>,
+    /// When the current press started
+    pressed_at: u32,
+    long_reported: bool,
+    click: bool,
+}
+
+/// Turns the raw edges of a button, queued by a pin-change ISR, into debounced press, release,
+/// long-press, and click events for the main loop
+///
+/// The ISR only queues each edge with the time it happened (from any free-running tick counter,
+/// which may wrap around), so it stays short. The main loop calls `poll` with the current time,
+/// which returns the next event, if any: a new level only counts once it has been stable for the
+/// debounce time, and a press held for the long-press time is reported once as a `LongPress`. A
+/// release ends with a `Click` if no `LongPress` was reported for that press.
+///
+/// Up to `N` raw edges can be queued between polls, which must also cover the bouncing of the
+/// contacts. Edges that don't fit are dropped, which could leave the debounced level stale until
+/// the next edge.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{ButtonEvent, ButtonEvents};
+/// #
+/// // 20 ms debounce, 500 ms long press, with a millisecond tick
+/// static BUTTON: ButtonEvents<8> = ButtonEvents::new(20, 500);
+///
+/// // in the pin-change ISR: the contacts bounce when pressed at 100 ms, released at 300 ms
+/// critical_section::with(|cs| {
+///     for (pressed, ms) in [(true, 100), (false, 102), (true, 104), (false, 300)] {
+///         BUTTON.edge(cs, pressed, ms);
+///     }
+/// });
+///
+/// // in the main loop
+/// critical_section::with(|cs| {
+///     assert_eq!(BUTTON.poll(cs, 400), Some(ButtonEvent::Press));
+///     assert_eq!(BUTTON.poll(cs, 400), Some(ButtonEvent::Release));
+///     assert_eq!(BUTTON.poll(cs, 400), Some(ButtonEvent::Click));
+///     assert_eq!(BUTTON.poll(cs, 400), None);
+/// });
+/// ```
+pub struct ButtonEvents<const N: usize> {
+    edges: Queue<(bool, u32), N>,
+    state: Mutex<Cell<State>>,
+    debounce: u32,
+    long_press: u32,
+}
+
+impl<const N: usize> ButtonEvents<N> {
+    /// Creates a new object for a released button, with the given debounce and long-press times
+    /// in ticks
+    pub const fn new(debounce: u32, long_press: u32) -> Self {
+        Self {
+            edges: Queue::new(),
+            state: Mutex::new(Cell::new(State {
+                pressed: false,
+                pending: None,
+                pressed_at: 0,
+                long_reported: false,
+                click: false,
+            })),
+            debounce,
+            long_press,
+        }
+    }
+
+    /// Queues a raw edge to the `pressed` level at `timestamp`, returning false if the queue is
+    /// full and the edge was dropped
+    pub fn edge(&self, cs: CriticalSection, pressed: bool, timestamp: u32) -> bool {
+        self.edges.enqueue(cs, (pressed, timestamp)).is_ok()
+    }
+
+    /// Returns true if the debounced level is pressed
+    pub fn is_pressed(&self, cs: CriticalSection) -> bool {
+        self.state.borrow(cs).get().pressed
+    }
+
+    /// Moves the debounced level to `pressed` at `timestamp`, returning the resulting event
+    fn settle(&self, state: &mut State, pressed: bool, timestamp: u32) -> Option<ButtonEvent> {
+        if pressed == state.pressed {
+            return None;
+        }
+        state.pressed = pressed;
+        if pressed {
+            state.pressed_at = timestamp;
+            state.long_reported = false;
+            Some(ButtonEvent::Press)
+        } else {
+            state.click = !state.long_reported;
+            Some(ButtonEvent::Release)
+        }
+    }
+
+    /// Processes the queued edges up to the time `now`, and returns the next event, if any
+    ///
+    /// Events are returned one per call, so this should be called until it returns None.
+    pub fn poll(&self, cs: CriticalSection, now: u32) -> Option<ButtonEvent> {
+        let cell = self.state.borrow(cs);
+        let mut state = cell.get();
+        let event = self.next_event(cs, &mut state, now);
+        cell.set(state);
+        event
+    }
+
+    fn next_event(&self, cs: CriticalSection, state: &mut State, now: u32) -> Option<ButtonEvent> {
+        if core::mem::take(&mut state.click) {
+            return Some(ButtonEvent::Click);
+        }
+        while let Some((pressed, timestamp)) = self.edges.peek(cs, |edge| Some(*edge), || None) {
+            if let Some((level, since)) = state.pending {
+                // the pending level was stable until this edge
+                if timestamp.wrapping_sub(since) >= self.debounce {
+                    state.pending = None;
+                    if let Some(event) = self.settle(state, level, since) {
+                        return Some(event);
+                    }
+                }
+            }
+            self.edges.dequeue(cs);
+            state.pending = Some((pressed, timestamp));
+        }
+        if let Some((level, since)) = state.pending {
+            if now.wrapping_sub(since) >= self.debounce {
+                state.pending = None;
+                if let Some(event) = self.settle(state, level, since) {
+                    return Some(event);
+                }
+            }
+        }
+        if state.pressed
+            && !state.long_reported
+            && now.wrapping_sub(state.pressed_at) >= self.long_press
+        {
+            state.long_reported = true;
+            return Some(ButtonEvent::LongPress);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_press_suppresses_click() {
+        critical_section::with(|cs| {
+            let button: ButtonEvents<4> = ButtonEvents::new(10, 100);
+            button.edge(cs, true, 0);
+            // still bouncing
+            assert_eq!(button.poll(cs, 5), None);
+            assert_eq!(button.poll(cs, 10), Some(ButtonEvent::Press));
+            assert_eq!(button.poll(cs, 50), None);
+            assert_eq!(button.poll(cs, 100), Some(ButtonEvent::LongPress));
+            assert_eq!(button.poll(cs, 200), None);
+            assert!(button.is_pressed(cs));
+
+            button.edge(cs, false, 250);
+            assert_eq!(button.poll(cs, 260), Some(ButtonEvent::Release));
+            assert_eq!(button.poll(cs, 260), None);
+        });
+    }
+}
@@ -0,0 +1,128 @@
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+use portable_atomic::{AtomicU8, Ordering};
+
+/// A cell for rarely changing `Copy` data, whose readers keep a cached copy of it
+///
+/// Every change bumps a one-byte generation counter, which is read atomically without a critical
+/// section. A `CachedReader` (usually kept by the main loop) returns its cached copy as long as the
+/// generation matches the one it copied at, and only enters a critical section to refresh the
+/// copy once the value has changed. This keeps the hot read path of configuration-like data free
+/// of critical sections.
+///
+/// The counter wraps after 256 changes, so a reader could miss a change if exactly a multiple of
+/// 256 changes happened between two of its reads.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::CachedCell;
+/// #
+/// static CONFIG: CachedCell<[u16; 2]> = CachedCell::new([100, 200]);
+///
+/// let mut config = CONFIG.reader();
+/// assert_eq!(config.read(), [100, 200]);
+///
+/// // in the command ISR
+/// critical_section::with(|cs| CONFIG.with_mut(cs, |config| config[1] = 250));
+///
+/// // in the main loop, only this read enters a critical section again
+/// assert_eq!(config.read(), [100, 250]);
+/// assert_eq!(config.read(), [100, 250]);
+/// ```
+pub struct CachedCell<T: Copy> {
+    value: Mutex<RefCell<T>>,
+    /// The number of changes to the value (wrapping)
+    generation: AtomicU8,
+}
+
+impl<T: Copy> CachedCell<T> {
+    /// Creates a new object holding `value`
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: Mutex::new(RefCell::new(value)),
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    /// Returns a new reader, which copies the value on its first read
+    pub fn reader(&self) -> CachedReader<'_, T> {
+        CachedReader {
+            cell: self,
+            cached: None,
+        }
+    }
+
+    /// Returns a copy of the stored value
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn get(&self, cs: CriticalSection) -> T {
+        *self.value.borrow_ref(cs)
+    }
+
+    /// Sets the stored value
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn set(&self, cs: CriticalSection, value: T) {
+        self.with_mut(cs, |stored| *stored = value);
+    }
+
+    /// Passes a mutable reference to the stored value in `func` and returns the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, cs: CriticalSection, func: F) -> Y {
+        let result = func(&mut self.value.borrow_ref_mut(cs));
+        // writers are serialized by the critical section, so this doesn't need to be atomic
+        let generation = self.generation.load(Ordering::Relaxed).wrapping_add(1);
+        self.generation.store(generation, Ordering::Release);
+        result
+    }
+}
+
+impl<T: Copy + Default> Default for CachedCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// A cached copy of the value of a `CachedCell`
+pub struct CachedReader<'a, T: Copy> {
+    cell: &'a CachedCell<T>,
+    /// The copy, and the generation it was taken at
+    cached: Option<(u8, T)>,
+}
+
+impl<T: Copy> CachedReader<'_, T> {
+    /// Returns true if the value changed since the last read
+    pub fn is_stale(&self) -> bool {
+        match self.cached {
+            Some((generation, _)) => self.cell.generation.load(Ordering::Acquire) != generation,
+            None => true,
+        }
+    }
+
+    /// Returns the value, entering a critical section to refresh the cached copy only if the
+    /// value changed since the last read
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while the value is mutably borrowed (from a closure passed to
+    /// `CachedCell::with_mut`) and the copy must be refreshed.
+    pub fn read(&mut self) -> T {
+        match self.cached {
+            Some((_, value)) if !self.is_stale() => value,
+            _ => critical_section::with(|cs| {
+                let generation = self.cell.generation.load(Ordering::Relaxed);
+                let value = self.cell.get(cs);
+                self.cached = Some((generation, value));
+                value
+            }),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::Edge;
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+struct Ring<const N: usize> {
+    buffer: [(u32, Edge); N],
+    /// The index the next capture is written to
+    next: usize,
+    len: usize,
+}
+
+impl<const N: usize> Ring<N> {
+    /// Returns the captures from oldest to newest
+    fn iter(&self) -> impl Iterator<Item = (u32, Edge)> + '_ {
+        (0..self.len).map(move |age| self.buffer[(self.next + N - self.len + age) % N])
+    }
+}
+
+/// A buffer of the last `N` timestamps captured by a timer's input-capture ISR, from which the
+/// main loop computes the period, frequency, and pulse width of the signal
+///
+/// Timestamps come straight from the timer (or any free-running counter) and may wrap around,
+/// as long as the captures held span less than a full period of it. The results are averaged
+/// over every capture held, which smooths out jitter. For a timer that only captures one edge,
+/// every capture can be passed as `Edge::Rising`, in which case only the period is known.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{CaptureBuffer, Edge};
+/// #
+/// static TACHO: CaptureBuffer<8> = CaptureBuffer::new();
+///
+/// // in the input-capture ISR of a 1 MHz timer, for a 250 Hz signal that is high for 1 ms
+/// critical_section::with(|cs| {
+///     for cycle in 0..3 {
+///         TACHO.capture(cs, cycle * 4000, Edge::Rising);
+///         TACHO.capture(cs, cycle * 4000 + 1000, Edge::Falling);
+///     }
+/// });
+///
+/// // in the main loop
+/// critical_section::with(|cs| {
+///     assert_eq!(TACHO.period(cs), Some(4000));
+///     assert_eq!(TACHO.frequency(cs, 1_000_000), Some(250));
+///     assert_eq!(TACHO.pulse_width(cs), Some(1000));
+/// });
+/// ```
+pub struct CaptureBuffer<const N: usize>(Mutex<RefCell<Ring<N>>>);
+
+impl<const N: usize> CaptureBuffer<N> {
+    /// Creates a new object with no captures
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(Ring {
+            buffer: [(0, Edge::Rising); N],
+            next: 0,
+            len: 0,
+        })))
+    }
+
+    /// Records a capture of `edge` at `timestamp`, forgetting the oldest one if `N` are already
+    /// held
+    pub fn capture(&self, cs: CriticalSection, timestamp: u32, edge: Edge) {
+        if N == 0 {
+            return;
+        }
+        let mut ring = self.0.borrow_ref_mut(cs);
+        let next = ring.next;
+        ring.buffer[next] = (timestamp, edge);
+        ring.next = (next + 1) % N;
+        ring.len = (ring.len + 1).min(N);
+    }
+
+    /// Returns the number of captures held
+    pub fn len(&self, cs: CriticalSection) -> usize {
+        self.0.borrow_ref(cs).len
+    }
+
+    /// Returns true if there are no captures
+    pub fn is_empty(&self, cs: CriticalSection) -> bool {
+        self.len(cs) == 0
+    }
+
+    /// Forgets every capture, such as after the signal stopped
+    pub fn clear(&self, cs: CriticalSection) {
+        self.0.borrow_ref_mut(cs).len = 0;
+    }
+
+    /// Returns the number of periods between the oldest and newest rising edges held, and the
+    /// ticks they span
+    fn periods(&self, cs: CriticalSection) -> Option<(u32, u32)> {
+        let ring = self.0.borrow_ref(cs);
+        let mut rising = ring
+            .iter()
+            .filter(|&(_, edge)| edge == Edge::Rising)
+            .map(|(timestamp, _)| timestamp);
+        let first = rising.next()?;
+        let (count, last) = rising.fold((0, first), |(count, _), timestamp| (count + 1, timestamp));
+        (count > 0).then(|| (count, last.wrapping_sub(first)))
+    }
+
+    /// Returns the average time between rising edges in ticks, or None if fewer than two rising
+    /// edges are held
+    pub fn period(&self, cs: CriticalSection) -> Option<u32> {
+        self.periods(cs).map(|(count, span)| span / count)
+    }
+
+    /// Returns the frequency in Hz for a timer running at `tick_hz`, or None if fewer than two
+    /// rising edges are held (or they have the same timestamp), saturating at `u32::MAX`
+    pub fn frequency(&self, cs: CriticalSection, tick_hz: u32) -> Option<u32> {
+        let (count, span) = self.periods(cs)?;
+        if span == 0 {
+            return None;
+        }
+        let frequency = u64::from(tick_hz) * u64::from(count) / u64::from(span);
+        Some(u32::try_from(frequency).unwrap_or(u32::MAX))
+    }
+
+    /// Returns the average time from a rising edge to the following falling edge in ticks, or
+    /// None if no such pair is held
+    pub fn pulse_width(&self, cs: CriticalSection) -> Option<u32> {
+        let ring = self.0.borrow_ref(cs);
+        let mut rising = None;
+        let (mut count, mut total) = (0u64, 0u64);
+        for (timestamp, edge) in ring.iter() {
+            match edge {
+                Edge::Rising => rising = Some(timestamp),
+                Edge::Falling => {
+                    if let Some(start) = rising.take() {
+                        count += 1;
+                        total += u64::from(timestamp.wrapping_sub(start));
+                    }
+                }
+            }
+        }
+        (count > 0).then(|| (total / count) as u32)
+    }
+}
+
+impl<const N: usize> Default for CaptureBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_around_timer_and_buffer() {
+        critical_section::with(|cs| {
+            let buffer: CaptureBuffer<3> = CaptureBuffer::new();
+            assert_eq!(buffer.period(cs), None);
+            for timestamp in [0, 100, u32::MAX - 99, 44, 144] {
+                buffer.capture(cs, timestamp, Edge::Rising);
+            }
+            // the last three captures, across the overflow of the timer
+            assert_eq!(buffer.len(cs), 3);
+            assert_eq!(buffer.period(cs), Some(122));
+            assert_eq!(buffer.frequency(cs, 244_000), Some(2000));
+            assert_eq!(buffer.pulse_width(cs), None);
+        });
+    }
+}
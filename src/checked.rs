@@ -0,0 +1,182 @@
+use crate::pod::{bytes_of, crc32};
+use crate::Pod;
+use core::cell::RefCell;
+use core::fmt;
+use critical_section::{CriticalSection, Mutex};
+
+/// The error returned when a `CheckedCell` or `RedundantCell` finds that its data was corrupted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Corrupted;
+
+impl fmt::Display for Corrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("cell data failed its integrity check")
+    }
+}
+
+struct Checked<T> {
+    value: T,
+    crc: u32,
+}
+
+/// A cell for safety-relevant plain data that detects memory corruption
+///
+/// A CRC-32 of the value is stored alongside it, verified before every access and recomputed after
+/// every change, so a bit flipped in RAM (such as by a single-event upset) is caught the next
+/// time the value is used instead of going unnoticed. On a mismatch, the access returns
+/// `Corrupted` without running its closure, after calling the corruption handler (if one was set
+/// with `on_corruption`). The handler runs inside the critical section and can, for example,
+/// restore a safe value with `set` or trigger a fault reaction.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::CheckedCell;
+/// # use critical_section::CriticalSection;
+/// #
+/// fn restore_limit(cs: CriticalSection) {
+///     SPEED_LIMIT.set(cs, 0);
+/// }
+///
+/// static SPEED_LIMIT: CheckedCell<u16> = CheckedCell::new(80).on_corruption(restore_limit);
+///
+/// critical_section::with(|cs| {
+///     SPEED_LIMIT.with_mut(cs, |limit| *limit = 120).unwrap();
+///     assert_eq!(SPEED_LIMIT.get(cs), Ok(120));
+/// });
+/// ```
+pub struct CheckedCell<T: Pod> {
+    data: Mutex<RefCell<Checked<T>>>,
+    on_corruption: Option<fn(CriticalSection)>,
+}
+
+impl<T: Pod> CheckedCell<T> {
+    /// Creates a new object holding `value`, with no corruption handler
+    pub const fn new(value: T) -> Self {
+        Self {
+            data: Mutex::new(RefCell::new(Checked {
+                value,
+                crc: crc32(bytes_of(&value)),
+            })),
+            on_corruption: None,
+        }
+    }
+
+    /// Sets the function called (inside the critical section) when corruption is detected
+    pub const fn on_corruption(mut self, handler: fn(CriticalSection)) -> Self {
+        self.on_corruption = Some(handler);
+        self
+    }
+
+    /// Checks the stored value against its CRC, calling the corruption handler on a mismatch
+    ///
+    /// This can be called periodically to find corruption in values that are rarely accessed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn verify(&self, cs: CriticalSection) -> Result<(), Corrupted> {
+        let valid = {
+            let data = self.data.borrow_ref(cs);
+            data.crc == crc32(bytes_of(&data.value))
+        };
+        if valid {
+            return Ok(());
+        }
+        if let Some(handler) = self.on_corruption {
+            handler(cs);
+        }
+        Err(Corrupted)
+    }
+
+    /// Passes an immutable reference to the verified value in `func` and returns the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn with<Y, F: FnOnce(&T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, Corrupted> {
+        self.verify(cs)?;
+        Ok(func(&self.data.borrow_ref(cs).value))
+    }
+
+    /// Passes a mutable reference to the verified value in `func`, updates the CRC, and returns
+    /// the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn with_mut<Y, F: FnOnce(&mut T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, Corrupted> {
+        self.verify(cs)?;
+        let mut data = self.data.borrow_ref_mut(cs);
+        let result = func(&mut data.value);
+        data.crc = crc32(bytes_of(&data.value));
+        Ok(result)
+    }
+
+    /// Returns a copy of the verified value
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn get(&self, cs: CriticalSection) -> Result<T, Corrupted> {
+        self.with(cs, |value| *value)
+    }
+
+    /// Sets the stored value and its CRC, without checking the old value
+    ///
+    /// This is how a corrupted cell is recovered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn set(&self, cs: CriticalSection, value: T) {
+        *self.data.borrow_ref_mut(cs) = Checked {
+            value,
+            crc: crc32(bytes_of(&value)),
+        };
+    }
+}
+
+impl<T: Pod + Default> Default for CheckedCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static RECOVERIES: AtomicU32 = AtomicU32::new(0);
+
+    static CELL: CheckedCell<[u8; 4]> = CheckedCell::new([1, 2, 3, 4]).on_corruption(recover);
+
+    fn recover(cs: CriticalSection) {
+        RECOVERIES.fetch_add(1, Ordering::Relaxed);
+        CELL.set(cs, [0; 4]);
+    }
+
+    #[test]
+    fn corruption_calls_handler() {
+        critical_section::with(|cs| {
+            assert_eq!(CELL.get(cs), Ok([1, 2, 3, 4]));
+
+            // a single bit flips in RAM
+            CELL.data.borrow_ref_mut(cs).value[2] ^= 0x04;
+            assert_eq!(CELL.with_mut(cs, |value| value[0] = 9), Err(Corrupted));
+            assert_eq!(RECOVERIES.load(Ordering::Relaxed), 1);
+
+            // the handler restored a safe value
+            assert_eq!(CELL.get(cs), Ok([0; 4]));
+        });
+    }
+}
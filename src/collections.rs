@@ -0,0 +1,188 @@
+//! Shortcuts for cells holding `heapless` collections
+
+use crate::StaticRefCell;
+use critical_section::CriticalSection;
+use heapless::{Deque, String, Vec};
+
+impl<T, const N: usize> StaticRefCell<Vec<T, N>> {
+    /// Appends `item` to the stored vector, or returns it back if the vector is full or the
+    /// stored value is still None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static EVENTS: StaticRefCell<heapless::Vec<u8, 2>> = StaticRefCell::new();
+    /// critical_section::with(|cs| EVENTS.init_default(cs));
+    ///
+    /// // in the ISR
+    /// critical_section::with(|cs| {
+    ///     assert_eq!(EVENTS.push(cs, 1), Ok(()));
+    ///     assert_eq!(EVENTS.push(cs, 2), Ok(()));
+    ///     assert_eq!(EVENTS.push(cs, 3), Err(3));
+    /// });
+    ///
+    /// // in the main loop
+    /// let mut events = [0; 4];
+    /// let len = critical_section::with(|cs| EVENTS.drain_into(cs, &mut events));
+    /// assert_eq!(&events[..len], [1, 2]);
+    /// ```
+    #[track_caller]
+    pub fn push(&self, cs: CriticalSection, item: T) -> Result<(), T> {
+        self.borrow_mut_option(cs, |vec| match vec {
+            Some(vec) => vec.push(item),
+            None => Err(item),
+        })
+    }
+
+    /// Removes the last item of the stored vector and returns it, or returns None if the vector
+    /// is empty or the stored value is still None
+    #[track_caller]
+    pub fn pop(&self, cs: CriticalSection) -> Option<T> {
+        self.borrow_mut(cs, |vec| vec.pop(), || None)
+    }
+
+    /// Moves items from the front of the stored vector into `buf`, until either runs out, and
+    /// returns how many were moved
+    ///
+    /// The remaining items move to the front of the vector.
+    #[track_caller]
+    pub fn drain_into(&self, cs: CriticalSection, buf: &mut [T]) -> usize {
+        self.borrow_mut(
+            cs,
+            |vec| {
+                let count = vec.len().min(buf.len());
+                // move the drained items to the end, in order, so they can be popped off
+                vec.rotate_left(count);
+                for slot in buf[..count].iter_mut().rev() {
+                    if let Some(item) = vec.pop() {
+                        *slot = item;
+                    }
+                }
+                count
+            },
+            || 0,
+        )
+    }
+}
+
+impl<T, const N: usize> StaticRefCell<Deque<T, N>> {
+    /// Appends `item` to the back of the stored deque, or returns it back if the deque is full or
+    /// the stored value is still None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static COMMANDS: StaticRefCell<heapless::Deque<char, 4>> = StaticRefCell::new();
+    /// critical_section::with(|cs| COMMANDS.init_default(cs));
+    ///
+    /// // in the UART ISR
+    /// critical_section::with(|cs| "go".chars().for_each(|c| COMMANDS.push(cs, c).unwrap()));
+    ///
+    /// // in the main loop, commands are handled oldest first
+    /// assert_eq!(critical_section::with(|cs| COMMANDS.pop(cs)), Some('g'));
+    /// ```
+    #[track_caller]
+    pub fn push(&self, cs: CriticalSection, item: T) -> Result<(), T> {
+        self.borrow_mut_option(cs, |deque| match deque {
+            Some(deque) => deque.push_back(item),
+            None => Err(item),
+        })
+    }
+
+    /// Removes the item at the front of the stored deque and returns it, or returns None if the
+    /// deque is empty or the stored value is still None
+    #[track_caller]
+    pub fn pop(&self, cs: CriticalSection) -> Option<T> {
+        self.borrow_mut(cs, |deque| deque.pop_front(), || None)
+    }
+
+    /// Moves items from the front of the stored deque into `buf`, until either runs out, and
+    /// returns how many were moved
+    #[track_caller]
+    pub fn drain_into(&self, cs: CriticalSection, buf: &mut [T]) -> usize {
+        self.borrow_mut(
+            cs,
+            |deque| {
+                let mut count = 0;
+                for slot in buf.iter_mut() {
+                    match deque.pop_front() {
+                        Some(item) => *slot = item,
+                        None => break,
+                    }
+                    count += 1;
+                }
+                count
+            },
+            || 0,
+        )
+    }
+}
+
+impl<const N: usize> StaticRefCell<String<N>> {
+    /// Appends `c` to the stored string, or returns it back if the string is full or the stored
+    /// value is still None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static STATUS: StaticRefCell<heapless::String<8>> = StaticRefCell::new();
+    /// critical_section::with(|cs| STATUS.init_default(cs));
+    ///
+    /// critical_section::with(|cs| {
+    ///     STATUS.push_str(cs, "ok: ").unwrap();
+    ///     STATUS.push(cs, '✓').unwrap();
+    /// });
+    ///
+    /// let mut buf = [0; 5];
+    /// let len = critical_section::with(|cs| STATUS.drain_into(cs, &mut buf));
+    /// // the check mark doesn't fit whole, so it is left in the string
+    /// assert_eq!(&buf[..len], b"ok: ");
+    /// ```
+    #[track_caller]
+    pub fn push(&self, cs: CriticalSection, c: char) -> Result<(), char> {
+        self.borrow_mut(cs, |string| string.push(c).map_err(|()| c), || Err(c))
+    }
+
+    /// Appends `s` to the stored string, or returns an error (leaving the string unchanged) if it
+    /// doesn't fit or the stored value is still None
+    #[track_caller]
+    #[allow(clippy::result_unit_err)]
+    pub fn push_str(&self, cs: CriticalSection, s: &str) -> Result<(), ()> {
+        self.borrow_mut(cs, |string| string.push_str(s), || Err(()))
+    }
+
+    /// Removes the last character of the stored string and returns it, or returns None if the
+    /// string is empty or the stored value is still None
+    #[track_caller]
+    pub fn pop(&self, cs: CriticalSection) -> Option<char> {
+        self.borrow_mut(cs, |string| string.pop(), || None)
+    }
+
+    /// Moves as many whole characters from the front of the stored string as fit into `buf`, as
+    /// UTF-8, and returns the number of bytes moved
+    #[track_caller]
+    pub fn drain_into(&self, cs: CriticalSection, buf: &mut [u8]) -> usize {
+        self.borrow_mut(
+            cs,
+            |string| {
+                let mut count = string.len().min(buf.len());
+                while !string.is_char_boundary(count) {
+                    count -= 1;
+                }
+                buf[..count].copy_from_slice(&string.as_bytes()[..count]);
+                let mut rest = String::new();
+                // the rest is shorter than the original, so it always fits
+                let _ = rest.push_str(&string[count..]);
+                *string = rest;
+                count
+            },
+            || 0,
+        )
+    }
+}
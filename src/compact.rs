@@ -0,0 +1,264 @@
+use crate::BorrowError;
+use core::cell::{Cell, UnsafeCell};
+use core::mem::MaybeUninit;
+use critical_section::CriticalSection;
+
+/// The stored value is uninitialized
+const UNINIT: u8 = 0;
+/// The stored value is initialized and not borrowed
+const IDLE: u8 = 1;
+/// The stored value is mutably borrowed; any state between `IDLE` and this counts shared borrows
+const WRITING: u8 = u8::MAX;
+
+/// A `StaticRefCell` alternative that stores its payload as a `MaybeUninit<T>` plus a single
+/// state byte
+///
+/// `StaticRefCell` pays for an `Option` discriminant, a `RefCell` borrow counter, and an
+/// initialization flag, which adds up for large payloads like frame or DMA buffers. This type
+/// packs the initialization and borrow state into one byte instead, while enforcing the same rules:
+/// every access requires a `CriticalSection`, and conflicting borrows panic (or return an error
+/// from the `try_*` methods).
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::CompactStaticRefCell;
+/// #
+/// static FRAME: CompactStaticRefCell<[u8; 64]> = CompactStaticRefCell::new();
+///
+/// critical_section::with(|cs| FRAME.init(cs, [0; 64]));
+/// critical_section::with(|cs| FRAME.borrow_mut(cs, |frame| frame[0] = 0xff, || {}));
+///
+/// let first = critical_section::with(|cs| FRAME.borrow(cs, |frame| frame[0], || 0));
+/// assert_eq!(first, 0xff);
+/// ```
+pub struct CompactStaticRefCell<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    state: Cell<u8>,
+}
+
+// SAFETY: both fields are only accessed while holding a critical section, which serializes access
+// the same way `critical_section::Mutex` does
+unsafe impl<T: Send> Sync for CompactStaticRefCell<T> {}
+
+/// Restores the state byte when a borrow ends, including when the borrowing closure panics
+struct Release<'a> {
+    state: &'a Cell<u8>,
+    restore: u8,
+}
+
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        self.state.set(self.restore);
+    }
+}
+
+impl<T> CompactStaticRefCell<T> {
+    /// Creates a new uninitialized object
+    pub const fn new() -> Self {
+        Self {
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            state: Cell::new(UNINIT),
+        }
+    }
+
+    /// Sets the stored value for this object, dropping the previously stored value if there was one
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored value is currently borrowed.
+    pub fn init(&self, cs: CriticalSection, value: T) {
+        drop(self.replace(cs, value));
+    }
+
+    /// Sets the stored value for this object and returns the previously stored value, or None if
+    /// the object was not yet initialized
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored value is currently borrowed.
+    pub fn replace(&self, cs: CriticalSection, value: T) -> Option<T> {
+        let old = self.take(cs);
+        // SAFETY: `take` left the object uninitialized, so nothing refers to the stored value
+        unsafe { (*self.value.get()).write(value) };
+        self.state.set(IDLE);
+        old
+    }
+
+    /// Moves the stored value out of this object, leaving it uninitialized
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored value is currently borrowed.
+    pub fn take(&self, _cs: CriticalSection) -> Option<T> {
+        match self.state.get() {
+            UNINIT => None,
+            IDLE => {
+                self.state.set(UNINIT);
+                // SAFETY: the state was `IDLE`, so the value is initialized and not borrowed, and
+                // marking it uninitialized first means it won't be read (or dropped) again
+                Some(unsafe { (*self.value.get()).assume_init_read() })
+            }
+            _ => panic!("{}", BorrowError::AlreadyBorrowed),
+        }
+    }
+
+    /// Returns true if the stored value for this object is initialized
+    pub fn is_initialized(&self, _cs: CriticalSection) -> bool {
+        self.state.get() != UNINIT
+    }
+
+    /// Passes an immutable reference to the stored data in `func` and returns the result, or
+    /// returns the result of `none_func` if the stored data is uninitialized
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently mutably borrowed.
+    pub fn borrow<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        match self.try_borrow(cs, func) {
+            Ok(result) => result,
+            Err(BorrowError::Uninitialized) => none_func(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Passes a mutable reference to the stored data in `func` and returns the result, or returns
+    /// the result of `none_func` if the stored data is uninitialized
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently borrowed.
+    pub fn borrow_mut<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        match self.try_borrow_mut(cs, func) {
+            Ok(result) => result,
+            Err(BorrowError::Uninitialized) => none_func(),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Passes an immutable reference to the stored data in `func` and returns the result, or
+    /// returns a `BorrowError` if the stored data is uninitialized or mutably borrowed
+    pub fn try_borrow<Y, F: FnOnce(&T) -> Y>(
+        &self,
+        _cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, BorrowError> {
+        let state = self.state.get();
+        match state {
+            UNINIT => return Err(BorrowError::Uninitialized),
+            // the last shared borrow count before `WRITING` is treated as a conflict rather than
+            // wrapping into the mutable borrow state
+            s if s >= WRITING - 1 => return Err(BorrowError::AlreadyBorrowed),
+            _ => {}
+        }
+        self.state.set(state + 1);
+        let _release = Release {
+            state: &self.state,
+            restore: state,
+        };
+        // SAFETY: the state was initialized without a mutable borrow, and the incremented state
+        // keeps anyone from mutably borrowing or removing the value until `_release` is dropped
+        Ok(func(unsafe { (*self.value.get()).assume_init_ref() }))
+    }
+
+    /// Passes a mutable reference to the stored data in `func` and returns the result, or returns
+    /// a `BorrowError` if the stored data is uninitialized or already borrowed
+    pub fn try_borrow_mut<Y, F: FnOnce(&mut T) -> Y>(
+        &self,
+        _cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, BorrowError> {
+        match self.state.get() {
+            UNINIT => return Err(BorrowError::Uninitialized),
+            IDLE => {}
+            _ => return Err(BorrowError::AlreadyBorrowed),
+        }
+        self.state.set(WRITING);
+        let _release = Release {
+            state: &self.state,
+            restore: IDLE,
+        };
+        // SAFETY: the state was initialized without any borrows, and the `WRITING` state keeps
+        // anyone else from borrowing or removing the value until `_release` is dropped
+        Ok(func(unsafe { (*self.value.get()).assume_init_mut() }))
+    }
+}
+
+impl<T> Default for CompactStaticRefCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for CompactStaticRefCell<T> {
+    fn drop(&mut self) {
+        if self.state.get() != UNINIT {
+            // SAFETY: the value is initialized, and `&mut self` means it can't be borrowed
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrow_rules() {
+        let cell: CompactStaticRefCell<u32> = CompactStaticRefCell::new();
+
+        critical_section::with(|cs| {
+            assert_eq!(cell.try_borrow(cs, |_| ()), Err(BorrowError::Uninitialized));
+
+            cell.init(cs, 1);
+
+            // shared borrows may nest, but not with a mutable borrow
+            let nested = cell.try_borrow(cs, |a| cell.try_borrow(cs, |b| a + b));
+            assert_eq!(nested, Ok(Ok(2)));
+            let nested = cell.try_borrow(cs, |_| cell.try_borrow_mut(cs, |_| ()));
+            assert_eq!(nested, Ok(Err(BorrowError::AlreadyBorrowed)));
+            let nested = cell.try_borrow_mut(cs, |_| cell.try_borrow(cs, |_| ()));
+            assert_eq!(nested, Ok(Err(BorrowError::AlreadyBorrowed)));
+
+            // borrows are released afterwards
+            cell.borrow_mut(cs, |value| *value += 1, || {});
+            assert_eq!(cell.take(cs), Some(2));
+            assert!(!cell.is_initialized(cs));
+        });
+    }
+
+    #[test]
+    fn drops_stored_value() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Buffer;
+
+        impl Drop for Buffer {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let cell: CompactStaticRefCell<Buffer> = CompactStaticRefCell::new();
+        critical_section::with(|cs| {
+            cell.init(cs, Buffer);
+            cell.init(cs, Buffer);
+        });
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+
+        drop(cell);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+    }
+}
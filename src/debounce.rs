@@ -0,0 +1,88 @@
+/// A change in the debounced level of a pin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// The level changed from low to high
+    Rising,
+    /// The level changed from high to low
+    Falling,
+}
+
+/// Debounces a digital input that is sampled by an ISR
+///
+/// The debounced level only changes once `SAMPLES` consecutive raw samples agree on the new level.
+/// Every change is latched as a pending edge, so an edge detected in the ISR can be handled later
+/// by the main loop with `take_rising` / `take_falling`. It is meant to be stored in a
+/// `StaticRefCell` (or `LazyStaticRefCell`) shared by the sampling ISR and the main loop.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{Debouncer, StaticRefCell};
+/// #
+/// static BUTTON: StaticRefCell<Debouncer<3>> = StaticRefCell::new_with(Debouncer::new(false));
+///
+/// // in the timer ISR that samples the pin
+/// for level in [true, false, true, true, true] {
+///     critical_section::with(|cs| BUTTON.borrow_mut(cs, |button| button.update(level), || None));
+/// }
+///
+/// // in the main loop
+/// let pressed = critical_section::with(|cs| BUTTON.borrow_mut(cs, |b| b.take_rising(), || false));
+/// assert!(pressed);
+/// assert!(critical_section::with(|cs| BUTTON.borrow(cs, |button| button.is_high(), || false)));
+/// ```
+pub struct Debouncer<const SAMPLES: u8> {
+    level: bool,
+    count: u8,
+    rising: bool,
+    falling: bool,
+}
+
+impl<const SAMPLES: u8> Debouncer<SAMPLES> {
+    /// Creates a new debouncer whose debounced level starts at `high`, with no pending edges
+    pub const fn new(high: bool) -> Self {
+        Self {
+            level: high,
+            count: 0,
+            rising: false,
+            falling: false,
+        }
+    }
+
+    /// Feeds a raw sample of the pin level, returning the edge if the debounced level changed
+    pub fn update(&mut self, high: bool) -> Option<Edge> {
+        if high == self.level {
+            self.count = 0;
+            return None;
+        }
+        self.count += 1;
+        if self.count < SAMPLES {
+            return None;
+        }
+
+        self.count = 0;
+        self.level = high;
+        if high {
+            self.rising = true;
+            Some(Edge::Rising)
+        } else {
+            self.falling = true;
+            Some(Edge::Falling)
+        }
+    }
+
+    /// Returns the debounced level
+    pub fn is_high(&self) -> bool {
+        self.level
+    }
+
+    /// Returns true if there is a pending rising edge, and clears it
+    pub fn take_rising(&mut self) -> bool {
+        core::mem::take(&mut self.rising)
+    }
+
+    /// Returns true if there is a pending falling edge, and clears it
+    pub fn take_falling(&mut self) -> bool {
+        core::mem::take(&mut self.falling)
+    }
+}
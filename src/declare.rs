@@ -0,0 +1,182 @@
+/// Declares `StaticRefCell` statics along with free accessor functions, so application code never
+/// has to call `critical_section::with` directly
+///
+/// The accessor functions are named after the static in lowercase. There are two forms:
+///
+/// - `static NAME: T = value;` creates a cell that is already initialized with the const
+///   expression `value`, plus `name_get()`, `name_set(value)`, `name_with(|v| ...)`, and
+///   `name_with_mut(|v| ...)`. Since the cell is never uninitialized, these return values directly
+///   instead of `Option`s. `name_get` copies the value out, so this form requires `T: Copy`.
+/// - `static NAME: T;` creates an uninitialized cell, plus `name_init(value)`, `name_take()`,
+///   `name_with(|v| ...)`, and `name_with_mut(|v| ...)`, which return `Option`s that are None
+///   while the cell is uninitialized. This form suits peripherals that are set up at runtime.
+///
+/// Attributes are applied to the static, and its visibility is applied to the generated functions
+/// too.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::static_ref_cell;
+/// #
+/// struct Led {
+///     on: bool,
+/// }
+///
+/// static_ref_cell! {
+///     static MILLIS: u32 = 0;
+///     static LED: Led;
+/// }
+///
+/// // in the timer ISR
+/// millis_with_mut(|millis| *millis += 1);
+///
+/// // in main
+/// led_init(Led { on: false });
+/// led_with_mut(|led| led.on = millis_get() > 0);
+/// assert_eq!(led_with(|led| led.on), Some(true));
+/// ```
+#[macro_export]
+macro_rules! static_ref_cell {
+    () => {};
+    (
+        $(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        $vis static $name: $crate::StaticRefCell<$ty> = $crate::StaticRefCell::new_with($init).named(stringify!($name));
+
+        $crate::__private::paste! {
+            #[doc = concat!("Returns a copy of the value stored in `", stringify!($name), "`")]
+            #[track_caller]
+            $vis fn [<$name:lower _get>]() -> $ty {
+                [<$name:lower _with>](|value| *value)
+            }
+
+            #[doc = concat!("Sets the value stored in `", stringify!($name), "`")]
+            #[track_caller]
+            $vis fn [<$name:lower _set>](value: $ty) {
+                $crate::__private::init(&$name, value)
+            }
+
+            #[doc = concat!("Passes an immutable reference to the value stored in `", stringify!($name), "` in `func`")]
+            #[track_caller]
+            $vis fn [<$name:lower _with>]<Y>(func: impl FnOnce(&$ty) -> Y) -> Y {
+                $name
+                    .with(func)
+                    .expect(concat!(stringify!($name), " is not initialized"))
+            }
+
+            #[doc = concat!("Passes a mutable reference to the value stored in `", stringify!($name), "` in `func`")]
+            #[track_caller]
+            $vis fn [<$name:lower _with_mut>]<Y>(func: impl FnOnce(&mut $ty) -> Y) -> Y {
+                $name
+                    .with_mut(func)
+                    .expect(concat!(stringify!($name), " is not initialized"))
+            }
+        }
+
+        $crate::static_ref_cell!($($rest)*);
+    };
+    (
+        $(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty;
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        $vis static $name: $crate::StaticRefCell<$ty> = $crate::StaticRefCell::new().named(stringify!($name));
+
+        $crate::__private::paste! {
+            #[doc = concat!("Sets the value stored in `", stringify!($name), "`")]
+            #[track_caller]
+            $vis fn [<$name:lower _init>](value: $ty) {
+                $crate::__private::init(&$name, value)
+            }
+
+            #[doc = concat!("Moves the value stored in `", stringify!($name), "` out, leaving it uninitialized")]
+            #[track_caller]
+            $vis fn [<$name:lower _take>]() -> ::core::option::Option<$ty> {
+                $crate::__private::take(&$name)
+            }
+
+            #[doc = concat!("Passes an immutable reference to the value stored in `", stringify!($name), "` in `func`, or returns None if it is not initialized")]
+            #[track_caller]
+            $vis fn [<$name:lower _with>]<Y>(func: impl FnOnce(&$ty) -> Y) -> ::core::option::Option<Y> {
+                $name.with(func)
+            }
+
+            #[doc = concat!("Passes a mutable reference to the value stored in `", stringify!($name), "` in `func`, or returns None if it is not initialized")]
+            #[track_caller]
+            $vis fn [<$name:lower _with_mut>]<Y>(func: impl FnOnce(&mut $ty) -> Y) -> ::core::option::Option<Y> {
+                $name.with_mut(func)
+            }
+        }
+
+        $crate::static_ref_cell!($($rest)*);
+    };
+}
+
+/// Declares a `StaticRefCell` static for each of several peripherals or pins, along with a
+/// function that moves all of them into their cells
+///
+/// The function takes a `CriticalSection` and the structs the fields come from (such as the
+/// `Peripherals` and the `Pins` of `avr-hal`), and each static names the expression it is
+/// initialized with, which may convert the field first (such as into an output pin). This replaces
+/// declaring every uninitialized static and initializing it by hand. The cells are named after
+/// their statics (see `StaticRefCell::named`).
+///
+/// Requires the `avr-hal` feature, though nothing in it is specific to `avr-hal`.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::into_cells;
+/// # mod arduino_hal {
+/// #     pub struct Peripherals { pub TC0: TC0, pub USART0: USART0 }
+/// #     pub struct TC0;
+/// #     pub struct USART0;
+/// #     pub struct Pins { pub d13: Pin, pub d2: Pin }
+/// #     pub struct Pin;
+/// #     impl Pin {
+/// #         pub fn into_output(self) -> Pin { self }
+/// #     }
+/// # }
+/// # use arduino_hal::{Peripherals, Pin, Pins, TC0};
+/// #
+/// into_cells! {
+///     fn init_cells(cs, dp: Peripherals, pins: Pins) {
+///         static TIMER: TC0 = dp.TC0;
+///         static LED: Pin = pins.d13.into_output();
+///         static BUTTON: Pin = pins.d2;
+///     }
+/// }
+///
+/// # let (dp, pins) = (
+/// #     Peripherals { TC0: arduino_hal::TC0, USART0: arduino_hal::USART0 },
+/// #     Pins { d13: Pin, d2: Pin },
+/// # );
+/// // in main, with dp and pins taken from arduino_hal
+/// critical_section::with(|cs| init_cells(cs, dp, pins));
+/// assert!(critical_section::with(|cs| LED.is_initialized(cs)));
+/// ```
+#[cfg(feature = "avr-hal")]
+#[macro_export]
+macro_rules! into_cells {
+    (
+        $(#[$fn_attr:meta])*
+        $vis:vis fn $init:ident($cs:ident, $($arg:ident: $arg_ty:ty),+ $(,)?) {
+            $(
+                $(#[$attr:meta])* $static_vis:vis static $name:ident: $ty:ty = $value:expr;
+            )*
+        }
+    ) => {
+        $(
+            $(#[$attr])*
+            $static_vis static $name: $crate::StaticRefCell<$ty> = $crate::StaticRefCell::new().named(stringify!($name));
+        )*
+
+        $(#[$fn_attr])*
+        $vis fn $init($cs: $crate::__private::critical_section::CriticalSection, $($arg: $arg_ty),+) {
+            $($name.init($cs, $value);)*
+        }
+    };
+}
@@ -0,0 +1,78 @@
+use core::cell::Cell;
+use core::fmt;
+use core::panic::Location;
+use critical_section::{CriticalSection, Mutex};
+
+/// A description of a conflicting borrow of a `StaticRefCell`, recorded with the `debug-borrows`
+/// feature
+///
+/// See `StaticRefCell::last_conflict`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowConflict {
+    /// The name of the cell, if it was given one with `StaticRefCell::named`
+    pub name: Option<&'static str>,
+    /// Where the most recent successful borrow of the cell was made, which is usually the borrow
+    /// that is still held
+    pub borrowed_at: Option<&'static Location<'static>>,
+    /// Where the conflicting borrow was attempted
+    pub conflict_at: &'static Location<'static>,
+}
+
+impl fmt::Display for BorrowConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StaticRefCell")?;
+        if let Some(name) = self.name {
+            write!(f, " `{name}`")?;
+        }
+        write!(
+            f,
+            " is already borrowed: conflicting borrow at {}",
+            self.conflict_at
+        )?;
+        if let Some(borrowed_at) = self.borrowed_at {
+            write!(f, ", last borrowed at {borrowed_at}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The name and borrow history of one cell
+pub(crate) struct Diagnostics {
+    pub(crate) name: Option<&'static str>,
+    borrowed_at: Mutex<Cell<Option<&'static Location<'static>>>>,
+    last_conflict: Mutex<Cell<Option<BorrowConflict>>>,
+}
+
+impl Diagnostics {
+    pub(crate) const fn new() -> Self {
+        Self {
+            name: None,
+            borrowed_at: Mutex::new(Cell::new(None)),
+            last_conflict: Mutex::new(Cell::new(None)),
+        }
+    }
+
+    /// Records `location` as the most recent successful borrow
+    pub(crate) fn borrowed(&self, cs: CriticalSection, location: &'static Location<'static>) {
+        self.borrowed_at.borrow(cs).set(Some(location));
+    }
+
+    /// Records `location` as a conflicting borrow and returns the description
+    pub(crate) fn conflict(
+        &self,
+        cs: CriticalSection,
+        location: &'static Location<'static>,
+    ) -> BorrowConflict {
+        let conflict = BorrowConflict {
+            name: self.name,
+            borrowed_at: self.borrowed_at.borrow(cs).get(),
+            conflict_at: location,
+        };
+        self.last_conflict.borrow(cs).set(Some(conflict));
+        conflict
+    }
+
+    pub(crate) fn last_conflict(&self, cs: CriticalSection) -> Option<BorrowConflict> {
+        self.last_conflict.borrow(cs).get()
+    }
+}
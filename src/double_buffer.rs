@@ -0,0 +1,131 @@
+use core::cell::{Cell, RefCell};
+use critical_section::{CriticalSection, Mutex};
+
+/// A pair of buffers where an ISR fills the back buffer while the main loop reads the front one
+///
+/// Once the ISR has finished a frame in the back buffer, it calls `commit`, which marks the frame
+/// as ready. The main loop then calls `swap`, which exchanges the buffers if a frame is ready, so
+/// the finished frame becomes the front buffer and the ISR starts filling the old front buffer.
+/// The buffers themselves are never copied.
+///
+/// While a committed frame waits for the main loop to swap, the back buffer holds that frame, so
+/// `write_back` refuses to touch it (returning None) and the ISR has to drop or hold on to new
+/// data until the swap. This keeps a frame that is filled over several interrupts from being
+/// overwritten in place, which would hand the main loop a torn frame.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::DoubleBuffer;
+/// #
+/// static SAMPLES: DoubleBuffer<[u16; 4]> = DoubleBuffer::new([0; 4], [0; 4]);
+///
+/// // in the ADC ISR, unless the last frame is still waiting for the main loop
+/// critical_section::with(|cs| {
+///     if SAMPLES.write_back(cs, |samples| samples.fill(512)).is_some() {
+///         SAMPLES.commit(cs);
+///     }
+/// });
+///
+/// // in the main loop
+/// critical_section::with(|cs| {
+///     if SAMPLES.swap(cs) {
+///         assert_eq!(SAMPLES.read_front(cs, |samples| samples[0]), 512);
+///     }
+///     // no new frame arrived since the last swap
+///     assert!(!SAMPLES.swap(cs));
+/// });
+/// ```
+pub struct DoubleBuffer<T> {
+    buffers: Mutex<RefCell<[T; 2]>>,
+    /// The index of the front buffer
+    front: Mutex<Cell<usize>>,
+    dirty: Mutex<Cell<bool>>,
+}
+
+impl<T> DoubleBuffer<T> {
+    /// Creates a new double buffer with the given front and back buffers, and no frame ready
+    pub const fn new(front: T, back: T) -> Self {
+        Self {
+            buffers: Mutex::new(RefCell::new([front, back])),
+            front: Mutex::new(Cell::new(0)),
+            dirty: Mutex::new(Cell::new(false)),
+        }
+    }
+
+    /// Passes a mutable reference to the back buffer in `func` and returns the result, or returns
+    /// None (without calling `func`) if a committed frame is waiting to be swapped
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers are currently borrowed.
+    pub fn write_back<Y, F: FnOnce(&mut T) -> Y>(&self, cs: CriticalSection, func: F) -> Option<Y> {
+        if self.is_dirty(cs) {
+            return None;
+        }
+        let back = 1 - self.front.borrow(cs).get();
+        Some(func(&mut self.buffers.borrow_ref_mut(cs)[back]))
+    }
+
+    /// Marks the back buffer as holding a finished frame
+    pub fn commit(&self, cs: CriticalSection) {
+        self.dirty.borrow(cs).set(true);
+    }
+
+    /// Returns true if a frame was committed since the last swap
+    pub fn is_dirty(&self, cs: CriticalSection) -> bool {
+        self.dirty.borrow(cs).get()
+    }
+
+    /// Exchanges the front and back buffers if a frame was committed, returning true if they
+    /// were exchanged
+    pub fn swap(&self, cs: CriticalSection) -> bool {
+        if !self.dirty.borrow(cs).replace(false) {
+            return false;
+        }
+        let front = self.front.borrow(cs);
+        front.set(1 - front.get());
+        true
+    }
+
+    /// Passes an immutable reference to the front buffer in `func` and returns the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers are currently mutably borrowed.
+    pub fn read_front<Y, F: FnOnce(&T) -> Y>(&self, cs: CriticalSection, func: F) -> Y {
+        let front = self.front.borrow(cs).get();
+        func(&self.buffers.borrow_ref(cs)[front])
+    }
+}
+
+impl<T: Default> Default for DoubleBuffer<T> {
+    fn default() -> Self {
+        Self::new(T::default(), T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn committed_frame_is_not_refilled() {
+        critical_section::with(|cs| {
+            let buffer = DoubleBuffer::new([0u8; 4], [0u8; 4]);
+            for (index, sample) in [1, 2, 3, 4].into_iter().enumerate() {
+                buffer
+                    .write_back(cs, |frame| frame[index] = sample)
+                    .unwrap();
+            }
+            buffer.commit(cs);
+
+            // the ISR starts on the next frame before the main loop swapped
+            assert_eq!(buffer.write_back(cs, |frame| frame[0] = 9), None);
+
+            assert!(buffer.swap(cs));
+            assert_eq!(buffer.read_front(cs, |frame| *frame), [1, 2, 3, 4]);
+            assert_eq!(buffer.write_back(cs, |frame| frame[0] = 9), Some(()));
+        });
+    }
+}
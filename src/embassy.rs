@@ -0,0 +1,81 @@
+//! Compatibility with `embassy-sync`
+//!
+//! `StaticRawMutex` implements embassy-sync's `RawMutex` with the same critical sections used by
+//! `StaticRefCell`, so drivers and channels written against embassy-sync can be shared with ISR
+//! code that uses this crate, under the same locking discipline. A `StaticRefCell` converts into
+//! a `BlockingMutex<RefCell<Option<T>>>` and back.
+//!
+//! Requires the `embassy` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use core::cell::{Cell, RefCell};
+//! use embedded_static_ref_cell::embassy::BlockingMutex;
+//! use embedded_static_ref_cell::StaticRefCell;
+//!
+//! static PRESSES: BlockingMutex<Cell<u32>> = BlockingMutex::new(Cell::new(0));
+//!
+//! // in an ISR, or in a driver that is generic over `RawMutex`
+//! PRESSES.lock(|presses| presses.set(presses.get() + 1));
+//!
+//! assert_eq!(PRESSES.lock(Cell::get), 1);
+//!
+//! // a cell converts to a mutex holding its Option, and back
+//! let mutex: BlockingMutex<RefCell<Option<u8>>> = StaticRefCell::new_with(7).into();
+//! assert_eq!(mutex.lock(|value| *value.borrow()), Some(7));
+//! let cell = StaticRefCell::from(mutex);
+//! assert_eq!(critical_section::with(|cs| cell.get(cs)), Some(7));
+//! ```
+
+use crate::StaticRefCell;
+use core::cell::RefCell;
+use embassy_sync::blocking_mutex::raw::RawMutex;
+
+/// An embassy-sync `RawMutex` that locks by entering a critical section, like `StaticRefCell`
+///
+/// This can be used anywhere embassy-sync expects a `RawMutex`, such as in its channels, signals,
+/// and blocking mutexes, and is safe to share between ISRs and executors.
+pub struct StaticRawMutex {
+    _private: (),
+}
+
+impl StaticRawMutex {
+    /// Creates a new raw mutex
+    pub const fn new() -> Self {
+        Self { _private: () }
+    }
+}
+
+impl Default for StaticRawMutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SAFETY: `lock` runs `f` in a critical section, so no other context can hold the lock concurrently
+unsafe impl RawMutex for StaticRawMutex {
+    const INIT: Self = Self::new();
+
+    fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        critical_section::with(|_| f())
+    }
+}
+
+/// An embassy-sync blocking mutex that uses `StaticRawMutex`
+pub type BlockingMutex<T> = embassy_sync::blocking_mutex::Mutex<StaticRawMutex, T>;
+
+impl<T> From<StaticRefCell<T>> for BlockingMutex<RefCell<Option<T>>> {
+    fn from(cell: StaticRefCell<T>) -> Self {
+        BlockingMutex::new(RefCell::new(cell.into_inner()))
+    }
+}
+
+impl<T> From<BlockingMutex<RefCell<Option<T>>>> for StaticRefCell<T> {
+    fn from(mutex: BlockingMutex<RefCell<Option<T>>>) -> Self {
+        match mutex.into_inner().into_inner() {
+            Some(value) => StaticRefCell::new_with(value),
+            None => StaticRefCell::new(),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use core::cell::Cell;
+use critical_section::{CriticalSection, Mutex};
+
+/// The direction a quadrature encoder last moved in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Counting up, as the AB levels go 00, 01, 11, 10
+    Forward,
+    /// Counting down, as the AB levels go 00, 10, 11, 01
+    Reverse,
+}
+
+/// The position change for each transition of the AB state, indexed by `from << 2 | to`, or None
+/// if both channels changed at once (a step was missed)
+const STEPS: [Option<i8>; 16] = [
+    Some(0),
+    Some(1),
+    Some(-1),
+    None,
+    Some(-1),
+    Some(0),
+    None,
+    Some(1),
+    Some(1),
+    None,
+    Some(0),
+    Some(-1),
+    None,
+    Some(-1),
+    Some(1),
+    Some(0),
+];
+
+#[derive(Clone, Copy)]
+struct State {
+    /// The last AB state, as `a << 1 | b`
+    last: Option<u8>,
+    position: i32,
+    delta: i32,
+    direction: Option<Direction>,
+    errors: u16,
+}
+
+/// A quadrature encoder counter, fed with the A and B pin levels by a pin-change ISR
+///
+/// Every edge of either channel moves the position by one count (so a detent of a typical rotary
+/// encoder is four counts). The main loop reads the absolute position, or the counts since it
+/// last looked with `take_delta`. A transition where both channels changed at once means an edge
+/// was missed (such as when the ISR ran late); it leaves the position unchanged and is counted by
+/// `errors`.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{Direction, EncoderCell};
+/// #
+/// static KNOB: EncoderCell = EncoderCell::new();
+///
+/// // in the pin-change ISR, with the levels read from the pins
+/// critical_section::with(|cs| {
+///     for (a, b) in [(false, false), (true, false), (true, true), (false, true)] {
+///         KNOB.update(cs, a, b);
+///     }
+/// });
+///
+/// // in the main loop
+/// critical_section::with(|cs| {
+///     assert_eq!(KNOB.take_delta(cs), -3);
+///     assert_eq!(KNOB.direction(cs), Some(Direction::Reverse));
+///     assert_eq!(KNOB.take_delta(cs), 0);
+/// });
+/// ```
+pub struct EncoderCell(Mutex<Cell<State>>);
+
+impl EncoderCell {
+    /// Creates a new counter at position 0
+    ///
+    /// The first `update` only records the pin levels.
+    pub const fn new() -> Self {
+        Self(Mutex::new(Cell::new(State {
+            last: None,
+            position: 0,
+            delta: 0,
+            direction: None,
+            errors: 0,
+        })))
+    }
+
+    /// Updates the position from the current levels of the A and B pins
+    pub fn update(&self, cs: CriticalSection, a: bool, b: bool) {
+        let cell = self.0.borrow(cs);
+        let mut state = cell.get();
+        let current = u8::from(a) << 1 | u8::from(b);
+        if let Some(last) = state.last {
+            match STEPS[usize::from(last << 2 | current)] {
+                Some(0) => {}
+                Some(step) => {
+                    state.position = state.position.wrapping_add(i32::from(step));
+                    state.delta = state.delta.wrapping_add(i32::from(step));
+                    state.direction = Some(if step > 0 {
+                        Direction::Forward
+                    } else {
+                        Direction::Reverse
+                    });
+                }
+                None => state.errors = state.errors.saturating_add(1),
+            }
+        }
+        state.last = Some(current);
+        cell.set(state);
+    }
+
+    /// Returns the position, in counts (wrapping)
+    pub fn position(&self, cs: CriticalSection) -> i32 {
+        self.0.borrow(cs).get().position
+    }
+
+    /// Sets the position, such as when homing against an end stop
+    pub fn set_position(&self, cs: CriticalSection, position: i32) {
+        let cell = self.0.borrow(cs);
+        cell.set(State {
+            position,
+            ..cell.get()
+        });
+    }
+
+    /// Returns the change in position since the last call
+    pub fn take_delta(&self, cs: CriticalSection) -> i32 {
+        let cell = self.0.borrow(cs);
+        let state = cell.get();
+        cell.set(State { delta: 0, ..state });
+        state.delta
+    }
+
+    /// Returns the direction of the last movement, or None if the encoder hasn't moved
+    pub fn direction(&self, cs: CriticalSection) -> Option<Direction> {
+        self.0.borrow(cs).get().direction
+    }
+
+    /// Returns the number of transitions where an edge was missed (saturating)
+    pub fn errors(&self, cs: CriticalSection) -> u16 {
+        self.0.borrow(cs).get().errors
+    }
+}
+
+impl Default for EncoderCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_cycles_and_missed_edges() {
+        critical_section::with(|cs| {
+            let encoder = EncoderCell::new();
+            // two forward cycles of 00 -> 01 -> 11 -> 10
+            for _ in 0..2 {
+                for (a, b) in [(false, false), (false, true), (true, true), (true, false)] {
+                    encoder.update(cs, a, b);
+                }
+            }
+            encoder.update(cs, false, false);
+            assert_eq!(encoder.position(cs), 8);
+            assert_eq!(encoder.direction(cs), Some(Direction::Forward));
+
+            // 00 -> 11 changes both channels
+            encoder.update(cs, true, true);
+            assert_eq!(encoder.errors(cs), 1);
+            assert_eq!(encoder.take_delta(cs), 8);
+
+            // back again: 11 -> 01 -> 00
+            encoder.update(cs, false, true);
+            encoder.update(cs, false, false);
+            assert_eq!(encoder.take_delta(cs), -2);
+            assert_eq!(encoder.position(cs), 6);
+        });
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{Integer, StaticPrimitiveCell};
+use core::ops::{BitAnd, Not};
+
+/// A set of event flags stored as a bitmask, set by ISRs and consumed by the main loop
+///
+/// ISRs `set` the bits for the events they signal, and the main loop reads and clears them in one
+/// atomic step with `take_flags`, so an event that fires between reading and clearing is never lost.
+/// Like `StaticPrimitiveCell`, none of the operations need a critical section.
+///
+/// The `wait_*` functions spin until the flags are set, so they should only be called from the main
+/// loop, with the interrupts that set the flags enabled. They assume that a single context (the
+/// main loop) clears the flags.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::StaticEventFlags;
+/// #
+/// const RX_DONE: u8 = 1 << 0;
+/// const TX_DONE: u8 = 1 << 1;
+/// const BUTTON: u8 = 1 << 2;
+///
+/// static EVENTS: StaticEventFlags<u8> = StaticEventFlags::new();
+///
+/// // in the ISRs
+/// EVENTS.set(RX_DONE);
+/// EVENTS.set(BUTTON);
+///
+/// // in the main loop
+/// let events = EVENTS.take_flags(RX_DONE | TX_DONE);
+/// assert_eq!(events, RX_DONE);
+/// assert_eq!(EVENTS.get(), BUTTON);
+/// ```
+pub struct StaticEventFlags<T: Integer = u8>(StaticPrimitiveCell<T>);
+
+impl<T: Integer> StaticEventFlags<T> {
+    /// Creates a new object with every flag cleared
+    pub const fn new() -> Self {
+        Self(StaticPrimitiveCell::new(T::ZERO))
+    }
+
+    /// Returns the current flags without clearing any of them
+    pub fn get(&self) -> T {
+        self.0.load()
+    }
+
+    /// Sets the flags in `mask`
+    pub fn set(&self, mask: T) {
+        self.0.fetch_or(mask);
+    }
+
+    /// Clears every flag, returning the flags that were set
+    pub fn take_all(&self) -> T {
+        self.0.swap(T::ZERO)
+    }
+}
+
+impl<T: Integer + BitAnd<Output = T> + Not<Output = T> + PartialEq> StaticEventFlags<T> {
+    /// Clears the flags in `mask`
+    pub fn clear(&self, mask: T) {
+        self.0.fetch_and(!mask);
+    }
+
+    /// Returns true if any of the flags in `mask` are set
+    pub fn is_any_set(&self, mask: T) -> bool {
+        self.get() & mask != T::ZERO
+    }
+
+    /// Returns true if all of the flags in `mask` are set
+    pub fn is_all_set(&self, mask: T) -> bool {
+        self.get() & mask == mask
+    }
+
+    /// Clears the flags in `mask`, returning which of them were set
+    pub fn take_flags(&self, mask: T) -> T {
+        self.0.fetch_and(!mask) & mask
+    }
+
+    /// Spins until any of the flags in `mask` are set, then clears the flags in `mask` and returns
+    /// which of them were set
+    pub fn wait_any(&self, mask: T) -> T {
+        while !self.is_any_set(mask) {
+            core::hint::spin_loop();
+        }
+        self.take_flags(mask)
+    }
+
+    /// Spins until all of the flags in `mask` are set, then clears them
+    pub fn wait_all(&self, mask: T) {
+        while !self.is_all_set(mask) {
+            core::hint::spin_loop();
+        }
+        self.clear(mask);
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T: Integer + ufmt::uDebug> ufmt::uDebug for StaticEventFlags<T> {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        f.debug_tuple("StaticEventFlags")?
+            .field(&self.get())?
+            .finish()
+    }
+}
+
+impl<T: Integer> Default for StaticEventFlags<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
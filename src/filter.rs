@@ -0,0 +1,168 @@
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+/// The filter computed by a `FilterCell`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// The mean of the last `N` samples
+    MovingAverage,
+    /// A first-order IIR (exponential moving average) with a smoothing factor of `1 / N`, kept
+    /// in fixed point
+    Iir,
+}
+
+struct State<const N: usize> {
+    window: [i32; N],
+    /// The index the next sample is written to
+    next: usize,
+    len: usize,
+    /// The sum of the window for `Filter::MovingAverage`, or the output scaled by `N` for
+    /// `Filter::Iir`
+    acc: i64,
+}
+
+/// A cell filtering samples pushed by an ISR, such as ADC readings, for the main loop to read
+///
+/// The filter is chosen when the cell is created. A moving average keeps the last `N` samples
+/// and returns their mean, while an IIR filter only keeps a fixed-point accumulator (the window
+/// is unused) and follows new samples with a time constant of about `N` samples. Until `N`
+/// samples were pushed, the moving average is the mean of the samples so far, and the IIR filter
+/// starts out at its first sample.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::FilterCell;
+/// #
+/// static CURRENT: FilterCell<4> = FilterCell::moving_average();
+///
+/// // in the ADC ISR
+/// critical_section::with(|cs| {
+///     for sample in [100, 104, 96, 100, 120] {
+///         CURRENT.push(cs, sample);
+///     }
+/// });
+///
+/// // in the main loop
+/// assert_eq!(critical_section::with(|cs| CURRENT.value(cs)), Some(105));
+/// ```
+pub struct FilterCell<const N: usize> {
+    state: Mutex<RefCell<State<N>>>,
+    filter: Filter,
+}
+
+impl<const N: usize> FilterCell<N> {
+    /// Creates a new object computing `filter`, with no samples pushed
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is 0.
+    pub const fn new(filter: Filter) -> Self {
+        assert!(N > 0, "a FilterCell needs a length of at least 1");
+        Self {
+            state: Mutex::new(RefCell::new(State {
+                window: [0; N],
+                next: 0,
+                len: 0,
+                acc: 0,
+            })),
+            filter,
+        }
+    }
+
+    /// Creates a new object computing the mean of the last `N` samples
+    pub const fn moving_average() -> Self {
+        Self::new(Filter::MovingAverage)
+    }
+
+    /// Creates a new object computing an IIR filter with a smoothing factor of `1 / N`
+    pub const fn iir() -> Self {
+        Self::new(Filter::Iir)
+    }
+
+    /// Returns the filter this object computes
+    pub const fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    /// Feeds `sample` into the filter
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter state is currently borrowed.
+    pub fn push(&self, cs: CriticalSection, sample: i32) {
+        let mut state = self.state.borrow_ref_mut(cs);
+        let n = N as i64;
+        match self.filter {
+            Filter::MovingAverage => {
+                let next = state.next;
+                if state.len == N {
+                    state.acc -= i64::from(state.window[next]);
+                } else {
+                    state.len += 1;
+                }
+                state.window[next] = sample;
+                state.acc += i64::from(sample);
+                state.next = (next + 1) % N;
+            }
+            Filter::Iir if state.len == 0 => {
+                state.len = 1;
+                state.acc = i64::from(sample) * n;
+            }
+            Filter::Iir => state.acc += i64::from(sample) - state.acc / n,
+        }
+    }
+
+    /// Returns the filtered value, or None if no samples were pushed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter state is currently mutably borrowed.
+    pub fn value(&self, cs: CriticalSection) -> Option<i32> {
+        let state = self.state.borrow_ref(cs);
+        let divisor = match self.filter {
+            Filter::MovingAverage => state.len as i64,
+            Filter::Iir => N as i64,
+        };
+        // the mean of `i32` samples always fits in an `i32`
+        (state.len > 0).then(|| (state.acc / divisor) as i32)
+    }
+
+    /// Forgets every sample pushed so far
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter state is currently borrowed.
+    pub fn reset(&self, cs: CriticalSection) {
+        let mut state = self.state.borrow_ref_mut(cs);
+        state.next = 0;
+        state.len = 0;
+        state.acc = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iir_converges_on_step() {
+        critical_section::with(|cs| {
+            let filter: FilterCell<4> = FilterCell::iir();
+            assert_eq!(filter.value(cs), None);
+            filter.push(cs, 0);
+            filter.push(cs, 400);
+            assert_eq!(filter.value(cs), Some(100));
+            filter.push(cs, 400);
+            assert_eq!(filter.value(cs), Some(175));
+            for _ in 0..100 {
+                filter.push(cs, 400);
+            }
+            assert_eq!(filter.value(cs), Some(400));
+
+            filter.reset(cs);
+            filter.push(cs, -8);
+            assert_eq!(filter.value(cs), Some(-8));
+        });
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{BorrowError, StaticRefCell};
+use core::fmt;
+
+impl<T: fmt::Debug> fmt::Debug for StaticRefCell<T> {
+    /// Formats the stored value, or the reason it can't be borrowed
+    ///
+    /// This enters a critical section to borrow the stored value, so it never panics, even if the
+    /// value is currently mutably borrowed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        critical_section::with(|cs| match self.try_borrow_ref(cs) {
+            Ok(value) => f.debug_tuple("StaticRefCell").field(&*value).finish(),
+            Err(BorrowError::Uninitialized) => f.write_str("StaticRefCell(Uninit)"),
+            Err(BorrowError::AlreadyBorrowed) => f.write_str("StaticRefCell(Borrowed)"),
+            Err(BorrowError::Poisoned) => f.write_str("StaticRefCell(Poisoned)"),
+        })
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: defmt::Format> defmt::Format for StaticRefCell<T> {
+    /// Formats the stored value, or the reason it can't be borrowed
+    ///
+    /// This enters a critical section to borrow the stored value.
+    fn format(&self, f: defmt::Formatter) {
+        critical_section::with(|cs| match self.try_borrow_ref(cs) {
+            Ok(value) => defmt::write!(f, "StaticRefCell(Init({}))", *value),
+            Err(BorrowError::Uninitialized) => defmt::write!(f, "StaticRefCell(Uninit)"),
+            Err(BorrowError::AlreadyBorrowed) => defmt::write!(f, "StaticRefCell(Borrowed)"),
+            Err(BorrowError::Poisoned) => defmt::write!(f, "StaticRefCell(Poisoned)"),
+        })
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T: ufmt::uDebug> ufmt::uDebug for StaticRefCell<T> {
+    /// Formats the stored value, or the reason it can't be borrowed
+    ///
+    /// This enters a critical section to borrow the stored value.
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        critical_section::with(|cs| match self.try_borrow_ref(cs) {
+            Ok(value) => f.debug_tuple("StaticRefCell")?.field(&*value)?.finish(),
+            Err(BorrowError::Uninitialized) => f.write_str("StaticRefCell(Uninit)"),
+            Err(BorrowError::AlreadyBorrowed) => f.write_str("StaticRefCell(Borrowed)"),
+            Err(BorrowError::Poisoned) => f.write_str("StaticRefCell(Poisoned)"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    struct Buffer {
+        bytes: [u8; 32],
+        len: usize,
+    }
+
+    impl Buffer {
+        fn new() -> Self {
+            Self {
+                bytes: [0; 32],
+                len: 0,
+            }
+        }
+
+        fn push(&mut self, s: &str) -> Result<(), ()> {
+            let end = self.len + s.len();
+            self.bytes
+                .get_mut(self.len..end)
+                .ok_or(())?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+
+        fn as_bytes(&self) -> &[u8] {
+            &self.bytes[..self.len]
+        }
+    }
+
+    impl Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.push(s).map_err(|_| fmt::Error)
+        }
+    }
+
+    #[cfg(feature = "ufmt")]
+    impl ufmt::uWrite for Buffer {
+        type Error = ();
+
+        fn write_str(&mut self, s: &str) -> Result<(), ()> {
+            self.push(s)
+        }
+    }
+
+    #[test]
+    fn debug_reports_state() {
+        let cell: StaticRefCell<u8> = StaticRefCell::new();
+        let mut buffer = Buffer::new();
+        write!(buffer, "{:?}", cell).unwrap();
+        assert_eq!(buffer.as_bytes(), b"StaticRefCell(Uninit)");
+
+        critical_section::with(|cs| {
+            cell.init(cs, 7);
+            let mut buffer = Buffer::new();
+            cell.borrow_mut(cs, |_| write!(buffer, "{:?}", cell).unwrap(), || {});
+            assert_eq!(buffer.as_bytes(), b"StaticRefCell(Borrowed)");
+        });
+
+        let mut buffer = Buffer::new();
+        write!(buffer, "{:?}", cell).unwrap();
+        assert_eq!(buffer.as_bytes(), b"StaticRefCell(7)");
+    }
+
+    #[cfg(feature = "ufmt")]
+    #[test]
+    fn udebug_reports_state() {
+        let cell: StaticRefCell<u8> = StaticRefCell::new();
+        let mut buffer = Buffer::new();
+        ufmt::uwrite!(buffer, "{:?}", cell).unwrap();
+        assert_eq!(buffer.as_bytes(), b"StaticRefCell(Uninit)");
+
+        critical_section::with(|cs| cell.init(cs, 7));
+        let mut buffer = Buffer::new();
+        ufmt::uwrite!(buffer, "{:?}", cell).unwrap();
+        assert_eq!(buffer.as_bytes(), b"StaticRefCell(7)");
+    }
+}
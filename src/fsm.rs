@@ -0,0 +1,151 @@
+use core::cell::RefCell;
+use core::fmt;
+use critical_section::{CriticalSection, Mutex};
+
+/// The outcome of a transition function passed to `FsmCell::transition`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition<S> {
+    /// Move to the given state, running the exit and entry hooks
+    Next(S),
+    /// Stay in the current state, without running any hooks
+    Stay,
+    /// The event isn't valid in the current state, so the state is left unchanged
+    Invalid,
+}
+
+/// The error returned by `FsmCell::transition` when the transition function returned
+/// `Transition::Invalid`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidTransition;
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid state transition")
+    }
+}
+
+/// A state machine whose state is shared between ISRs and the main loop
+///
+/// All changes go through `transition`, which decides the next state from the current one, so
+/// the transitions of a protocol can be written (and tested) as a single function. The optional
+/// exit and entry hooks run on every change of state, inside the critical section, with the old
+/// and new state respectively.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{FsmCell, InvalidTransition, Transition};
+/// #
+/// #[derive(Debug, Clone, Copy, PartialEq)]
+/// enum Link {
+///     Idle,
+///     Receiving { len: u8 },
+///     Done,
+/// }
+///
+/// fn on_byte(state: &Link, byte: u8) -> Transition<Link> {
+///     match (*state, byte) {
+///         (Link::Idle, b'$') => Transition::Next(Link::Receiving { len: 0 }),
+///         (Link::Idle, _) => Transition::Stay,
+///         (Link::Receiving { .. }, b'\n') => Transition::Next(Link::Done),
+///         (Link::Receiving { len }, _) => Transition::Next(Link::Receiving { len: len + 1 }),
+///         (Link::Done, _) => Transition::Invalid,
+///     }
+/// }
+///
+/// static LINK: FsmCell<Link> = FsmCell::new(Link::Idle);
+///
+/// // in the UART ISR
+/// critical_section::with(|cs| {
+///     for byte in *b"$ok\n" {
+///         LINK.transition(cs, |state| on_byte(state, byte)).unwrap();
+///     }
+///     assert_eq!(LINK.get(cs), Link::Done);
+///     // a frame must be handled before the next one starts
+///     assert_eq!(LINK.transition(cs, |state| on_byte(state, b'$')), Err(InvalidTransition));
+/// });
+/// ```
+pub struct FsmCell<S> {
+    state: Mutex<RefCell<S>>,
+    on_exit: Option<fn(&S)>,
+    on_entry: Option<fn(&S)>,
+}
+
+impl<S> FsmCell<S> {
+    /// Creates a new state machine in the `initial` state, with no hooks
+    pub const fn new(initial: S) -> Self {
+        Self {
+            state: Mutex::new(RefCell::new(initial)),
+            on_exit: None,
+            on_entry: None,
+        }
+    }
+
+    /// Sets a hook to call with the old state whenever a transition leaves it
+    pub const fn on_exit(mut self, hook: fn(&S)) -> Self {
+        self.on_exit = Some(hook);
+        self
+    }
+
+    /// Sets a hook to call with the new state whenever a transition enters it
+    pub const fn on_entry(mut self, hook: fn(&S)) -> Self {
+        self.on_entry = Some(hook);
+        self
+    }
+
+    /// Computes the next state from the current one with `func`, and moves to it
+    ///
+    /// Returns true if the state changed, false if `func` returned `Transition::Stay`, or an
+    /// error (leaving the state unchanged) if it returned `Transition::Invalid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from `func` or a hook, since the state is borrowed while they run.
+    pub fn transition<F: FnOnce(&S) -> Transition<S>>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<bool, InvalidTransition> {
+        let mut state = self.state.borrow_ref_mut(cs);
+        match func(&state) {
+            Transition::Next(next) => {
+                if let Some(hook) = self.on_exit {
+                    hook(&state);
+                }
+                *state = next;
+                if let Some(hook) = self.on_entry {
+                    hook(&state);
+                }
+                Ok(true)
+            }
+            Transition::Stay => Ok(false),
+            Transition::Invalid => Err(InvalidTransition),
+        }
+    }
+
+    /// Passes an immutable reference to the current state in `func` and returns the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a transition.
+    pub fn with<Y, F: FnOnce(&S) -> Y>(&self, cs: CriticalSection, func: F) -> Y {
+        func(&self.state.borrow_ref(cs))
+    }
+}
+
+impl<S: Copy> FsmCell<S> {
+    /// Returns a copy of the current state
+    ///
+    /// # Panics
+    ///
+    /// Panics if called during a transition.
+    pub fn get(&self, cs: CriticalSection) -> S {
+        *self.state.borrow_ref(cs)
+    }
+}
+
+impl<S: Default> Default for FsmCell<S> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
@@ -0,0 +1,193 @@
+use crate::StaticRefCell;
+use critical_section::CriticalSection;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A tuple of references to static cells, which can form a `CellGroup`
+///
+/// This trait is sealed and implemented for tuples of 1 to 8 `&'static StaticRefCell`s.
+pub trait Members: sealed::Sealed + Copy {
+    /// The tuple of the values stored in the cells
+    type Values;
+    /// The tuple of immutable references to the values stored in the cells
+    type Refs<'a>;
+    /// The tuple of mutable references to the values stored in the cells
+    type RefsMut<'a>;
+    #[doc(hidden)]
+    fn init(self, cs: CriticalSection, values: Self::Values);
+    #[doc(hidden)]
+    fn is_ready(self, cs: CriticalSection) -> bool;
+    #[doc(hidden)]
+    fn deinit(self, cs: CriticalSection);
+    #[doc(hidden)]
+    fn borrow<Y, F1, F2>(self, cs: CriticalSection, func: F1, none_func: F2) -> Y
+    where
+        F1: for<'a> FnOnce(Self::Refs<'a>) -> Y,
+        F2: FnOnce() -> Y;
+    #[doc(hidden)]
+    fn borrow_mut<Y, F1, F2>(self, cs: CriticalSection, func: F1, none_func: F2) -> Y
+    where
+        F1: for<'a> FnOnce(Self::RefsMut<'a>) -> Y,
+        F2: FnOnce() -> Y;
+}
+
+macro_rules! impl_members {
+    ($($cell:ident: $ty:ident),+) => {
+        impl<$($ty: 'static),+> sealed::Sealed for ($(&'static StaticRefCell<$ty>,)+) {}
+
+        impl<$($ty: 'static),+> Members for ($(&'static StaticRefCell<$ty>,)+) {
+            type Values = ($($ty,)+);
+            type Refs<'a> = ($(&'a $ty,)+);
+            type RefsMut<'a> = ($(&'a mut $ty,)+);
+
+            #[allow(non_snake_case)]
+            fn init(self, cs: CriticalSection, values: Self::Values) {
+                let ($($cell,)+) = self;
+                let ($($ty,)+) = values;
+                $($cell.init(cs, $ty);)+
+            }
+
+            fn is_ready(self, cs: CriticalSection) -> bool {
+                let ($($cell,)+) = self;
+                true $(&& $cell.is_initialized(cs))+
+            }
+
+            fn deinit(self, cs: CriticalSection) {
+                let ($($cell,)+) = self;
+                $($cell.deinit(cs);)+
+            }
+
+            fn borrow<Y, F1, F2>(self, cs: CriticalSection, func: F1, none_func: F2) -> Y
+            where
+                F1: for<'a> FnOnce(Self::Refs<'a>) -> Y,
+                F2: FnOnce() -> Y,
+            {
+                let ($($cell,)+) = self;
+                match ($($cell.borrow_ref(cs),)+) {
+                    ($(Some($cell),)+) => func(($(&*$cell,)+)),
+                    _ => none_func(),
+                }
+            }
+
+            fn borrow_mut<Y, F1, F2>(self, cs: CriticalSection, func: F1, none_func: F2) -> Y
+            where
+                F1: for<'a> FnOnce(Self::RefsMut<'a>) -> Y,
+                F2: FnOnce() -> Y,
+            {
+                let ($($cell,)+) = self;
+                match ($($cell.borrow_ref_mut(cs),)+) {
+                    ($(Some(mut $cell),)+) => func(($(&mut *$cell,)+)),
+                    _ => none_func(),
+                }
+            }
+        }
+    };
+}
+
+impl_members!(a: A);
+impl_members!(a: A, b: B);
+impl_members!(a: A, b: B, c: C);
+impl_members!(a: A, b: B, c: C, d: D);
+impl_members!(a: A, b: B, c: C, d: D, e: E);
+impl_members!(a: A, b: B, c: C, d: D, e: E, f: F);
+impl_members!(a: A, b: B, c: C, d: D, e: E, f: F, g: G);
+impl_members!(a: A, b: B, c: C, d: D, e: E, f: F, g: G, h: H);
+
+/// Several static cells that are initialized together and only used once all of them are
+///
+/// `init` initializes every member from a tuple of values in one critical section, `is_ready`
+/// reports whether all of them are initialized, and `borrow` and `borrow_mut` pass all of the
+/// values at once, calling a single `none_func` if any member is still uninitialized. This keeps
+/// the set of cells an ISR depends on, such as the peripherals it drives, from being half set up.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{CellGroup, StaticRefCell};
+/// #
+/// # struct Led;
+/// # struct Timer(u16);
+/// static LED: StaticRefCell<Led> = StaticRefCell::new();
+/// static TIMER: StaticRefCell<Timer> = StaticRefCell::new();
+/// static BLINKER: CellGroup<(&StaticRefCell<Led>, &StaticRefCell<Timer>)> =
+///     CellGroup::new((&LED, &TIMER));
+///
+/// critical_section::with(|cs| {
+///     assert!(!BLINKER.is_ready(cs));
+///     BLINKER.init(cs, (Led, Timer(500)));
+///     assert!(BLINKER.is_ready(cs));
+/// });
+///
+/// // in the timer ISR
+/// let period = critical_section::with(|cs| {
+///     BLINKER.borrow_mut(cs, |(_led, timer)| {
+///         timer.0 += 1;
+///         timer.0
+///     }, || 0)
+/// });
+/// assert_eq!(period, 501);
+/// ```
+pub struct CellGroup<C>(C);
+
+impl<C> CellGroup<C> {
+    /// Creates a new group of the cells in the tuple `cells`
+    pub const fn new(cells: C) -> Self {
+        Self(cells)
+    }
+}
+
+impl<C: Members> CellGroup<C> {
+    /// Sets the stored value of every member, from the tuple `values`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data of any member is currently borrowed.
+    pub fn init(&self, cs: CriticalSection, values: C::Values) {
+        self.0.init(cs, values);
+    }
+
+    /// Returns true if every member is initialized
+    pub fn is_ready(&self, cs: CriticalSection) -> bool {
+        self.0.is_ready(cs)
+    }
+
+    /// Drops the stored value of every member, returning them to the uninitialized state
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data of any member is currently borrowed.
+    pub fn deinit(&self, cs: CriticalSection) {
+        self.0.deinit(cs);
+    }
+
+    /// Passes a tuple of immutable references to the values stored in the members in `func` and
+    /// returns the result, or returns the result of `none_func` if any member is uninitialized
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data of any member is currently mutably borrowed.
+    pub fn borrow<Y, F1, F2>(&self, cs: CriticalSection, func: F1, none_func: F2) -> Y
+    where
+        F1: for<'a> FnOnce(C::Refs<'a>) -> Y,
+        F2: FnOnce() -> Y,
+    {
+        self.0.borrow(cs, func, none_func)
+    }
+
+    /// Passes a tuple of mutable references to the values stored in the members in `func` and
+    /// returns the result, or returns the result of `none_func` if any member is uninitialized
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data of any member is currently borrowed, including when the same
+    /// cell is a member twice.
+    pub fn borrow_mut<Y, F1, F2>(&self, cs: CriticalSection, func: F1, none_func: F2) -> Y
+    where
+        F1: for<'a> FnOnce(C::RefsMut<'a>) -> Y,
+        F2: FnOnce() -> Y,
+    {
+        self.0.borrow_mut(cs, func, none_func)
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{BorrowError, StaticRefCell};
+use critical_section::CriticalSection;
+
+/// The main-context handle to a `StaticRefCell`, created by `StaticRefCell::split`
+///
+/// This handle can set up and tear down the stored value, in addition to borrowing it. It is not
+/// `Clone`, so as long as the cell itself is kept private it can only end up where it is handed to.
+pub struct MainHandle<T: 'static> {
+    cell: &'static StaticRefCell<T>,
+}
+
+/// The interrupt-context handle to a `StaticRefCell`, created by `StaticRefCell::split`
+///
+/// This handle can only borrow the stored value, so code that only holds an `IsrHandle` cannot
+/// accidentally initialize or remove it. It is `Copy` and can be created in a const context, so it
+/// can be stored in a static for use in an ISR.
+pub struct IsrHandle<T: 'static> {
+    cell: &'static StaticRefCell<T>,
+}
+
+impl<T> Clone for IsrHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for IsrHandle<T> {}
+
+impl<T> StaticRefCell<T> {
+    /// Splits a static cell into a main-context handle and an interrupt-context handle
+    ///
+    /// Keeping the cell private and only exposing the handles encodes which context is allowed to
+    /// initialize the cell in the types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// mod shared {
+    ///     # use embedded_static_ref_cell::{IsrHandle, MainHandle, StaticRefCell};
+    ///     static COUNTER: StaticRefCell<u32> = StaticRefCell::new();
+    ///
+    ///     pub static COUNTER_ISR: IsrHandle<u32> = COUNTER.split().1;
+    ///
+    ///     pub fn counter_main() -> MainHandle<u32> {
+    ///         COUNTER.split().0
+    ///     }
+    /// }
+    ///
+    /// let counter = shared::counter_main();
+    /// critical_section::with(|cs| counter.init(cs, 0));
+    ///
+    /// // in the ISR, `COUNTER_ISR.init` does not exist
+    /// critical_section::with(|cs| shared::COUNTER_ISR.borrow_mut(cs, |count| *count += 1, || {}));
+    ///
+    /// assert_eq!(critical_section::with(|cs| counter.take(cs)), Some(1));
+    /// ```
+    pub const fn split(&'static self) -> (MainHandle<T>, IsrHandle<T>) {
+        (MainHandle { cell: self }, IsrHandle { cell: self })
+    }
+}
+
+impl<T> MainHandle<T> {
+    /// Returns an interrupt-context handle to the same cell
+    pub const fn isr_handle(&self) -> IsrHandle<T> {
+        IsrHandle { cell: self.cell }
+    }
+
+    /// See `StaticRefCell::init`
+    #[track_caller]
+    pub fn init(&self, cs: CriticalSection, value: T) {
+        self.cell.init(cs, value)
+    }
+
+    /// See `StaticRefCell::replace`
+    #[track_caller]
+    pub fn replace(&self, cs: CriticalSection, value: T) -> Option<T> {
+        self.cell.replace(cs, value)
+    }
+
+    /// See `StaticRefCell::take`
+    #[track_caller]
+    pub fn take(&self, cs: CriticalSection) -> Option<T> {
+        self.cell.take(cs)
+    }
+
+    /// See `StaticRefCell::deinit`
+    #[track_caller]
+    pub fn deinit(&self, cs: CriticalSection) {
+        self.cell.deinit(cs)
+    }
+
+    /// See `StaticRefCell::is_initialized`
+    pub fn is_initialized(&self, cs: CriticalSection) -> bool {
+        self.cell.is_initialized(cs)
+    }
+
+    /// See `StaticRefCell::borrow`
+    #[track_caller]
+    pub fn borrow<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        self.cell.borrow(cs, func, none_func)
+    }
+
+    /// See `StaticRefCell::borrow_mut`
+    #[track_caller]
+    pub fn borrow_mut<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        self.cell.borrow_mut(cs, func, none_func)
+    }
+}
+
+impl<T> IsrHandle<T> {
+    /// See `StaticRefCell::is_initialized`
+    pub fn is_initialized(&self, cs: CriticalSection) -> bool {
+        self.cell.is_initialized(cs)
+    }
+
+    /// See `StaticRefCell::borrow`
+    #[track_caller]
+    pub fn borrow<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        self.cell.borrow(cs, func, none_func)
+    }
+
+    /// See `StaticRefCell::borrow_mut`
+    #[track_caller]
+    pub fn borrow_mut<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        self.cell.borrow_mut(cs, func, none_func)
+    }
+
+    /// See `StaticRefCell::try_borrow`
+    #[track_caller]
+    pub fn try_borrow<Y, F: FnOnce(&T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, BorrowError> {
+        self.cell.try_borrow(cs, func)
+    }
+
+    /// See `StaticRefCell::try_borrow_mut`
+    #[track_caller]
+    pub fn try_borrow_mut<Y, F: FnOnce(&mut T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, BorrowError> {
+        self.cell.try_borrow_mut(cs, func)
+    }
+}
@@ -0,0 +1,171 @@
+use core::cell::Cell;
+use critical_section::{CriticalSection, Mutex};
+
+/// A slot holding a handler function that is registered at runtime and called from an ISR
+///
+/// The handler type `F` is a function pointer, `fn()` by default. With a context type, such as
+/// `fn(&mut Ctx)`, the ISR passes the context in `dispatch_with`. This lets a library hook an
+/// interrupt without owning its `#[interrupt]` function: the application's ISR only needs to call
+/// `dispatch`.
+///
+/// The handler is copied out of the slot before it is called, so it can register or unregister
+/// handlers itself.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{HandlerSlot, StaticPrimitiveCell};
+/// #
+/// static TIMER0_HANDLER: HandlerSlot = HandlerSlot::new();
+/// static TICKS: StaticPrimitiveCell<u8> = StaticPrimitiveCell::new(0);
+///
+/// fn on_tick() {
+///     TICKS.fetch_add(1);
+/// }
+///
+/// // the ISR body
+/// fn timer0_isr() {
+///     critical_section::with(|cs| TIMER0_HANDLER.dispatch(cs));
+/// }
+///
+/// timer0_isr();
+/// critical_section::with(|cs| TIMER0_HANDLER.register(cs, on_tick));
+/// timer0_isr();
+/// assert_eq!(TICKS.load(), 1);
+/// ```
+pub struct HandlerSlot<F: Copy = fn()>(Mutex<Cell<Option<F>>>);
+
+impl<F: Copy> HandlerSlot<F> {
+    /// Creates a new slot with no handler registered
+    pub const fn new() -> Self {
+        Self(Mutex::new(Cell::new(None)))
+    }
+
+    /// Registers `handler`, returning the handler it replaced (if any)
+    pub fn register(&self, cs: CriticalSection, handler: F) -> Option<F> {
+        self.0.borrow(cs).replace(Some(handler))
+    }
+
+    /// Removes the registered handler and returns it, or returns None if there wasn't one
+    pub fn unregister(&self, cs: CriticalSection) -> Option<F> {
+        self.0.borrow(cs).take()
+    }
+
+    /// Returns the registered handler, or None if there isn't one
+    pub fn get(&self, cs: CriticalSection) -> Option<F> {
+        self.0.borrow(cs).get()
+    }
+
+    /// Returns true if a handler is registered
+    pub fn is_registered(&self, cs: CriticalSection) -> bool {
+        self.get(cs).is_some()
+    }
+}
+
+impl HandlerSlot<fn()> {
+    /// Calls the registered handler, or returns false if there isn't one
+    pub fn dispatch(&self, cs: CriticalSection) -> bool {
+        self.get(cs).map(|handler| handler()).is_some()
+    }
+}
+
+impl<C> HandlerSlot<fn(&mut C)> {
+    /// Calls the registered handler with `context`, or returns false if there isn't one
+    pub fn dispatch_with(&self, cs: CriticalSection, context: &mut C) -> bool {
+        self.get(cs).map(|handler| handler(context)).is_some()
+    }
+}
+
+impl<F: Copy> Default for HandlerSlot<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A table of `N` handler slots, such as one per interrupt line of a shared ISR
+///
+/// This behaves like an array of `HandlerSlot`s in one static, indexed by a number the ISR decides
+/// on (for example, the pin that triggered a pin-change interrupt). Indexes that are out of bounds
+/// behave like empty slots.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::HandlerTable;
+/// #
+/// static PIN_HANDLERS: HandlerTable<8, fn(&mut u8)> = HandlerTable::new();
+///
+/// fn count_presses(presses: &mut u8) {
+///     *presses += 1;
+/// }
+///
+/// critical_section::with(|cs| PIN_HANDLERS.register(cs, 3, count_presses));
+///
+/// // in the pin-change ISR, after finding out which pins changed
+/// let mut presses = 0;
+/// critical_section::with(|cs| {
+///     for pin in [3, 4, 3] {
+///         PIN_HANDLERS.dispatch_with(cs, pin, &mut presses);
+///     }
+/// });
+/// assert_eq!(presses, 2);
+/// ```
+pub struct HandlerTable<const N: usize, F: Copy = fn()>(Mutex<[Cell<Option<F>>; N]>);
+
+impl<const N: usize, F: Copy> HandlerTable<N, F> {
+    /// Creates a new table with no handlers registered
+    pub const fn new() -> Self {
+        Self(Mutex::new([const { Cell::new(None) }; N]))
+    }
+
+    /// Returns the number of slots, `N`
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if `N` is zero
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Registers `handler` at `index`, returning the handler it replaced (if any)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn register(&self, cs: CriticalSection, index: usize, handler: F) -> Option<F> {
+        self.0.borrow(cs)[index].replace(Some(handler))
+    }
+
+    /// Removes the handler at `index` and returns it, or returns None if there wasn't one
+    pub fn unregister(&self, cs: CriticalSection, index: usize) -> Option<F> {
+        self.0.borrow(cs).get(index)?.take()
+    }
+
+    /// Returns the handler at `index`, or None if there isn't one
+    pub fn get(&self, cs: CriticalSection, index: usize) -> Option<F> {
+        self.0.borrow(cs).get(index)?.get()
+    }
+}
+
+impl<const N: usize> HandlerTable<N, fn()> {
+    /// Calls the handler at `index`, or returns false if there isn't one
+    pub fn dispatch(&self, cs: CriticalSection, index: usize) -> bool {
+        self.get(cs, index).map(|handler| handler()).is_some()
+    }
+}
+
+impl<const N: usize, C> HandlerTable<N, fn(&mut C)> {
+    /// Calls the handler at `index` with `context`, or returns false if there isn't one
+    pub fn dispatch_with(&self, cs: CriticalSection, index: usize, context: &mut C) -> bool {
+        self.get(cs, index)
+            .map(|handler| handler(context))
+            .is_some()
+    }
+}
+
+impl<const N: usize, F: Copy> Default for HandlerTable<N, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,123 @@
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+struct Ring<T, const N: usize> {
+    buffer: [Option<T>; N],
+    /// The index the next value is written to
+    next: usize,
+    len: usize,
+}
+
+/// A cell that remembers the last `N` values set, such as the recent samples written by an ISR
+///
+/// Each `set` records the value, replacing the oldest one once `N` are stored. This keeps the
+/// inputs of a digital filter at hand, or the readings leading up to an intermittent sensor
+/// glitch.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::HistoryCell;
+/// #
+/// static TEMPERATURE: HistoryCell<i16, 3> = HistoryCell::new();
+///
+/// // in the sensor ISR
+/// critical_section::with(|cs| {
+///     for reading in [210, 212, 999, 213] {
+///         TEMPERATURE.set(cs, reading);
+///     }
+/// });
+///
+/// // in the main loop
+/// critical_section::with(|cs| {
+///     assert_eq!(TEMPERATURE.latest(cs), Some(213));
+///     assert_eq!(TEMPERATURE.get(cs, 1), Some(999));
+///
+///     let mut samples = [0; 3];
+///     assert_eq!(TEMPERATURE.copy_to(cs, &mut samples), 3);
+///     assert_eq!(samples, [212, 999, 213]);
+/// });
+/// ```
+pub struct HistoryCell<T: Copy, const N: usize>(Mutex<RefCell<Ring<T, N>>>);
+
+impl<T: Copy, const N: usize> HistoryCell<T, N> {
+    /// Creates a new object with no values recorded
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(Ring {
+            buffer: [const { None }; N],
+            next: 0,
+            len: 0,
+        })))
+    }
+
+    /// Returns the number of values this object remembers, `N`
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Records `value` as the latest value, forgetting the oldest one if `N` are already stored
+    pub fn set(&self, cs: CriticalSection, value: T) {
+        if N == 0 {
+            return;
+        }
+        let mut ring = self.0.borrow_ref_mut(cs);
+        let next = ring.next;
+        ring.buffer[next] = Some(value);
+        ring.next = (next + 1) % N;
+        ring.len = (ring.len + 1).min(N);
+    }
+
+    /// Returns the value set most recently, or None if no values are recorded
+    pub fn latest(&self, cs: CriticalSection) -> Option<T> {
+        self.get(cs, 0)
+    }
+
+    /// Returns the value set `age` values before the latest one (so `get(cs, 0)` is the latest),
+    /// or None if it isn't remembered
+    pub fn get(&self, cs: CriticalSection, age: usize) -> Option<T> {
+        let ring = self.0.borrow_ref(cs);
+        if age >= ring.len {
+            return None;
+        }
+        ring.buffer[(ring.next + N - 1 - age) % N]
+    }
+
+    /// Returns the number of values remembered
+    pub fn len(&self, cs: CriticalSection) -> usize {
+        self.0.borrow_ref(cs).len
+    }
+
+    /// Returns true if no values are recorded
+    pub fn is_empty(&self, cs: CriticalSection) -> bool {
+        self.len(cs) == 0
+    }
+
+    /// Forgets every recorded value
+    pub fn clear(&self, cs: CriticalSection) {
+        let mut ring = self.0.borrow_ref_mut(cs);
+        ring.buffer = [None; N];
+        ring.next = 0;
+        ring.len = 0;
+    }
+
+    /// Copies the most recent values into `out`, from oldest to newest, and returns how many were
+    /// copied
+    ///
+    /// If `out` is shorter than the number of values remembered, only the newest values that fit
+    /// are copied.
+    pub fn copy_to(&self, cs: CriticalSection, out: &mut [T]) -> usize {
+        let count = self.len(cs).min(out.len());
+        for (age, slot) in (0..count).rev().zip(out.iter_mut()) {
+            if let Some(value) = self.get(cs, age) {
+                *slot = value;
+            }
+        }
+        count
+    }
+}
+
+impl<T: Copy, const N: usize> Default for HistoryCell<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
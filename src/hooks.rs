@@ -0,0 +1,158 @@
+use crate::BorrowError;
+use core::cell::Cell;
+use core::panic::Location;
+use critical_section::{CriticalSection, Mutex};
+
+/// A hook, called with the critical section of the access, the address of the cell, and the
+/// location in the application's code where the cell was accessed
+pub type Hook = fn(CriticalSection, usize, &'static Location<'static>);
+
+/// Callbacks for lifecycle events of every `StaticRefCell`, installed with `set_hooks`
+///
+/// Hooks run inside the critical section of the access, so they should be short, such as
+/// toggling a debug pin or emitting an RTT event. The address passed to them identifies the cell
+/// (compare it with `&CELL as *const _ as usize`). Hooks should not access the cell that triggered
+/// them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Hooks {
+    /// Called when a value is stored with `init` (or `init_default`, `set`, or `get_or_init`)
+    pub on_init: Option<Hook>,
+    /// Called when the stored value is borrowed immutably
+    pub on_borrow: Option<Hook>,
+    /// Called when the stored value is borrowed mutably
+    pub on_borrow_mut: Option<Hook>,
+    /// Called when a borrow finds the cell uninitialized (or poisoned)
+    pub on_uninit_access: Option<Hook>,
+}
+
+static HOOKS: Mutex<Cell<Hooks>> = Mutex::new(Cell::new(Hooks {
+    on_init: None,
+    on_borrow: None,
+    on_borrow_mut: None,
+    on_uninit_access: None,
+}));
+
+/// Installs `hooks` for every `StaticRefCell`, replacing the ones installed before (requires the
+/// `hooks` feature)
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{set_hooks, Hooks, StaticRefCell};
+/// # use critical_section::CriticalSection;
+/// # use core::panic::Location;
+/// # use core::sync::atomic::{AtomicUsize, Ordering};
+/// static LAST_MISS: AtomicUsize = AtomicUsize::new(0);
+///
+/// fn record_miss(_cs: CriticalSection, cell: usize, location: &'static Location<'static>) {
+///     // e.g. set a debug pin, or send `cell` and `location.line()` over RTT
+///     assert!(location.file().ends_with(".rs"));
+///     LAST_MISS.store(cell, Ordering::Relaxed);
+/// }
+///
+/// static CONFIG: StaticRefCell<u8> = StaticRefCell::new();
+///
+/// critical_section::with(|cs| {
+///     set_hooks(cs, Hooks { on_uninit_access: Some(record_miss), ..Hooks::default() });
+///     CONFIG.borrow(cs, |_| {}, || {});
+/// });
+/// assert_eq!(LAST_MISS.load(Ordering::Relaxed), &CONFIG as *const _ as usize);
+/// ```
+pub fn set_hooks(cs: CriticalSection, hooks: Hooks) {
+    HOOKS.borrow(cs).set(hooks);
+}
+
+fn call(
+    cs: CriticalSection,
+    cell: usize,
+    select: fn(&Hooks) -> Option<Hook>,
+    location: &'static Location<'static>,
+) {
+    if let Some(hook) = select(&HOOKS.borrow(cs).get()) {
+        hook(cs, cell, location);
+    }
+}
+
+pub(crate) fn init(cs: CriticalSection, cell: usize, location: &'static Location<'static>) {
+    call(cs, cell, |hooks| hooks.on_init, location);
+}
+
+pub(crate) fn borrow(cs: CriticalSection, cell: usize, location: &'static Location<'static>) {
+    call(cs, cell, |hooks| hooks.on_borrow, location);
+}
+
+pub(crate) fn borrow_mut(cs: CriticalSection, cell: usize, location: &'static Location<'static>) {
+    call(cs, cell, |hooks| hooks.on_borrow_mut, location);
+}
+
+pub(crate) fn miss(cs: CriticalSection, cell: usize, location: &'static Location<'static>) {
+    call(cs, cell, |hooks| hooks.on_uninit_access, location);
+}
+
+/// Reports a borrow through a guard method, which either found a value (`hit`) or didn't
+pub(crate) fn found(
+    cs: CriticalSection,
+    cell: usize,
+    mutable: bool,
+    hit: bool,
+    location: &'static Location<'static>,
+) {
+    match (hit, mutable) {
+        (true, false) => borrow(cs, cell, location),
+        (true, true) => borrow_mut(cs, cell, location),
+        (false, _) => miss(cs, cell, location),
+    }
+}
+
+/// Reports the outcome of a fallible borrow
+pub(crate) fn record<V>(
+    cs: CriticalSection,
+    cell: usize,
+    mutable: bool,
+    result: &Result<V, BorrowError>,
+    location: &'static Location<'static>,
+) {
+    if !matches!(result, Err(BorrowError::AlreadyBorrowed)) {
+        found(cs, cell, mutable, result.is_ok(), location);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StaticRefCell;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static CELL: StaticRefCell<u8> = StaticRefCell::new();
+    static MISS_LINE: AtomicU32 = AtomicU32::new(0);
+
+    fn record_miss(_cs: CriticalSection, cell: usize, location: &'static Location<'static>) {
+        if cell == &CELL as *const _ as usize {
+            assert_eq!(location.file(), file!());
+            MISS_LINE.store(location.line(), Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn hooks_report_caller_location() {
+        critical_section::with(|cs| {
+            set_hooks(
+                cs,
+                Hooks {
+                    on_uninit_access: Some(record_miss),
+                    ..Hooks::default()
+                },
+            );
+            CELL.borrow(cs, |_| {}, || {});
+            assert_eq!(MISS_LINE.load(Ordering::Relaxed), line!() - 1);
+        });
+
+        // these open their own critical section, which must not hide the caller
+        CELL.with(|_| {});
+        assert_eq!(MISS_LINE.load(Ordering::Relaxed), line!() - 1);
+        CELL.with_mut(|_| {});
+        assert_eq!(MISS_LINE.load(Ordering::Relaxed), line!() - 1);
+
+        critical_section::with(|cs| set_hooks(cs, Hooks::default()));
+    }
+}
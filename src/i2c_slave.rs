@@ -0,0 +1,204 @@
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+struct Map<const N: usize> {
+    registers: [u8; N],
+    /// Registers written by the master since the main loop last took them
+    written: [bool; N],
+    /// The registers as they were when the current read transaction started
+    snapshot: [u8; N],
+    /// Bytes received in the current write transaction, applied when it stops
+    incoming: [Option<u8>; N],
+    pointer: usize,
+    /// True until the first byte of a write transaction (the register address) is received
+    expecting_address: bool,
+}
+
+/// A register map shared between the TWI/I2C ISR of a target (slave) device and the main loop
+///
+/// The master selects a register by writing its address as the first byte of a write
+/// transaction, and then reads or writes consecutive registers from there. The ISR calls
+/// `address_write`, `receive`, `address_read`, `transmit`, and `stop` as the transaction
+/// progresses, and the main loop `publish`es the registers the master reads and takes the ones it
+/// wrote with `take_written`.
+///
+/// Multi-byte values stay consistent on both sides: a read transaction sends the registers as
+/// they were when it started (even if the main loop publishes new values meanwhile), and the
+/// bytes of a write transaction are only applied to the registers, all at once, when it stops.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::I2cSlaveBuffer;
+/// #
+/// static REGISTERS: I2cSlaveBuffer<8> = I2cSlaveBuffer::new();
+///
+/// // in the main loop, publish a 16-bit temperature at registers 2 and 3
+/// critical_section::with(|cs| REGISTERS.publish(cs, 2, &215u16.to_le_bytes()));
+///
+/// // in the TWI ISR: the master writes register address 2, then reads two bytes
+/// let temperature = critical_section::with(|cs| {
+///     REGISTERS.address_write(cs);
+///     REGISTERS.receive(cs, 2);
+///     REGISTERS.address_read(cs);
+///     let bytes = [REGISTERS.transmit(cs), REGISTERS.transmit(cs)];
+///     REGISTERS.stop(cs);
+///     u16::from_le_bytes(bytes)
+/// });
+/// assert_eq!(temperature, 215);
+///
+/// // the master writes 0x01 to register 0
+/// critical_section::with(|cs| {
+///     REGISTERS.address_write(cs);
+///     REGISTERS.receive(cs, 0);
+///     REGISTERS.receive(cs, 0x01);
+///     REGISTERS.stop(cs);
+/// });
+///
+/// // in the main loop
+/// let mut control = [0];
+/// assert!(critical_section::with(|cs| REGISTERS.take_written(cs, 0, &mut control)));
+/// assert_eq!(control, [0x01]);
+/// ```
+pub struct I2cSlaveBuffer<const N: usize>(Mutex<RefCell<Map<N>>>);
+
+impl<const N: usize> I2cSlaveBuffer<N> {
+    /// Creates a new register map with every register 0
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(Map {
+            registers: [0; N],
+            written: [false; N],
+            snapshot: [0; N],
+            incoming: [None; N],
+            pointer: 0,
+            expecting_address: false,
+        })))
+    }
+
+    /// Sets the registers starting at `offset` to `bytes`, for the next read transaction
+    ///
+    /// # Panics
+    ///
+    /// Panics if the registers don't fit in the map.
+    pub fn publish(&self, cs: CriticalSection, offset: usize, bytes: &[u8]) {
+        self.0.borrow_ref_mut(cs).registers[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// Copies the registers starting at `offset` into `buf`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the registers don't fit in the map.
+    pub fn read(&self, cs: CriticalSection, offset: usize, buf: &mut [u8]) {
+        buf.copy_from_slice(&self.0.borrow_ref(cs).registers[offset..offset + buf.len()]);
+    }
+
+    /// Copies the registers starting at `offset` into `buf`, returning true if the master wrote
+    /// any of them since they were last taken
+    ///
+    /// # Panics
+    ///
+    /// Panics if the registers don't fit in the map.
+    pub fn take_written(&self, cs: CriticalSection, offset: usize, buf: &mut [u8]) -> bool {
+        let mut map = self.0.borrow_ref_mut(cs);
+        let range = offset..offset + buf.len();
+        buf.copy_from_slice(&map.registers[range.clone()]);
+        let mut written = false;
+        for flag in &mut map.written[range] {
+            written |= core::mem::take(flag);
+        }
+        written
+    }
+
+    /// Starts a write transaction, in which the first byte received selects the register
+    pub fn address_write(&self, cs: CriticalSection) {
+        let mut map = self.0.borrow_ref_mut(cs);
+        map.expecting_address = true;
+        map.incoming = [None; N];
+    }
+
+    /// Handles a byte written by the master, selecting a register or writing to it
+    ///
+    /// Bytes past the end of the map are ignored.
+    pub fn receive(&self, cs: CriticalSection, byte: u8) {
+        let mut map = self.0.borrow_ref_mut(cs);
+        if core::mem::take(&mut map.expecting_address) {
+            map.pointer = usize::from(byte);
+            return;
+        }
+        let pointer = map.pointer;
+        if let Some(slot) = map.incoming.get_mut(pointer) {
+            *slot = Some(byte);
+        }
+        map.pointer = pointer.saturating_add(1);
+    }
+
+    /// Starts a read transaction from the selected register, taking a snapshot of the registers
+    pub fn address_read(&self, cs: CriticalSection) {
+        let map = &mut *self.0.borrow_ref_mut(cs);
+        map.snapshot = map.registers;
+    }
+
+    /// Returns the next byte for the master to read, or 0xFF past the end of the map
+    pub fn transmit(&self, cs: CriticalSection) -> u8 {
+        let mut map = self.0.borrow_ref_mut(cs);
+        let pointer = map.pointer;
+        map.pointer = pointer.saturating_add(1);
+        map.snapshot.get(pointer).copied().unwrap_or(0xff)
+    }
+
+    /// Ends the transaction, applying the bytes written in it to the registers
+    pub fn stop(&self, cs: CriticalSection) {
+        let map = &mut *self.0.borrow_ref_mut(cs);
+        for (index, byte) in map.incoming.iter_mut().enumerate() {
+            if let Some(byte) = byte.take() {
+                map.registers[index] = byte;
+                map.written[index] = true;
+            }
+        }
+        map.expecting_address = false;
+    }
+}
+
+impl<const N: usize> Default for I2cSlaveBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transactions_see_consistent_values() {
+        critical_section::with(|cs| {
+            let map: I2cSlaveBuffer<4> = I2cSlaveBuffer::new();
+            map.publish(cs, 0, &[1, 2]);
+
+            // the main loop publishes while a read is in progress
+            map.address_write(cs);
+            map.receive(cs, 0);
+            map.address_read(cs);
+            assert_eq!(map.transmit(cs), 1);
+            map.publish(cs, 0, &[3, 4]);
+            assert_eq!(map.transmit(cs), 2);
+            map.stop(cs);
+
+            // a write isn't visible until it stops
+            map.address_write(cs);
+            map.receive(cs, 2);
+            map.receive(cs, 9);
+            let mut regs = [0; 2];
+            assert!(!map.take_written(cs, 2, &mut regs));
+            map.receive(cs, 8);
+            // a byte past the end of the map
+            map.receive(cs, 7);
+            map.stop(cs);
+            assert!(map.take_written(cs, 2, &mut regs));
+            assert_eq!(regs, [9, 8]);
+            assert!(!map.take_written(cs, 0, &mut regs));
+            assert_eq!(regs, [3, 4]);
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use crate::BorrowError;
+use core::cell::Cell;
+use critical_section::{CriticalSection, Mutex};
+
+/// Access counters for a `StaticRefCell`, recorded with the `instrument` feature
+///
+/// Counters saturate instead of wrapping. See `StaticRefCell::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BorrowStats {
+    /// The number of successful immutable borrows
+    pub borrows: u32,
+    /// The number of successful mutable borrows
+    pub mut_borrows: u32,
+    /// The number of borrows that found the cell uninitialized or poisoned (calling `none_func`
+    /// or returning an error)
+    pub misses: u32,
+    /// The number of fallible borrows that failed because the stored value was already borrowed
+    pub conflicts: u32,
+    /// The largest number of closure borrows (such as nested `borrow` calls) held at the same time
+    pub max_depth: u8,
+}
+
+/// The counters of one cell, along with the number of closure borrows currently held
+pub(crate) struct Instrument {
+    stats: Mutex<Cell<BorrowStats>>,
+    depth: Mutex<Cell<u8>>,
+}
+
+/// Counts a closure borrow as held until dropped
+pub(crate) struct Depth<'cs>(&'cs Cell<u8>);
+
+impl Drop for Depth<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+impl Instrument {
+    pub(crate) const fn new() -> Self {
+        Self {
+            stats: Mutex::new(Cell::new(BorrowStats {
+                borrows: 0,
+                mut_borrows: 0,
+                misses: 0,
+                conflicts: 0,
+                max_depth: 0,
+            })),
+            depth: Mutex::new(Cell::new(0)),
+        }
+    }
+
+    fn update<F: FnOnce(&mut BorrowStats)>(&self, cs: CriticalSection, func: F) {
+        let cell = self.stats.borrow(cs);
+        let mut stats = cell.get();
+        func(&mut stats);
+        cell.set(stats);
+    }
+
+    pub(crate) fn borrow(&self, cs: CriticalSection) {
+        self.update(cs, |stats| stats.borrows = stats.borrows.saturating_add(1));
+    }
+
+    pub(crate) fn borrow_mut(&self, cs: CriticalSection) {
+        self.update(cs, |stats| {
+            stats.mut_borrows = stats.mut_borrows.saturating_add(1)
+        });
+    }
+
+    pub(crate) fn miss(&self, cs: CriticalSection) {
+        self.update(cs, |stats| stats.misses = stats.misses.saturating_add(1));
+    }
+
+    pub(crate) fn conflict(&self, cs: CriticalSection) {
+        self.update(cs, |stats| {
+            stats.conflicts = stats.conflicts.saturating_add(1)
+        });
+    }
+
+    /// Records a borrow through a guard method, which either found a value (`hit`) or didn't
+    pub(crate) fn found(&self, cs: CriticalSection, mutable: bool, hit: bool) {
+        match (hit, mutable) {
+            (true, false) => self.borrow(cs),
+            (true, true) => self.borrow_mut(cs),
+            (false, _) => self.miss(cs),
+        }
+    }
+
+    /// Records the outcome of a fallible borrow
+    pub(crate) fn record<V>(
+        &self,
+        cs: CriticalSection,
+        mutable: bool,
+        result: &Result<V, BorrowError>,
+    ) {
+        match result {
+            Err(BorrowError::AlreadyBorrowed) => self.conflict(cs),
+            _ => self.found(cs, mutable, result.is_ok()),
+        }
+    }
+
+    /// Records a closure borrow as held until the returned guard is dropped
+    pub(crate) fn enter<'cs>(&'cs self, cs: CriticalSection<'cs>) -> Depth<'cs> {
+        let depth = self.depth.borrow(cs);
+        depth.set(depth.get().saturating_add(1));
+        let current = depth.get();
+        self.update(cs, |stats| stats.max_depth = stats.max_depth.max(current));
+        Depth(depth)
+    }
+
+    pub(crate) fn stats(&self, cs: CriticalSection) -> BorrowStats {
+        self.stats.borrow(cs).get()
+    }
+
+    pub(crate) fn reset(&self, cs: CriticalSection) -> BorrowStats {
+        self.stats.borrow(cs).replace(BorrowStats::default())
+    }
+}
@@ -0,0 +1,149 @@
+use crate::StaticRefCell;
+use critical_section::CriticalSection;
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl StaticRefCell<$ty> {
+                /// Adds `value` to the stored value (wrapping around on overflow), and returns
+                /// the previous value, or None if the object is not yet initialized
+                #[track_caller]
+                pub fn fetch_add(&self, cs: CriticalSection, value: $ty) -> Option<$ty> {
+                    self.borrow_mut(
+                        cs,
+                        |stored| Some(core::mem::replace(stored, stored.wrapping_add(value))),
+                        || None,
+                    )
+                }
+
+                /// Subtracts `value` from the stored value (wrapping around on overflow), and
+                /// returns the previous value, or None if the object is not yet initialized
+                #[track_caller]
+                pub fn fetch_sub(&self, cs: CriticalSection, value: $ty) -> Option<$ty> {
+                    self.borrow_mut(
+                        cs,
+                        |stored| Some(core::mem::replace(stored, stored.wrapping_sub(value))),
+                        || None,
+                    )
+                }
+
+                /// Adds `value` to the stored value (stopping at the maximum), and returns the
+                /// new value, or None if the object is not yet initialized
+                #[track_caller]
+                pub fn saturating_add(&self, cs: CriticalSection, value: $ty) -> Option<$ty> {
+                    self.borrow_mut(
+                        cs,
+                        |stored| {
+                            *stored = stored.saturating_add(value);
+                            Some(*stored)
+                        },
+                        || None,
+                    )
+                }
+
+                /// Subtracts `value` from the stored value (stopping at the minimum), and
+                /// returns the new value, or None if the object is not yet initialized
+                #[track_caller]
+                pub fn saturating_sub(&self, cs: CriticalSection, value: $ty) -> Option<$ty> {
+                    self.borrow_mut(
+                        cs,
+                        |stored| {
+                            *stored = stored.saturating_sub(value);
+                            Some(*stored)
+                        },
+                        || None,
+                    )
+                }
+
+                /// Increments the stored value (wrapping around on overflow), and returns the
+                /// new value, or None if the object is not yet initialized
+                #[track_caller]
+                pub fn wrapping_inc(&self, cs: CriticalSection) -> Option<$ty> {
+                    self.fetch_add(cs, 1).map(|old| old.wrapping_add(1))
+                }
+
+                /// Decrements the stored value (wrapping around on overflow), and returns the
+                /// new value, or None if the object is not yet initialized
+                #[track_caller]
+                pub fn wrapping_dec(&self, cs: CriticalSection) -> Option<$ty> {
+                    self.fetch_sub(cs, 1).map(|old| old.wrapping_sub(1))
+                }
+
+                /// Sets the bits in `mask`, and returns the previous value, or None if the object
+                /// is not yet initialized
+                #[track_caller]
+                pub fn set_bits(&self, cs: CriticalSection, mask: $ty) -> Option<$ty> {
+                    self.borrow_mut(
+                        cs,
+                        |stored| Some(core::mem::replace(stored, *stored | mask)),
+                        || None,
+                    )
+                }
+
+                /// Clears the bits in `mask`, and returns the previous value, or None if the
+                /// object is not yet initialized
+                #[track_caller]
+                pub fn clear_bits(&self, cs: CriticalSection, mask: $ty) -> Option<$ty> {
+                    self.borrow_mut(
+                        cs,
+                        |stored| Some(core::mem::replace(stored, *stored & !mask)),
+                        || None,
+                    )
+                }
+
+                /// Returns true if any of the bits in `mask` are set (false if the object is not
+                /// yet initialized)
+                #[track_caller]
+                pub fn test_bits(&self, cs: CriticalSection, mask: $ty) -> bool {
+                    self.borrow(cs, |stored| *stored & mask != 0, || false)
+                }
+
+                /// Clears the bits in `mask`, and returns which of them were set, or None if the
+                /// object is not yet initialized
+                ///
+                /// Since reading and clearing happen in the same critical section, a bit set by
+                /// an ISR in between is never lost.
+                #[track_caller]
+                pub fn take_bits(&self, cs: CriticalSection, mask: $ty) -> Option<$ty> {
+                    self.clear_bits(cs, mask).map(|old| old & mask)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow_semantics() {
+        critical_section::with(|cs| {
+            let cell = StaticRefCell::new_with(250u8);
+            assert_eq!(cell.fetch_add(cs, 10), Some(250));
+            assert_eq!(cell.get(cs), Some(4));
+            assert_eq!(cell.saturating_sub(cs, 10), Some(0));
+            assert_eq!(cell.wrapping_dec(cs), Some(255));
+            assert_eq!(cell.saturating_add(cs, 1), Some(255));
+            assert_eq!(cell.wrapping_inc(cs), Some(0));
+
+            let empty: StaticRefCell<i16> = StaticRefCell::new();
+            assert_eq!(empty.wrapping_inc(cs), None);
+        });
+    }
+
+    #[test]
+    fn take_bits_reads_and_clears() {
+        critical_section::with(|cs| {
+            let cell = StaticRefCell::new_with(0u16);
+            assert_eq!(cell.set_bits(cs, 0b0110), Some(0));
+            assert!(cell.test_bits(cs, 0b0011));
+            assert!(!cell.test_bits(cs, 0b1000));
+            assert_eq!(cell.take_bits(cs, 0b1100), Some(0b0100));
+            assert_eq!(cell.clear_bits(cs, 0b0010), Some(0b0010));
+            assert_eq!(cell.get(cs), Some(0));
+        });
+    }
+}
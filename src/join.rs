@@ -0,0 +1,156 @@
+use crate::StaticRefCell;
+use critical_section::CriticalSection;
+
+/// Passes immutable references to the data stored in two cells in `func` and returns the result,
+/// or returns the result of `none_func` if either stored value is still None
+///
+/// Both cells are borrowed within the same critical section. For more than two cells, see
+/// `borrow_all!`.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{borrow_both, StaticRefCell};
+/// #
+/// static SCALE: StaticRefCell<u16> = StaticRefCell::new_with(4);
+/// static READING: StaticRefCell<u16> = StaticRefCell::new_with(25);
+///
+/// let scaled = critical_section::with(|cs| {
+///     borrow_both(cs, &SCALE, &READING, |scale, reading| scale * reading, || 0)
+/// });
+/// assert_eq!(scaled, 100);
+/// ```
+pub fn borrow_both<A, B, Y, F1, F2>(
+    cs: CriticalSection,
+    a: &StaticRefCell<A>,
+    b: &StaticRefCell<B>,
+    func: F1,
+    none_func: F2,
+) -> Y
+where
+    F1: FnOnce(&A, &B) -> Y,
+    F2: FnOnce() -> Y,
+{
+    match (a.borrow_ref(cs), b.borrow_ref(cs)) {
+        (Some(a), Some(b)) => func(&a, &b),
+        _ => none_func(),
+    }
+}
+
+/// Passes mutable references to the data stored in two cells in `func` and returns the result,
+/// or returns the result of `none_func` if either stored value is still None
+///
+/// Both cells are borrowed within the same critical section. For more than two cells, see
+/// `borrow_all_mut!`.
+///
+/// # Panics
+///
+/// Panics if either stored value is already borrowed, including when `a` and `b` are the same
+/// cell.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{borrow_both_mut, StaticRefCell};
+/// #
+/// static PENDING: StaticRefCell<[u8; 4]> = StaticRefCell::new_with([1, 2, 3, 4]);
+/// static SENT: StaticRefCell<u32> = StaticRefCell::new_with(0);
+///
+/// critical_section::with(|cs| {
+///     borrow_both_mut(cs, &PENDING, &SENT, |pending, sent| {
+///         *sent += pending.len() as u32;
+///         *pending = [0; 4];
+///     }, || {})
+/// });
+/// assert_eq!(critical_section::with(|cs| SENT.get(cs)), Some(4));
+/// ```
+pub fn borrow_both_mut<A, B, Y, F1, F2>(
+    cs: CriticalSection,
+    a: &StaticRefCell<A>,
+    b: &StaticRefCell<B>,
+    func: F1,
+    none_func: F2,
+) -> Y
+where
+    F1: FnOnce(&mut A, &mut B) -> Y,
+    F2: FnOnce() -> Y,
+{
+    match (a.borrow_ref_mut(cs), b.borrow_ref_mut(cs)) {
+        (Some(mut a), Some(mut b)) => func(&mut a, &mut b),
+        _ => none_func(),
+    }
+}
+
+/// Borrows the data stored in any number of cells immutably within one critical section
+///
+/// Each cell is bound to a name that the `func` block can use. The `none_func` block is evaluated
+/// instead if any of the cells is still uninitialized.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{borrow_all, StaticRefCell};
+/// #
+/// static A: StaticRefCell<u8> = StaticRefCell::new_with(1);
+/// static B: StaticRefCell<u8> = StaticRefCell::new_with(2);
+/// static C: StaticRefCell<u8> = StaticRefCell::new_with(3);
+///
+/// let sum = critical_section::with(|cs| {
+///     borrow_all!(cs, [a = &A, b = &B, c = &C], { a + b + c }, { 0 })
+/// });
+/// assert_eq!(sum, 6);
+/// ```
+#[macro_export]
+macro_rules! borrow_all {
+    ($cs:expr, [$($name:ident = $cell:expr),+ $(,)?], $func:block, $none_func:block) => {{
+        let cs = $cs;
+        match ($($crate::StaticRefCell::borrow_ref($cell, cs),)+) {
+            ($(::core::option::Option::Some($name),)+) => {
+                $(let $name = &*$name;)+
+                $func
+            }
+            _ => $none_func,
+        }
+    }};
+}
+
+/// Borrows the data stored in any number of cells mutably within one critical section
+///
+/// Each cell is bound to a name that the `func` block can use. The `none_func` block is evaluated
+/// instead if any of the cells is still uninitialized.
+///
+/// # Panics
+///
+/// Panics if any of the stored values is already borrowed, including when the same cell is listed
+/// twice.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{borrow_all_mut, StaticRefCell};
+/// #
+/// static RX: StaticRefCell<u8> = StaticRefCell::new_with(b'x');
+/// static LAST: StaticRefCell<u8> = StaticRefCell::new_with(0);
+/// static COUNT: StaticRefCell<u16> = StaticRefCell::new_with(0);
+///
+/// critical_section::with(|cs| {
+///     borrow_all_mut!(cs, [rx = &RX, last = &LAST, count = &COUNT], {
+///         *last = *rx;
+///         *count += 1;
+///     }, {})
+/// });
+/// assert_eq!(critical_section::with(|cs| LAST.get(cs)), Some(b'x'));
+/// ```
+#[macro_export]
+macro_rules! borrow_all_mut {
+    ($cs:expr, [$($name:ident = $cell:expr),+ $(,)?], $func:block, $none_func:block) => {{
+        let cs = $cs;
+        match ($($crate::StaticRefCell::borrow_ref_mut($cell, cs),)+) {
+            ($(::core::option::Option::Some(mut $name),)+) => {
+                $(let $name = &mut *$name;)+
+                $func
+            }
+            _ => $none_func,
+        }
+    }};
+}
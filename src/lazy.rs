@@ -0,0 +1,94 @@
+use crate::StaticRefCell;
+use critical_section::CriticalSection;
+
+/// A `StaticRefCell` that initializes itself with a stored function on first access
+///
+/// This removes the manual `init` call for payloads that don't depend on runtime peripherals, and
+/// since every access initializes the object first, its accessors don't take a `none_func`. With
+/// the `poison` feature, a poisoned value is replaced by running the initializer again.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::LazyStaticRefCell;
+/// #
+/// static HISTORY: LazyStaticRefCell<[u16; 8]> = LazyStaticRefCell::new(|| [0; 8]);
+///
+/// critical_section::with(|cs| HISTORY.borrow_mut(cs, |history| history[0] = 512));
+///
+/// let first = critical_section::with(|cs| HISTORY.borrow(cs, |history| history[0]));
+/// assert_eq!(first, 512);
+/// ```
+pub struct LazyStaticRefCell<T, F = fn() -> T> {
+    cell: StaticRefCell<T>,
+    init_fn: F,
+}
+
+impl<T, F: Fn() -> T> LazyStaticRefCell<T, F> {
+    /// Creates a new object that will be initialized with the result of `init_fn`
+    pub const fn new(init_fn: F) -> Self {
+        Self {
+            cell: StaticRefCell::new(),
+            init_fn,
+        }
+    }
+
+    /// Initializes the stored value if it hasn't been initialized yet, or is poisoned
+    ///
+    /// Calling this is never required, but it can be used during setup to move the cost of running
+    /// the initializer out of the first access (which may happen in an ISR).
+    #[track_caller]
+    pub fn force(&self, cs: CriticalSection) {
+        if self.cell.needs_init(cs) {
+            self.cell.init(cs, (self.init_fn)());
+        }
+    }
+
+    /// Passes an immutable reference to the stored data in `func` and returns the result,
+    /// initializing the stored data first if needed
+    #[track_caller]
+    pub fn borrow<Y, G: FnOnce(&T) -> Y>(&self, cs: CriticalSection, func: G) -> Y {
+        self.force(cs);
+        self.cell
+            .borrow(cs, func, || unreachable!("cell was initialized above"))
+    }
+
+    /// Passes a mutable reference to the stored data in `func` and returns the result,
+    /// initializing the stored data first if needed
+    #[track_caller]
+    pub fn borrow_mut<Y, G: FnOnce(&mut T) -> Y>(&self, cs: CriticalSection, func: G) -> Y {
+        self.force(cs);
+        self.cell
+            .borrow_mut(cs, func, || unreachable!("cell was initialized above"))
+    }
+}
+
+#[cfg(all(test, feature = "poison"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poisoned_value_is_initialized_again() {
+        extern crate std;
+
+        static LAZY: LazyStaticRefCell<i32> = LazyStaticRefCell::new(|| 2);
+
+        critical_section::with(|cs| LAZY.borrow_mut(cs, |value| *value = 1));
+        let _ = std::panic::catch_unwind(|| {
+            critical_section::with(|cs| LAZY.borrow_mut(cs, |_| panic!("torn update")))
+        });
+        assert_eq!(
+            critical_section::with(|cs| LAZY.borrow(cs, |value| *value)),
+            2
+        );
+
+        let _ = std::panic::catch_unwind(|| {
+            critical_section::with(|cs| LAZY.borrow_mut(cs, |_| panic!("torn update")))
+        });
+        critical_section::with(|cs| LAZY.borrow_mut(cs, |value| *value += 1));
+        assert_eq!(
+            critical_section::with(|cs| LAZY.borrow(cs, |value| *value)),
+            3
+        );
+    }
+}
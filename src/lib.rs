@@ -58,30 +58,1137 @@
 //!     critical_section::with(|cs| MY_DATA.borrow_mut(cs, |value| value = !value, panic!())); // TODO: check here
 //! }
 //! ```
+//!
+//! # Features
+//!
+//! - `async`: provide `StaticRefCell::wait_init`, a future that resolves once the cell is
+//!   initialized
+//! - `avr`: provide `enable_interrupts` on AVR targets, using `avr-device`
+//! - `avr-hal`: provide `into_cells!`, which declares a cell for each of several peripherals or
+//!   pins and a function that moves them in
+//! - `cortex-m`: provide `enable_interrupts` on ARM Cortex-M targets, using `cortex-m`, along
+//!   with the `backend::NvicMaskBackend` and `backend::BasepriBackend` locking backends
+//! - `debug-borrows`: report the name of the cell (see `StaticRefCell::named`) and the call
+//!   sites involved when a borrow conflicts, in the panic message or through
+//!   `StaticRefCell::last_conflict`
+//! - `derive`: provide `#[derive(SplitIntoCells)]` for splitting a struct of peripherals into one
+//!   cell per field
+//! - `defmt`: implement `defmt::Format` for `StaticRefCell`, and log initialization, removal, and
+//!   poisoning of cells with `defmt::trace!`
+//! - `dirty`: track whether each `StaticRefCell` was mutably borrowed since it was last marked
+//!   clean (see `StaticRefCell::is_dirty`)
+//! - `dyn-dispatch`: route `borrow` and `borrow_mut` through `&mut dyn FnMut` trampolines, so
+//!   their bodies are emitted once per payload type instead of once per closure, trading a little
+//!   speed for flash
+//! - `embassy`: provide `embassy::StaticRawMutex`, an embassy-sync `RawMutex` that uses the
+//!   same critical sections as `StaticRefCell`, and conversions between a `StaticRefCell` and an
+//!   embassy-sync blocking mutex
+//! - `embedded-hal`: provide the `proxy` module, which implements `embedded-hal` traits for
+//!   peripherals shared through a `StaticRefCell`
+//! - `embedded-hal-02`: also provide `proxy::WatchdogCell`, which implements the `embedded-hal`
+//!   0.2 watchdog traits (`embedded-hal` 1.0 has none)
+//! - `embedded-hal-async`: also implement the `embedded-hal-async` traits in the `proxy` module
+//! - `embedded-storage`: provide `PersistentCell`, which mirrors its value to non-volatile
+//!   storage implementing `embedded_storage::Storage`, such as EEPROM
+//! - `heapless`: provide `push`, `pop`, and `drain_into` on cells holding a `heapless::Vec`,
+//!   `heapless::Deque`, or `heapless::String`, and `spsc::split` for keeping the halves of a
+//!   `heapless::spsc::Queue` in statics
+//! - `hooks`: call the `Hooks` installed with `set_hooks` when any `StaticRefCell` is
+//!   initialized, borrowed, or found uninitialized, such as to toggle a debug pin or emit RTT
+//!   events
+//! - `instrument`: count borrows, misses (calls of `none_func`), conflicting borrows, and the
+//!   deepest nesting of borrows for each `StaticRefCell` (see `StaticRefCell::stats`)
+//! - `lock_api`: provide `lock_api::RawCsMutex`, a `lock_api::RawMutex` that uses the same
+//!   critical sections as `StaticRefCell`
+//! - `poison`: poison a `StaticRefCell` when a mutable borrow panics, so a possibly inconsistent
+//!   payload isn't silently reused (see `StaticRefCell::is_poisoned`)
+//! - `profile`: time each closure borrow of a `StaticRefCell` with a user-supplied cycle counter
+//!   (see `set_cycle_counter`), track the longest and total hold time of each cell (see
+//!   `StaticRefCell::hold_stats`), and enforce the budgets set with
+//!   `StaticRefCell::with_latency_budget`
+//! - `rp2040`: provide `backend::SpinlockBackend`, which locks with an RP2040/RP2350 hardware
+//!   spinlock so cells can be shared between both cores
+//! - `rtic`: provide `rtic::Resource`, which lends a `StaticRefCell` to code written against
+//!   RTIC's `Mutex` trait
+//! - `testing`: provide the `testing` module, a host-side harness that fires fake ISRs between
+//!   the steps of a simulated main loop, and an explorer that checks every interleaving of ISR
+//!   preemptions (requires `std`)
+//! - `ufmt`: implement `ufmt::uDebug` for `StaticRefCell`, `Mailbox`, `StaticEventFlags`,
+//!   `StaticSemaphore`, and the `spsc` queues, for printing shared state without `core::fmt`, and
+//!   `ufmt::uWrite` for `SerialWriter`
 
 #![no_std]
+#![cfg_attr(target_arch = "avr", feature(asm_experimental_arch))]
+
+/// Logs a lifecycle event of a cell with `defmt::trace!` if the `defmt` feature is enabled
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "defmt")]
+        defmt::trace!($($arg)*);
+    };
+}
+
+/// Records a successful or conflicting borrow of a cell if the `debug-borrows` feature is enabled
+macro_rules! diagnose {
+    ($cell:expr, $cs:expr, $event:ident $(, $arg:expr)*) => {
+        #[cfg(feature = "debug-borrows")]
+        $cell.diagnostics.$event($cs $(, $arg)*);
+    };
+}
+
+/// Reports an access of a cell to the installed `Hooks` if the `hooks` feature is enabled
+macro_rules! hook {
+    ($cell:expr, $cs:expr, $event:ident $(, $arg:expr)*) => {
+        #[cfg(feature = "hooks")]
+        hooks::$event($cs, $cell as *const _ as usize $(, $arg)*);
+    };
+}
+
+/// Records an access of a cell in its `BorrowStats` if the `instrument` feature is enabled
+///
+/// `enter` counts a closure borrow as held until the end of the enclosing block.
+macro_rules! instrument {
+    ($cell:expr, $cs:expr, enter) => {
+        #[cfg(feature = "instrument")]
+        let _depth = $cell.instrument.enter($cs);
+    };
+    ($cell:expr, $cs:expr, $event:ident $(, $arg:expr)*) => {
+        #[cfg(feature = "instrument")]
+        $cell.instrument.$event($cs $(, $arg)*);
+    };
+}
+
+mod arena;
+mod array;
+mod button;
+mod cached;
+mod capture;
+mod checked;
+#[cfg(feature = "heapless")]
+mod collections;
+mod compact;
+mod debounce;
+mod declare;
+#[cfg(feature = "debug-borrows")]
+mod diagnostics;
+mod double_buffer;
+mod encoder;
+mod event_flags;
+mod filter;
+mod format;
+mod fsm;
+mod group;
+mod handle;
+mod handler;
+mod history;
+#[cfg(feature = "hooks")]
+mod hooks;
+mod i2c_slave;
+#[cfg(feature = "instrument")]
+mod instrument;
+mod integer;
+mod join;
+mod lazy;
+mod mailbox;
+mod noinit;
+mod observable;
+mod once_cell;
+mod panic_info;
+#[cfg(feature = "embedded-storage")]
+mod persistent;
+mod pod;
+mod port;
+mod primitive;
+#[cfg(feature = "profile")]
+mod profile;
+mod progmem;
+mod redundant;
+mod semaphore;
+mod seq;
+mod serial;
+mod shared;
+mod stats;
+mod tick;
+mod token;
+mod transaction;
+mod triple_buffer;
+mod unchecked;
+#[cfg(feature = "async")]
+mod wait;
+mod waker;
+
+pub mod backend;
+#[cfg(feature = "embassy")]
+pub mod embassy;
+#[cfg(feature = "lock_api")]
+pub mod lock_api;
+pub mod log_buffer;
+pub mod pool;
+#[cfg(feature = "embedded-hal")]
+pub mod proxy;
+#[cfg(feature = "rtic")]
+pub mod rtic;
+pub mod sched;
+pub mod spsc;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod timer;
+pub mod uart;
+
+pub use arena::{Arena, ArenaHandle};
+pub use array::StaticRefCellArray;
+pub use button::{ButtonEvent, ButtonEvents};
+pub use cached::{CachedCell, CachedReader};
+pub use capture::CaptureBuffer;
+pub use checked::{CheckedCell, Corrupted};
+pub use compact::CompactStaticRefCell;
+pub use debounce::{Debouncer, Edge};
+#[cfg(feature = "debug-borrows")]
+pub use diagnostics::BorrowConflict;
+pub use double_buffer::DoubleBuffer;
+/// Splits a struct (such as a set of peripherals and pins) into one `StaticRefCell` per field
+///
+/// Moving a whole struct into a single cell serializes every ISR that uses any part of it. This
+/// derive generates a `<Name>Cells` struct with a `StaticRefCell` for each field, so each ISR only
+/// borrows what it needs. `<Name>Cells::new()` is a `const fn` for use in a static, and
+/// `split_into_statics(cs, value)` moves each field of `value` into its cell.
+///
+/// It can only be derived for non-generic structs with named fields.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::SplitIntoCells;
+/// #
+/// struct Led;
+/// struct Uart;
+///
+/// #[derive(SplitIntoCells)]
+/// struct Shared {
+///     led: Led,
+///     uart: Uart,
+/// }
+///
+/// static SHARED: SharedCells = SharedCells::new();
+///
+/// critical_section::with(|cs| SHARED.split_into_statics(cs, Shared { led: Led, uart: Uart }));
+///
+/// // an ISR that only needs the LED doesn't lock the UART
+/// assert!(critical_section::with(|cs| SHARED.led.is_initialized(cs)));
+/// ```
+#[cfg(feature = "derive")]
+pub use embedded_static_ref_cell_macros::SplitIntoCells;
+pub use encoder::{Direction, EncoderCell};
+pub use event_flags::StaticEventFlags;
+pub use filter::{Filter, FilterCell};
+pub use fsm::{FsmCell, InvalidTransition, Transition};
+pub use group::{CellGroup, Members};
+pub use handle::{IsrHandle, MainHandle};
+pub use handler::{HandlerSlot, HandlerTable};
+pub use history::HistoryCell;
+#[cfg(feature = "hooks")]
+pub use hooks::{set_hooks, Hook, Hooks};
+pub use i2c_slave::I2cSlaveBuffer;
+#[cfg(feature = "instrument")]
+pub use instrument::BorrowStats;
+pub use join::{borrow_both, borrow_both_mut};
+pub use lazy::LazyStaticRefCell;
+pub use mailbox::Mailbox;
+pub use noinit::NoinitCell;
+pub use observable::{ObservableCell, Observer};
+pub use once_cell::StaticOnceCell;
+pub use panic_info::{PanicInfoCell, PanicReport};
+#[cfg(feature = "embedded-storage")]
+pub use persistent::{PersistentCell, PersistentError};
+pub use pod::Pod;
+pub use port::PortCell;
+pub use primitive::{Integer, Primitive, StaticPrimitiveCell};
+#[cfg(feature = "profile")]
+pub use profile::{set_budget_handler, set_cycle_counter, BudgetExceeded, HoldStats};
+pub use progmem::ProgmemCell;
+pub use redundant::RedundantCell;
+pub use semaphore::StaticSemaphore;
+pub use seq::SeqCell;
+pub use serial::{SerialCell, SerialError, SerialWriter};
+pub use shared::Shared;
+pub use stats::{Stats, StatsCell};
+pub use tick::TickCounter;
+#[cfg(any(
+    all(feature = "avr", target_arch = "avr"),
+    all(feature = "cortex-m", target_arch = "arm")
+))]
+pub use token::enable_interrupts;
+pub use token::InitToken;
+pub use transaction::{transaction, Append, Transaction};
+pub use triple_buffer::{TripleBuffer, TripleConsumer, TripleProducer};
+pub use unchecked::StaticUnsafeCell;
+#[cfg(feature = "async")]
+pub use wait::WaitInit;
+pub use waker::WakerCell;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::token::Sealed;
+    pub use critical_section;
+    pub use paste::paste;
+
+    use crate::StaticRefCell;
+    use core::panic::Location;
+
+    /// Opens a critical section and initializes `cell`, reporting the access at the caller
+    #[track_caller]
+    pub fn init<T>(cell: &StaticRefCell<T>, value: T) {
+        let location = Location::caller();
+        critical_section::with(|cs| cell.init_at(cs, location, value))
+    }
+
+    /// Opens a critical section and takes the value out of `cell`, reporting the access at the
+    /// caller
+    #[track_caller]
+    pub fn take<T>(cell: &StaticRefCell<T>) -> Option<T> {
+        let location = Location::caller();
+        critical_section::with(|cs| cell.take_at(cs, location))
+    }
+}
 
-use core::cell::RefCell;
+use backend::{CriticalSectionBackend, RawMutexBackend};
+use core::cell::{Ref, RefCell, RefMut};
+use core::fmt;
+use core::panic::Location;
+use core::sync::atomic::{AtomicBool, Ordering};
 use critical_section::{CriticalSection, Mutex};
 
 type Mrco<T> = Mutex<RefCell<Option<T>>>;
 
+/// The reason a fallible borrow of a `StaticRefCell` failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorrowError {
+    /// The stored value is still None
+    Uninitialized,
+    /// The stored value is already borrowed in a way that conflicts with the requested borrow
+    AlreadyBorrowed,
+    /// A previous mutable borrow panicked, so the stored value may be in an inconsistent state
+    ///
+    /// This is only returned when the `poison` feature is enabled.
+    Poisoned,
+}
+
+impl fmt::Display for BorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BorrowError::Uninitialized => f.write_str("cell is not initialized"),
+            BorrowError::AlreadyBorrowed => f.write_str("cell is already borrowed"),
+            BorrowError::Poisoned => f.write_str("cell is poisoned"),
+        }
+    }
+}
+
 /// An object that allows for a non-Send/Sync type to be used safely in a static variable
 ///
+/// Nothing requires the object to be static, though: a local cell works the same way, which suits
+/// host-side tests and short-lived scopes, and `into_inner` and `get_mut` give direct access to
+/// the payload once the object is owned.
+///
+/// The lock is a global critical section, which is what the `CriticalSection` tokens taken by
+/// most methods prove. Another locking strategy can be chosen with the backend `M` (see the
+/// `backend` module), in which case the methods take the lock themselves instead of a token.
+///
 /// See the module-level documentation for more details
-pub struct StaticRefCell<T>(Mrco<T>);
+pub struct StaticRefCell<T, M: RawMutexBackend = CriticalSectionBackend> {
+    data: Mrco<T>,
+    backend: M,
+    initialized: AtomicBool,
+    #[cfg(feature = "poison")]
+    poisoned: AtomicBool,
+    #[cfg(feature = "dirty")]
+    dirty: AtomicBool,
+    #[cfg(feature = "async")]
+    init_waker: WakerCell,
+    #[cfg(feature = "instrument")]
+    instrument: instrument::Instrument,
+    #[cfg(feature = "debug-borrows")]
+    diagnostics: diagnostics::Diagnostics,
+    #[cfg(feature = "profile")]
+    profile: profile::Profile,
+}
+
+/// Poisons a cell if dropped while unwinding out of a mutable borrow
+#[cfg(feature = "poison")]
+struct PoisonOnUnwind<'a>(&'a AtomicBool);
+
+#[cfg(feature = "poison")]
+impl Drop for PoisonOnUnwind<'_> {
+    fn drop(&mut self) {
+        trace!("StaticRefCell poisoned");
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+impl<T, M: RawMutexBackend> StaticRefCell<T, M> {
+    /// Creates a new uninitialized object (stored value as None) that locks with `backend`
+    pub const fn with_backend(backend: M) -> Self {
+        Self {
+            data: Mutex::new(RefCell::new(None)),
+            backend,
+            initialized: AtomicBool::new(false),
+            #[cfg(feature = "poison")]
+            poisoned: AtomicBool::new(false),
+            #[cfg(feature = "dirty")]
+            dirty: AtomicBool::new(false),
+            #[cfg(feature = "async")]
+            init_waker: WakerCell::new(),
+            #[cfg(feature = "instrument")]
+            instrument: instrument::Instrument::new(),
+            #[cfg(feature = "debug-borrows")]
+            diagnostics: diagnostics::Diagnostics::new(),
+            #[cfg(feature = "profile")]
+            profile: profile::Profile::new(),
+        }
+    }
+
+    /// Creates a new object that is already initialized with `value` and locks with `backend`
+    pub const fn new_with_backend(value: T, backend: M) -> Self {
+        Self {
+            data: Mutex::new(RefCell::new(Some(value))),
+            backend,
+            initialized: AtomicBool::new(true),
+            #[cfg(feature = "poison")]
+            poisoned: AtomicBool::new(false),
+            #[cfg(feature = "dirty")]
+            dirty: AtomicBool::new(false),
+            #[cfg(feature = "async")]
+            init_waker: WakerCell::new(),
+            #[cfg(feature = "instrument")]
+            instrument: instrument::Instrument::new(),
+            #[cfg(feature = "debug-borrows")]
+            diagnostics: diagnostics::Diagnostics::new(),
+            #[cfg(feature = "profile")]
+            profile: profile::Profile::new(),
+        }
+    }
+
+    /// Returns the backend used for locking
+    pub const fn backend(&self) -> &M {
+        &self.backend
+    }
+
+    /// Consumes this object and returns the stored value, or None if it was never initialized
+    ///
+    /// No lock is needed, since owning the object means nothing else can access it.
+    /// Like `take`, this ignores poisoning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// // a local cell, as in a host-side test
+    /// let cell: StaticRefCell<Vec<u8>> = StaticRefCell::new();
+    /// critical_section::with(|cs| {
+    ///     cell.init(cs, Vec::new());
+    ///     cell.borrow_mut(cs, |bytes| bytes.push(1), || {});
+    /// });
+    ///
+    /// assert_eq!(cell.into_inner(), Some(vec![1]));
+    /// ```
+    pub fn into_inner(self) -> Option<T> {
+        self.data.into_inner().into_inner()
+    }
+
+    /// Returns a mutable reference to the stored value, or None if it is still None
+    ///
+    /// No lock is needed, since the mutable reference to this object guarantees exclusive access. Like `into_inner`, this ignores poisoning.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.data.get_mut().get_mut().as_mut()
+    }
+}
 
 impl<T> StaticRefCell<T> {
     /// Creates a new uninitialized object (stored value as None)
     pub const fn new() -> Self {
-        Self(Mutex::new(RefCell::new(None)))
+        Self::with_backend(CriticalSectionBackend)
+    }
+
+    /// Creates a new object that is already initialized with `value`
+    ///
+    /// Because this is a `const fn`, it can be used for static variables whose payload can be built
+    /// in a const context, so the cell is never observed uninitialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static TICKS: StaticRefCell<u32> = StaticRefCell::new_with(0);
+    ///
+    /// critical_section::with(|cs| TICKS.borrow_mut(cs, |ticks| *ticks += 1, || unreachable!()));
+    /// assert_eq!(critical_section::with(|cs| TICKS.get(cs)), Some(1));
+    /// ```
+    pub const fn new_with(value: T) -> Self {
+        Self::new_with_backend(value, CriticalSectionBackend)
+    }
+
+    /// Gives this object a name, which is included in the report of a conflicting borrow
+    ///
+    /// The name is only kept with the `debug-borrows` feature, so statics can be named
+    /// unconditionally at no cost. `static_ref_cell!` names its cells after their statics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static LED: StaticRefCell<bool> = StaticRefCell::new().named("LED");
+    /// ```
+    #[cfg_attr(not(feature = "debug-borrows"), allow(unused_mut))]
+    pub const fn named(mut self, name: &'static str) -> Self {
+        #[cfg(feature = "debug-borrows")]
+        {
+            self.diagnostics.name = Some(name);
+        }
+        #[cfg(not(feature = "debug-borrows"))]
+        let _ = name;
+        self
+    }
+
+    /// Sets the longest a closure borrow of this object should take, in the units of the counter
+    /// passed to `set_cycle_counter`
+    ///
+    /// The budget is only enforced with the `profile` feature, so statics can be given a budget
+    /// unconditionally at no cost. A borrow that exceeds it is counted in `HoldStats::over_budget`
+    /// and reported to the handler passed to `set_budget_handler`, or, if there is none, fails a
+    /// debug assertion. This catches long-running work accidentally done inside a critical
+    /// section.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static SAMPLES: StaticRefCell<[u16; 16]> = StaticRefCell::new().with_latency_budget(200);
+    /// ```
+    #[cfg_attr(not(feature = "profile"), allow(unused_mut))]
+    pub const fn with_latency_budget(mut self, budget: u32) -> Self {
+        #[cfg(feature = "profile")]
+        {
+            self.profile.budget = Some(budget);
+        }
+        #[cfg(not(feature = "profile"))]
+        let _ = budget;
+        self
+    }
+
+    /// Returns the name given to this object with `named`, if any
+    #[cfg(feature = "debug-borrows")]
+    pub const fn name(&self) -> Option<&'static str> {
+        self.diagnostics.name
+    }
+
+    /// Returns the most recent conflicting borrow of this object, including ones reported as
+    /// `BorrowError::AlreadyBorrowed` by the fallible methods
+    ///
+    /// A conflicting borrow through a panicking method (such as a nested `borrow_mut`) panics with
+    /// the same description, naming this object and the call sites involved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static CELL: StaticRefCell<i32> = StaticRefCell::new_with(1).named("CELL");
+    ///
+    /// critical_section::with(|cs| {
+    ///     CELL.borrow_mut(cs, |_| CELL.try_borrow(cs, |_| {}), || Ok(())).unwrap_err();
+    ///
+    ///     let conflict = CELL.last_conflict(cs).unwrap();
+    ///     assert_eq!(conflict.name, Some("CELL"));
+    ///     assert!(conflict.borrowed_at.is_some());
+    /// });
+    /// ```
+    #[cfg(feature = "debug-borrows")]
+    pub fn last_conflict(&self, cs: CriticalSection) -> Option<BorrowConflict> {
+        self.diagnostics.last_conflict(cs)
+    }
+
+    /// Immutably borrows the stored `Option`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently mutably borrowed, at the location of the caller,
+    /// describing the conflict with the `debug-borrows` feature.
+    #[track_caller]
+    fn stored<'cs>(&'cs self, cs: CriticalSection<'cs>) -> Ref<'cs, Option<T>> {
+        self.stored_at(cs, Location::caller())
+    }
+
+    /// Immutably borrows the stored `Option`, as an access made at `location`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently mutably borrowed, naming `location` as the
+    /// conflicting borrow.
+    #[track_caller]
+    fn stored_at<'cs>(
+        &'cs self,
+        cs: CriticalSection<'cs>,
+        location: &'static Location<'static>,
+    ) -> Ref<'cs, Option<T>> {
+        #[cfg(feature = "debug-borrows")]
+        return match self.data.borrow(cs).try_borrow() {
+            Ok(stored) => {
+                self.diagnostics.borrowed(cs, location);
+                stored
+            }
+            Err(_) => panic!("{}", self.diagnostics.conflict(cs, location)),
+        };
+        #[cfg(not(feature = "debug-borrows"))]
+        return match self.data.borrow(cs).try_borrow() {
+            Ok(stored) => stored,
+            Err(_) => panic!(
+                "StaticRefCell is already mutably borrowed: conflicting borrow at {location}"
+            ),
+        };
+    }
+
+    /// Mutably borrows the stored `Option`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently borrowed, at the location of the caller, describing
+    /// the conflict with the `debug-borrows` feature.
+    #[track_caller]
+    fn stored_mut<'cs>(&'cs self, cs: CriticalSection<'cs>) -> RefMut<'cs, Option<T>> {
+        self.stored_mut_at(cs, Location::caller())
+    }
+
+    /// Mutably borrows the stored `Option`, as an access made at `location`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently borrowed, naming `location` as the conflicting
+    /// borrow.
+    #[track_caller]
+    fn stored_mut_at<'cs>(
+        &'cs self,
+        cs: CriticalSection<'cs>,
+        location: &'static Location<'static>,
+    ) -> RefMut<'cs, Option<T>> {
+        #[cfg(feature = "debug-borrows")]
+        return match self.data.borrow(cs).try_borrow_mut() {
+            Ok(stored) => {
+                self.diagnostics.borrowed(cs, location);
+                stored
+            }
+            Err(_) => panic!("{}", self.diagnostics.conflict(cs, location)),
+        };
+        #[cfg(not(feature = "debug-borrows"))]
+        return match self.data.borrow(cs).try_borrow_mut() {
+            Ok(stored) => stored,
+            Err(_) => panic!("StaticRefCell is already borrowed: conflicting borrow at {location}"),
+        };
+    }
+
+    /// Runs `func` on the stored `Option` and keeps the initialization flag in sync with the result
+    ///
+    /// Since `func` replaces or removes the stored value, this also clears any poisoning. With the
+    /// `async` feature, a task waiting in `wait_init` is woken if the object is now initialized.
+    #[track_caller]
+    fn update<Y, F: FnOnce(&mut Option<T>) -> Y>(&self, cs: CriticalSection, func: F) -> Y {
+        self.update_at(cs, Location::caller(), func)
+    }
+
+    /// Like `update`, reporting the access at `location`
+    #[track_caller]
+    fn update_at<Y, F: FnOnce(&mut Option<T>) -> Y>(
+        &self,
+        cs: CriticalSection,
+        location: &'static Location<'static>,
+        func: F,
+    ) -> Y {
+        let mut stored = self.stored_mut_at(cs, location);
+        let result = func(&mut stored);
+        let initialized = stored.is_some();
+        drop(stored);
+        self.initialized.store(initialized, Ordering::Release);
+        #[cfg(feature = "poison")]
+        self.poisoned.store(false, Ordering::Release);
+        self.mark_dirty();
+        #[cfg(feature = "async")]
+        if initialized {
+            self.init_waker.wake(cs);
+        }
+        result
+    }
+
+    /// Returns true if the stored value is poisoned (always false without the `poison` feature)
+    fn poisoned(&self) -> bool {
+        #[cfg(feature = "poison")]
+        return self.poisoned.load(Ordering::Acquire);
+        #[cfg(not(feature = "poison"))]
+        return false;
+    }
+
+    /// Returns true if the stored value is None or poisoned, so it can't be borrowed until it is
+    /// initialized again
+    fn needs_init(&self, cs: CriticalSection) -> bool {
+        !self.is_initialized(cs) || self.poisoned()
+    }
+
+    /// Marks the stored value as changed (does nothing without the `dirty` feature)
+    fn mark_dirty(&self) {
+        #[cfg(feature = "dirty")]
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Calls `func`, timing it with the `profile` feature
+    #[cfg_attr(not(feature = "profile"), allow(unused_variables))]
+    fn timed<Y, F: FnOnce() -> Y>(&self, cs: CriticalSection, func: F) -> Y {
+        #[cfg(all(feature = "profile", feature = "debug-borrows"))]
+        return self.profile.time(cs, self.diagnostics.name, func);
+        #[cfg(all(feature = "profile", not(feature = "debug-borrows")))]
+        return self.profile.time(cs, None, func);
+        #[cfg(not(feature = "profile"))]
+        func()
+    }
+
+    /// Calls `func` on the stored value, poisoning this object if `func` panics
+    fn call_mut<Y, F: FnOnce(&mut T) -> Y>(&self, value: &mut T, func: F) -> Y {
+        self.mark_dirty();
+        #[cfg(feature = "poison")]
+        let guard = PoisonOnUnwind(&self.poisoned);
+        let result = func(value);
+        #[cfg(feature = "poison")]
+        core::mem::forget(guard);
+        result
+    }
+
+    /// Returns true if a mutable borrow of the stored value panicked, so the stored value may be in
+    /// an inconsistent state
+    ///
+    /// While poisoned, `borrow` and `borrow_mut` call `none_func`, and the other accessors behave
+    /// as if the object were uninitialized (or return `BorrowError::Poisoned`). Poisoning is
+    /// cleared by `clear_poison`, or by anything that replaces or removes the stored value, such
+    /// as `init`, `replace`, or `take`.
+    ///
+    /// Panics can only be detected when they unwind, so this has no effect with `panic = "abort"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::{BorrowError, StaticRefCell};
+    /// #
+    /// static CELL: StaticRefCell<i32> = StaticRefCell::new_with(1);
+    ///
+    /// let _ = std::panic::catch_unwind(|| {
+    ///     critical_section::with(|cs| CELL.borrow_mut(cs, |_| panic!("torn update"), || {}))
+    /// });
+    ///
+    /// critical_section::with(|cs| {
+    ///     assert!(CELL.is_poisoned(cs));
+    ///     assert_eq!(CELL.try_borrow(cs, |value| *value), Err(BorrowError::Poisoned));
+    ///
+    ///     CELL.clear_poison(cs);
+    ///     assert_eq!(CELL.try_borrow(cs, |value| *value), Ok(1));
+    /// });
+    /// ```
+    #[cfg(feature = "poison")]
+    pub fn is_poisoned(&self, _cs: CriticalSection) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    /// Clears poisoning, allowing the stored value to be borrowed again as-is
+    ///
+    /// See `is_poisoned` for details.
+    #[cfg(feature = "poison")]
+    pub fn clear_poison(&self, _cs: CriticalSection) {
+        self.poisoned.store(false, Ordering::Release);
+    }
+
+    /// Returns true if the stored value may have changed since the last `mark_clean`
+    ///
+    /// Every mutable borrow marks this object dirty (whether or not it actually changes the
+    /// value), as does anything that replaces or removes the stored value, such as `set`, `init`,
+    /// and `take`. Since this is a single atomic load, the main loop can poll it cheaply and only
+    /// recompute what depends on the value when it changed. To not miss a change made by an ISR
+    /// in between, call `mark_clean` before reading the value, not after.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static SETPOINT: StaticRefCell<i16> = StaticRefCell::new_with(0);
+    ///
+    /// // in an ISR
+    /// critical_section::with(|cs| SETPOINT.set(cs, 40));
+    ///
+    /// // in the main loop
+    /// if SETPOINT.is_dirty() {
+    ///     let setpoint = critical_section::with(|cs| {
+    ///         SETPOINT.mark_clean(cs);
+    ///         SETPOINT.get(cs)
+    ///     });
+    ///     assert_eq!(setpoint, Some(40));
+    /// }
+    /// assert!(!SETPOINT.is_dirty());
+    /// ```
+    #[cfg(feature = "dirty")]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
+    /// Marks the stored value as unchanged, until the next mutable borrow
+    ///
+    /// See `is_dirty` for details.
+    #[cfg(feature = "dirty")]
+    pub fn mark_clean(&self, _cs: CriticalSection) {
+        self.dirty.store(false, Ordering::Release);
+    }
+
+    /// Returns the access counters of this object
+    ///
+    /// Every borrow is counted, whether through a closure, a guard, or a fallible method, along
+    /// with borrows that found the object uninitialized or already borrowed. Nesting is only
+    /// tracked for closure borrows, since guards can be held without this object knowing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static CELL: StaticRefCell<i32> = StaticRefCell::new();
+    ///
+    /// critical_section::with(|cs| {
+    ///     CELL.borrow(cs, |_| {}, || {});
+    ///     CELL.init(cs, 1);
+    ///     CELL.borrow(cs, |_| CELL.borrow(cs, |_| {}, || {}), || {});
+    ///
+    ///     let stats = CELL.stats(cs);
+    ///     assert_eq!((stats.borrows, stats.misses, stats.max_depth), (2, 1, 2));
+    /// });
+    /// ```
+    #[cfg(feature = "instrument")]
+    pub fn stats(&self, cs: CriticalSection) -> BorrowStats {
+        self.instrument.stats(cs)
+    }
+
+    /// Returns the access counters of this object and resets them to zero
+    #[cfg(feature = "instrument")]
+    pub fn take_stats(&self, cs: CriticalSection) -> BorrowStats {
+        self.instrument.reset(cs)
+    }
+
+    /// Returns how long the closure borrows of this object took, as timed with the counter passed
+    /// to `set_cycle_counter`
+    ///
+    /// Only borrows that pass the stored value to a closure (such as `borrow`, `borrow_mut`, and
+    /// `try_borrow`) are timed, from just before the closure is called until it returns.
+    #[cfg(feature = "profile")]
+    pub fn hold_stats(&self, cs: CriticalSection) -> HoldStats {
+        self.profile.stats(cs)
+    }
+
+    /// Returns how long the closure borrows of this object took, and resets the statistics
+    #[cfg(feature = "profile")]
+    pub fn take_hold_stats(&self, cs: CriticalSection) -> HoldStats {
+        self.profile.reset(cs)
+    }
+
+    /// Sets the stored value for this object
+    ///
+    /// Requires passing in a CriticalSection, such as the one used in `critical_section::with`
+    ///
+    /// A value that is already stored is dropped and replaced. To treat initializing twice as an
+    /// error, use `try_init`.
+    #[track_caller]
+    pub fn init(&self, cs: CriticalSection, value: T) {
+        self.init_at(cs, Location::caller(), value)
+    }
+
+    /// Like `init`, reporting the access at `location`
+    #[track_caller]
+    fn init_at(&self, cs: CriticalSection, location: &'static Location<'static>, value: T) {
+        trace!(
+            "StaticRefCell initialized at {=usize:#x}",
+            self as *const _ as usize
+        );
+        self.update_at(cs, location, |stored| *stored = Some(value));
+        hook!(self, cs, init, location);
+    }
+
+    /// Sets the stored value for this object if it is not yet initialized, or returns `value`
+    /// back if it is
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static UART: StaticRefCell<u8> = StaticRefCell::new();
+    ///
+    /// critical_section::with(|cs| {
+    ///     assert_eq!(UART.try_init(cs, 1), Ok(()));
+    ///     // a second code path initializing the cell is detected
+    ///     assert_eq!(UART.try_init(cs, 2), Err(2));
+    ///     assert_eq!(UART.get(cs), Some(1));
+    /// });
+    /// ```
+    #[track_caller]
+    pub fn try_init(&self, cs: CriticalSection, value: T) -> Result<(), T> {
+        if self.is_initialized(cs) {
+            return Err(value);
+        }
+        self.init(cs, value);
+        Ok(())
+    }
+
+    /// Sets the stored value for this object, dropping and replacing any value already stored
+    ///
+    /// This is the same as `init`, for code that means to overwrite an initialized object, as
+    /// opposed to `try_init`.
+    #[track_caller]
+    pub fn force_init(&self, cs: CriticalSection, value: T) {
+        self.init(cs, value);
+    }
+
+    /// Passes an immutable reference to the stored data in `func` and returns the result, first
+    /// initializing the stored value with the result of `init_func` if it is still None
+    ///
+    /// `init_func` runs before the stored data is borrowed, so it may access this object itself.
+    /// With the `poison` feature, a poisoned value is replaced with the result of `init_func` too,
+    /// which clears the poisoning like `init` does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<i32> = StaticRefCell::new();
+    ///
+    /// let value = critical_section::with(|cs| cell.get_or_init(cs, || 1, |value| *value));
+    /// assert_eq!(value, 1);
+    ///
+    /// // the cell is already initialized, so the constructor is not called again
+    /// let value = critical_section::with(|cs| cell.get_or_init(cs, || 2, |value| *value));
+    /// assert_eq!(value, 1);
+    /// ```
+    #[track_caller]
+    pub fn get_or_init<Y, F1: FnOnce() -> T, F2: FnOnce(&T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        init_func: F1,
+        func: F2,
+    ) -> Y {
+        if self.needs_init(cs) {
+            let value = init_func();
+            self.init(cs, value);
+        }
+        self.borrow(cs, func, || unreachable!("cell was initialized above"))
+    }
+
+    /// Returns true if the stored value for this object is not None
+    ///
+    /// Because interrupts cannot change the stored value while the critical section is held, the
+    /// result stays accurate for the rest of the critical section.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<i32> = StaticRefCell::new();
+    /// assert!(!critical_section::with(|cs| cell.is_initialized(cs)));
+    ///
+    /// critical_section::with(|cs| cell.init(cs, 1));
+    /// assert!(critical_section::with(|cs| cell.is_initialized(cs)));
+    /// ```
+    pub fn is_initialized(&self, _cs: CriticalSection) -> bool {
+        self.initialized.load(Ordering::Acquire)
+    }
+
+    /// Returns true if the stored value for this object is not None, without requiring a
+    /// critical section
+    ///
+    /// This reads an atomic flag that is updated whenever the stored value is set or removed, so
+    /// it is cheap enough to poll from the main loop. The result may already be out of date by the
+    /// time it is used if an interrupt changes the stored value, so it should only gate behavior
+    /// where that is acceptable (or where the value is only ever set once).
+    pub fn is_initialized_relaxed(&self) -> bool {
+        self.initialized.load(Ordering::Acquire)
+    }
+
+    /// Moves the stored value out of this object, leaving it uninitialized (stored value as None)
+    ///
+    /// Returns None if the stored value was already None.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<i32> = StaticRefCell::new();
+    /// critical_section::with(|cs| cell.init(cs, 1));
+    ///
+    /// assert_eq!(critical_section::with(|cs| cell.take(cs)), Some(1));
+    /// assert_eq!(critical_section::with(|cs| cell.take(cs)), None);
+    /// ```
+    #[track_caller]
+    pub fn take(&self, cs: CriticalSection) -> Option<T> {
+        self.take_at(cs, Location::caller())
+    }
+
+    /// Like `take`, reporting the access at `location`
+    #[track_caller]
+    fn take_at(&self, cs: CriticalSection, location: &'static Location<'static>) -> Option<T> {
+        trace!(
+            "StaticRefCell taken at {=usize:#x}",
+            self as *const _ as usize
+        );
+        self.update_at(cs, location, Option::take)
+    }
+
+    /// Drops the stored value (if any) and returns this object to the uninitialized state
+    ///
+    /// Unlike `take`, the value is guaranteed to be dropped before the critical section ends,
+    /// which is useful for releasing peripherals and pins before entering a low-power mode. The
+    /// stored data is no longer borrowed when the value is dropped, so its `Drop` impl may access
+    /// this object.
+    #[track_caller]
+    pub fn deinit(&self, cs: CriticalSection) {
+        drop(self.take(cs));
+    }
+
+    /// Sets the stored value for this object and returns the previously stored value, or None if
+    /// the object was not yet initialized
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<i32> = StaticRefCell::new();
+    ///
+    /// assert_eq!(critical_section::with(|cs| cell.replace(cs, 1)), None);
+    /// assert_eq!(critical_section::with(|cs| cell.replace(cs, 2)), Some(1));
+    /// ```
+    #[track_caller]
+    pub fn replace(&self, cs: CriticalSection, value: T) -> Option<T> {
+        self.update(cs, |stored| stored.replace(value))
+    }
+
+    /// Computes a new stored value from the current one using `func`, and returns the previously
+    /// stored value
+    ///
+    /// `func` receives a mutable reference to the current value, or None if the object is not yet
+    /// initialized. Both steps happen within the same critical section.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<i32> = StaticRefCell::new();
+    ///
+    /// let old = critical_section::with(|cs| cell.replace_with(cs, |old| old.map_or(0, |v| *v + 1)));
+    /// assert_eq!(old, None);
+    ///
+    /// let old = critical_section::with(|cs| cell.replace_with(cs, |old| old.map_or(0, |v| *v + 1)));
+    /// assert_eq!(old, Some(0));
+    /// ```
+    #[track_caller]
+    pub fn replace_with<F: FnOnce(Option<&mut T>) -> T>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Option<T> {
+        self.update(cs, |stored| {
+            let value = func(stored.as_mut());
+            stored.replace(value)
+        })
+    }
+
+    /// Passes the stored value in `func`, and replaces it with the value `func` returns, if any
+    ///
+    /// Returns true if the value was replaced, or false if `func` returned None or the object is
+    /// not yet initialized (in which case `func` isn't called). Both steps happen in a single
+    /// mutable borrow, and nothing is written unless `func` returns a value (with the `dirty`
+    /// feature, the object is still marked dirty, as after any mutable borrow).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently borrowed.
+    ///
+    /// # Examples
+    ///
+    /// Latching only the first fault
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static FIRST_FAULT: StaticRefCell<Option<u8>> = StaticRefCell::new_with(None);
+    ///
+    /// critical_section::with(|cs| {
+    ///     assert!(FIRST_FAULT.compare_and_update(cs, |fault| fault.is_none().then_some(Some(3))));
+    ///     assert!(!FIRST_FAULT.compare_and_update(cs, |fault| fault.is_none().then_some(Some(7))));
+    ///     assert_eq!(FIRST_FAULT.get(cs), Some(Some(3)));
+    /// });
+    /// ```
+    #[track_caller]
+    pub fn compare_and_update<F: FnOnce(&T) -> Option<T>>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> bool {
+        self.borrow_mut_option(cs, |stored| match stored {
+            Some(stored) => match func(&*stored) {
+                Some(value) => {
+                    *stored = value;
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        })
     }
 
-    /// Sets the stored value for this object
+    /// Exchanges the stored values of this object and `other` (including None), within the same
+    /// critical section
     ///
-    /// Requires passing in a CriticalSection, such as the one used in `critical_section::with`
-    pub fn init(&self, cs: CriticalSection, value: T) {
-        *self.0.borrow_ref_mut(cs) = Some(value);
+    /// Swapping an object with itself does nothing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data of either object is currently borrowed.
+    ///
+    /// # Examples
+    ///
+    /// Double-buffering samples between an ISR and the main loop
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static FILLING: StaticRefCell<[u16; 4]> = StaticRefCell::new_with([0; 4]);
+    /// static READY: StaticRefCell<[u16; 4]> = StaticRefCell::new();
+    ///
+    /// // in the ADC ISR, once the buffer is full
+    /// critical_section::with(|cs| {
+    ///     FILLING.borrow_mut(cs, |samples| samples.fill(512), || {});
+    ///     FILLING.swap(cs, &READY);
+    /// });
+    ///
+    /// critical_section::with(|cs| {
+    ///     assert_eq!(READY.get(cs), Some([512; 4]));
+    ///     assert_eq!(FILLING.get(cs), None);
+    /// });
+    /// ```
+    #[track_caller]
+    pub fn swap(&self, cs: CriticalSection, other: &StaticRefCell<T>) {
+        if core::ptr::eq(self, other) {
+            return;
+        }
+        self.update(cs, |stored| {
+            other.update(cs, |other_stored| core::mem::swap(stored, other_stored))
+        });
     }
 
     /// Passes an immutable reference to the data stored by this object in `func` and returns the result,
@@ -110,15 +1217,70 @@ impl<T> StaticRefCell<T> {
     /// let cell_value = critical_section::with(|cs| cell.borrow(cs, |value| value.data, || -1));
     /// assert_eq!(cell_value, 1);
     /// ```
+    #[track_caller]
     pub fn borrow<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
         &self,
         cs: CriticalSection,
         func: F1,
         none_func: F2,
     ) -> Y {
-        match self.0.borrow_ref(cs).as_ref() {
+        self.borrow_at(cs, Location::caller(), func, none_func)
+    }
+
+    /// Like `borrow`, reporting the access at `location`
+    #[track_caller]
+    fn borrow_at<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        location: &'static Location<'static>,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        #[cfg(not(feature = "dyn-dispatch"))]
+        return self.borrow_option_at(cs, location, |value| match value {
             Some(value) => func(value),
             None => none_func(),
+        });
+        #[cfg(feature = "dyn-dispatch")]
+        {
+            let mut funcs = Some((func, none_func));
+            let mut result = None;
+            let trampoline: &mut dyn FnMut(Option<&T>) = &mut |value| {
+                if let Some((func, none_func)) = funcs.take() {
+                    result = Some(match value {
+                        Some(value) => func(value),
+                        None => none_func(),
+                    });
+                }
+            };
+            self.borrow_option_at(cs, location, trampoline);
+            result.unwrap_or_else(|| unreachable!("borrow_option calls its closure"))
+        }
+    }
+
+    /// Passes the stored value in `func`, or None if it is uninitialized or poisoned
+    ///
+    /// With the `dyn-dispatch` feature, `borrow` always calls this with a `&mut dyn FnMut`, so it is
+    /// only instantiated once per payload type.
+    #[track_caller]
+    fn borrow_option_at<Y, F: FnOnce(Option<&T>) -> Y>(
+        &self,
+        cs: CriticalSection,
+        location: &'static Location<'static>,
+        func: F,
+    ) -> Y {
+        match self.stored_at(cs, location).as_ref() {
+            Some(value) if !self.poisoned() => {
+                instrument!(self, cs, borrow);
+                hook!(self, cs, borrow, location);
+                instrument!(self, cs, enter);
+                self.timed(cs, || func(Some(value)))
+            }
+            _ => {
+                instrument!(self, cs, miss);
+                hook!(self, cs, miss, location);
+                func(None)
+            }
         }
     }
 
@@ -144,23 +1306,538 @@ impl<T> StaticRefCell<T> {
     /// let cell_value: MyData = critical_section::with(|cs| cell.borrow(cs, |value| value.clone(), || MyData{data: -1}));
     /// assert_eq!(cell_value, MyData{data: 2});
     /// ```
+    #[track_caller]
     pub fn borrow_mut<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
         &self,
         cs: CriticalSection,
         func: F1,
         none_func: F2,
     ) -> Y {
-        match self.0.borrow_ref_mut(cs).as_mut() {
+        self.borrow_mut_at(cs, Location::caller(), func, none_func)
+    }
+
+    /// Like `borrow_mut`, reporting the access at `location`
+    #[track_caller]
+    fn borrow_mut_at<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        location: &'static Location<'static>,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        #[cfg(not(feature = "dyn-dispatch"))]
+        return self.borrow_mut_option_at(cs, location, |value| match value {
             Some(value) => func(value),
             None => none_func(),
+        });
+        #[cfg(feature = "dyn-dispatch")]
+        {
+            let mut funcs = Some((func, none_func));
+            let mut result = None;
+            let trampoline: &mut dyn FnMut(Option<&mut T>) = &mut |value| {
+                if let Some((func, none_func)) = funcs.take() {
+                    result = Some(match value {
+                        Some(value) => func(value),
+                        None => none_func(),
+                    });
+                }
+            };
+            self.borrow_mut_option_at(cs, location, trampoline);
+            result.unwrap_or_else(|| unreachable!("borrow_mut_option calls its closure"))
+        }
+    }
+
+    /// Passes the stored value in `func`, or None if it is uninitialized or poisoned, poisoning
+    /// this object if `func` panics while holding the value
+    #[track_caller]
+    fn borrow_mut_option<Y, F: FnOnce(Option<&mut T>) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Y {
+        self.borrow_mut_option_at(cs, Location::caller(), func)
+    }
+
+    /// Like `borrow_mut_option`, reporting the access at `location`
+    ///
+    /// With the `dyn-dispatch` feature, `borrow_mut` always calls this with a `&mut dyn FnMut`, so
+    /// it is only instantiated once per payload type.
+    #[track_caller]
+    fn borrow_mut_option_at<Y, F: FnOnce(Option<&mut T>) -> Y>(
+        &self,
+        cs: CriticalSection,
+        location: &'static Location<'static>,
+        func: F,
+    ) -> Y {
+        match self.stored_mut_at(cs, location).as_mut() {
+            Some(value) if !self.poisoned() => {
+                instrument!(self, cs, borrow_mut);
+                hook!(self, cs, borrow_mut, location);
+                instrument!(self, cs, enter);
+                self.timed(cs, || self.call_mut(value, |value| func(Some(value))))
+            }
+            _ => {
+                instrument!(self, cs, miss);
+                hook!(self, cs, miss, location);
+                func(None)
+            }
+        }
+    }
+
+    /// Passes an immutable reference to a part of the stored data, selected by `project`, in `func`
+    /// and returns the result, or returns the result of `none_func` if the stored data is still None
+    ///
+    /// This keeps `func` focused on the one field it needs when the stored data is a large struct
+    /// of shared state. To keep a projected borrow around instead, use `Ref::map` on the guard
+    /// returned by `borrow_ref`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// struct SharedState {
+    ///     rx_count: u16,
+    ///     last_byte: u8,
+    /// }
+    /// let cell: StaticRefCell<SharedState> = StaticRefCell::new();
+    /// critical_section::with(|cs| cell.init(cs, SharedState { rx_count: 3, last_byte: 0 }));
+    ///
+    /// let rx_count = critical_section::with(|cs| {
+    ///     cell.borrow_map(cs, |state| &state.rx_count, |count| *count, || 0)
+    /// });
+    /// assert_eq!(rx_count, 3);
+    /// ```
+    #[track_caller]
+    pub fn borrow_map<U, Y, P, F1, F2>(
+        &self,
+        cs: CriticalSection,
+        project: P,
+        func: F1,
+        none_func: F2,
+    ) -> Y
+    where
+        U: ?Sized,
+        P: FnOnce(&T) -> &U,
+        F1: FnOnce(&U) -> Y,
+        F2: FnOnce() -> Y,
+    {
+        self.borrow(cs, |value| func(project(value)), none_func)
+    }
+
+    /// Passes a mutable reference to a part of the stored data, selected by `project`, in `func`
+    /// and returns the result, or returns the result of `none_func` if the stored data is still None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// struct SharedState {
+    ///     rx_count: u16,
+    ///     last_byte: u8,
+    /// }
+    /// let cell: StaticRefCell<SharedState> = StaticRefCell::new();
+    /// critical_section::with(|cs| cell.init(cs, SharedState { rx_count: 0, last_byte: 0 }));
+    ///
+    /// critical_section::with(|cs| {
+    ///     cell.borrow_map_mut(cs, |state| &mut state.last_byte, |byte| *byte = b'a', || {})
+    /// });
+    /// let last_byte = critical_section::with(|cs| cell.borrow(cs, |state| state.last_byte, || 0));
+    /// assert_eq!(last_byte, b'a');
+    /// ```
+    #[track_caller]
+    pub fn borrow_map_mut<U, Y, P, F1, F2>(
+        &self,
+        cs: CriticalSection,
+        project: P,
+        func: F1,
+        none_func: F2,
+    ) -> Y
+    where
+        U: ?Sized,
+        P: FnOnce(&mut T) -> &mut U,
+        F1: FnOnce(&mut U) -> Y,
+        F2: FnOnce() -> Y,
+    {
+        self.borrow_mut(cs, |value| func(project(value)), none_func)
+    }
+
+    /// Returns a guard holding an immutable borrow of the data stored by this object, or None if
+    /// the stored data is still None
+    ///
+    /// The guard cannot outlive the critical section, which allows calling several methods on the
+    /// stored data directly instead of going through a closure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently mutably borrowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<[u8; 4]> = StaticRefCell::new();
+    /// critical_section::with(|cs| cell.init(cs, [1, 2, 3, 4]));
+    ///
+    /// let sum: u8 = critical_section::with(|cs| {
+    ///     let values = cell.borrow_ref(cs).unwrap();
+    ///     values.iter().sum()
+    /// });
+    /// assert_eq!(sum, 10);
+    /// ```
+    #[track_caller]
+    pub fn borrow_ref<'cs>(&'cs self, cs: CriticalSection<'cs>) -> Option<Ref<'cs, T>> {
+        let value = Ref::filter_map(self.stored(cs), Option::as_ref)
+            .ok()
+            .filter(|_| !self.poisoned());
+        instrument!(self, cs, found, false, value.is_some());
+        hook!(self, cs, found, false, value.is_some(), Location::caller());
+        value
+    }
+
+    /// Returns a guard holding a mutable borrow of the data stored by this object, or None if the
+    /// stored data is still None
+    ///
+    /// The guard cannot outlive the critical section.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stored data is currently borrowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<[u8; 4]> = StaticRefCell::new();
+    /// critical_section::with(|cs| cell.init(cs, [1, 2, 3, 4]));
+    ///
+    /// critical_section::with(|cs| {
+    ///     if let Some(mut values) = cell.borrow_ref_mut(cs) {
+    ///         values.reverse();
+    ///         values[0] = 0;
+    ///     }
+    /// });
+    /// assert_eq!(critical_section::with(|cs| cell.get(cs)), Some([0, 3, 2, 1]));
+    /// ```
+    #[track_caller]
+    pub fn borrow_ref_mut<'cs>(&'cs self, cs: CriticalSection<'cs>) -> Option<RefMut<'cs, T>> {
+        let value = RefMut::filter_map(self.stored_mut(cs), Option::as_mut)
+            .ok()
+            .filter(|_| !self.poisoned());
+        if value.is_some() {
+            self.mark_dirty();
         }
+        instrument!(self, cs, found, true, value.is_some());
+        hook!(self, cs, found, true, value.is_some(), Location::caller());
+        value
+    }
+
+    /// Returns a guard holding an immutable borrow of the data stored by this object, or a
+    /// `BorrowError` if the stored data is still None or is already mutably borrowed
+    #[track_caller]
+    pub fn try_borrow_ref<'cs>(
+        &'cs self,
+        cs: CriticalSection<'cs>,
+    ) -> Result<Ref<'cs, T>, BorrowError> {
+        let result = match self.data.borrow(cs).try_borrow() {
+            Err(_) => {
+                diagnose!(self, cs, conflict, Location::caller());
+                Err(BorrowError::AlreadyBorrowed)
+            }
+            Ok(stored) if stored.is_some() && self.poisoned() => Err(BorrowError::Poisoned),
+            Ok(stored) => {
+                diagnose!(self, cs, borrowed, Location::caller());
+                Ref::filter_map(stored, Option::as_ref).map_err(|_| BorrowError::Uninitialized)
+            }
+        };
+        instrument!(self, cs, record, false, &result);
+        hook!(self, cs, record, false, &result, Location::caller());
+        result
+    }
+
+    /// Returns a guard holding a mutable borrow of the data stored by this object, or a
+    /// `BorrowError` if the stored data is still None or is already borrowed
+    #[track_caller]
+    pub fn try_borrow_ref_mut<'cs>(
+        &'cs self,
+        cs: CriticalSection<'cs>,
+    ) -> Result<RefMut<'cs, T>, BorrowError> {
+        let result = match self.data.borrow(cs).try_borrow_mut() {
+            Err(_) => {
+                diagnose!(self, cs, conflict, Location::caller());
+                Err(BorrowError::AlreadyBorrowed)
+            }
+            Ok(stored) if stored.is_some() && self.poisoned() => Err(BorrowError::Poisoned),
+            Ok(stored) => {
+                diagnose!(self, cs, borrowed, Location::caller());
+                let value = RefMut::filter_map(stored, Option::as_mut)
+                    .map_err(|_| BorrowError::Uninitialized);
+                if value.is_ok() {
+                    self.mark_dirty();
+                }
+                value
+            }
+        };
+        instrument!(self, cs, record, true, &result);
+        hook!(self, cs, record, true, &result, Location::caller());
+        result
+    }
+
+    /// Passes an immutable reference to the data stored by this object in `func` and returns the result,
+    /// or returns a `BorrowError` if the stored data is still None or is already mutably borrowed
+    ///
+    /// Unlike `borrow`, this never panics on a conflicting borrow, which makes it convenient for
+    /// propagating errors with `?`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::{BorrowError, StaticRefCell};
+    /// #
+    /// let cell: StaticRefCell<i32> = StaticRefCell::new();
+    ///
+    /// let result = critical_section::with(|cs| cell.try_borrow(cs, |value| *value));
+    /// assert_eq!(result, Err(BorrowError::Uninitialized));
+    ///
+    /// critical_section::with(|cs| cell.init(cs, 1));
+    ///
+    /// let result = critical_section::with(|cs| cell.try_borrow(cs, |value| *value));
+    /// assert_eq!(result, Ok(1));
+    /// ```
+    #[track_caller]
+    pub fn try_borrow<Y, F: FnOnce(&T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, BorrowError> {
+        let value = self.try_borrow_ref(cs)?;
+        instrument!(self, cs, enter);
+        Ok(self.timed(cs, || func(&value)))
+    }
+
+    /// Passes a mutable reference to the data stored by this object in `func` and returns the result,
+    /// or returns a `BorrowError` if the stored data is still None or is already borrowed
+    ///
+    /// # Examples
+    ///
+    /// A nested borrow is reported as an error rather than panicking
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::{BorrowError, StaticRefCell};
+    /// #
+    /// let cell: StaticRefCell<i32> = StaticRefCell::new();
+    /// critical_section::with(|cs| cell.init(cs, 1));
+    ///
+    /// let result = critical_section::with(|cs| {
+    ///     cell.try_borrow_mut(cs, |_| cell.try_borrow_mut(cs, |value| *value += 1))
+    /// });
+    /// assert_eq!(result, Ok(Err(BorrowError::AlreadyBorrowed)));
+    /// ```
+    #[track_caller]
+    pub fn try_borrow_mut<Y, F: FnOnce(&mut T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, BorrowError> {
+        let mut value = self.try_borrow_ref_mut(cs)?;
+        instrument!(self, cs, enter);
+        Ok(self.timed(cs, || self.call_mut(&mut value, func)))
+    }
+
+    /// Opens a critical section and passes an immutable reference to the stored data in `func`,
+    /// returning the result, or None if the stored data is still None
+    ///
+    /// This is shorthand for calling `borrow` inside `critical_section::with`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static CELL: StaticRefCell<i32> = StaticRefCell::new();
+    ///
+    /// assert_eq!(CELL.with(|value| *value), None);
+    ///
+    /// critical_section::with(|cs| CELL.init(cs, 1));
+    /// assert_eq!(CELL.with(|value| *value), Some(1));
+    /// ```
+    #[track_caller]
+    pub fn with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Option<Y> {
+        let location = Location::caller();
+        critical_section::with(|cs| {
+            self.borrow_at(cs, location, |value| Some(func(value)), || None)
+        })
+    }
+
+    /// Opens a critical section and passes a mutable reference to the stored data in `func`,
+    /// returning the result, or None if the stored data is still None
+    ///
+    /// This is shorthand for calling `borrow_mut` inside `critical_section::with`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static CELL: StaticRefCell<i32> = StaticRefCell::new();
+    /// critical_section::with(|cs| CELL.init(cs, 1));
+    ///
+    /// CELL.with_mut(|value| *value += 1);
+    /// assert_eq!(CELL.with(|value| *value), Some(2));
+    /// ```
+    #[track_caller]
+    pub fn with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Option<Y> {
+        let location = Location::caller();
+        critical_section::with(|cs| {
+            self.borrow_mut_at(cs, location, |value| Some(func(value)), || None)
+        })
+    }
+
+    /// Passes a mutable reference to the stored data in `func` without a critical section,
+    /// returning the result, or None if the stored data is still None
+    ///
+    /// This is meant for the setup phase before interrupts are enabled, when there is no
+    /// concurrency to protect against, so the cost of entering a critical section can be skipped.
+    ///
+    /// # Safety
+    ///
+    /// Nothing else may access this object while `func` runs: interrupts must be disabled (as
+    /// they are at reset, before `interrupt::enable()`), and on multi-core parts no other core may
+    /// be running code that accesses it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// static CONFIG: StaticRefCell<[u8; 4]> = StaticRefCell::new_with([0; 4]);
+    ///
+    /// // SAFETY: interrupts are not enabled yet
+    /// unsafe { CONFIG.borrow_mut_unchecked(|config| config[0] = 9) };
+    ///
+    /// // interrupts enabled here...
+    /// assert_eq!(critical_section::with(|cs| CONFIG.get(cs)), Some([9, 0, 0, 0]));
+    /// ```
+    #[track_caller]
+    pub unsafe fn borrow_mut_unchecked<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Option<Y> {
+        // SAFETY: the caller guarantees that nothing else can access this object, which is the
+        // guarantee a critical section provides
+        let cs = unsafe { CriticalSection::new() };
+        self.borrow_mut(cs, |value| Some(func(value)), || None)
+    }
+}
+
+impl<T: Default> StaticRefCell<T> {
+    /// Sets the stored value for this object to the default value of `T`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// #[derive(Default)]
+    /// struct State {
+    ///     count: u16,
+    ///     active: bool,
+    /// }
+    /// let cell: StaticRefCell<State> = StaticRefCell::new();
+    /// critical_section::with(|cs| cell.init_default(cs));
+    ///
+    /// let count = critical_section::with(|cs| cell.borrow(cs, |state| state.count, || 1));
+    /// assert_eq!(count, 0);
+    /// ```
+    #[track_caller]
+    pub fn init_default(&self, cs: CriticalSection) {
+        self.init(cs, T::default());
+    }
+}
+
+impl<T: Copy> StaticRefCell<T> {
+    /// Returns a copy of the stored value, or None if the stored value is still None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<u8> = StaticRefCell::new();
+    /// assert_eq!(critical_section::with(|cs| cell.get(cs)), None);
+    ///
+    /// critical_section::with(|cs| cell.set(cs, 3));
+    /// assert_eq!(critical_section::with(|cs| cell.get(cs)), Some(3));
+    /// ```
+    #[track_caller]
+    pub fn get(&self, cs: CriticalSection) -> Option<T> {
+        self.borrow(cs, |value| Some(*value), || None)
+    }
+
+    /// Returns a copy of the stored value, or `default` if the stored value is still None
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// let cell: StaticRefCell<bool> = StaticRefCell::new();
+    /// assert!(!critical_section::with(|cs| cell.get_or(cs, false)));
+    /// ```
+    #[track_caller]
+    pub fn get_or(&self, cs: CriticalSection, default: T) -> T {
+        self.borrow(cs, |value| *value, || default)
+    }
+
+    /// Sets the stored value for this object
+    ///
+    /// This is equivalent to `init`, but reads more naturally for simple flags and counters that
+    /// are overwritten repeatedly.
+    #[track_caller]
+    pub fn set(&self, cs: CriticalSection, value: T) {
+        self.init(cs, value);
+    }
+}
+
+impl<T: Clone> StaticRefCell<T> {
+    /// Returns a clone of the stored value, or None if the stored value is still None
+    ///
+    /// The clone is made within the critical section, so it is a consistent copy that can be
+    /// worked on afterwards without blocking interrupts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// #
+    /// #[derive(Clone)]
+    /// struct Telemetry {
+    ///     voltages: [u16; 8],
+    ///     faults: u8,
+    /// }
+    /// static TELEMETRY: StaticRefCell<Telemetry> = StaticRefCell::new();
+    /// critical_section::with(|cs| TELEMETRY.init(cs, Telemetry { voltages: [330; 8], faults: 0 }));
+    ///
+    /// // in the main loop
+    /// let telemetry = critical_section::with(|cs| TELEMETRY.snapshot(cs)).unwrap();
+    /// let total: u32 = telemetry.voltages.iter().map(|&v| u32::from(v)).sum();
+    /// assert_eq!(total, 2640);
+    /// ```
+    #[track_caller]
+    pub fn snapshot(&self, cs: CriticalSection) -> Option<T> {
+        self.borrow(cs, |value| Some(value.clone()), || None)
+    }
+}
+
+impl<T> Default for StaticRefCell<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use critical_section;
 
     // note: test uses a critical section implementation using critical-section's std feature
     #[test]
@@ -182,4 +1859,60 @@ mod tests {
         let my_value = critical_section::with(|cs| my_data.borrow(cs, |value| value.data, || 0));
         assert_eq!(my_value, 2);
     }
+
+    #[test]
+    fn deinit_drops_value() {
+        use core::sync::atomic::AtomicUsize;
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Peripheral;
+
+        impl Drop for Peripheral {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let cell: StaticRefCell<Peripheral> = StaticRefCell::new();
+        critical_section::with(|cs| cell.init(cs, Peripheral));
+
+        critical_section::with(|cs| {
+            cell.deinit(cs);
+            assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+            assert!(!cell.is_initialized(cs));
+        });
+
+        // deinit on an uninitialized cell is a no-op
+        critical_section::with(|cs| cell.deinit(cs));
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+    }
+
+    #[cfg(feature = "debug-borrows")]
+    #[test]
+    #[should_panic(expected = "StaticRefCell `NESTED` is already borrowed")]
+    fn nested_borrow_reports_conflict() {
+        static NESTED: StaticRefCell<i32> = StaticRefCell::new_with(0).named("NESTED");
+
+        critical_section::with(|cs| {
+            NESTED.borrow_mut(cs, |_| NESTED.borrow_mut(cs, |_| {}, || {}), || {})
+        });
+    }
+
+    #[cfg(feature = "poison")]
+    #[test]
+    fn get_or_init_replaces_poisoned_value() {
+        extern crate std;
+
+        static POISONED: StaticRefCell<i32> = StaticRefCell::new_with(1);
+
+        let _ = std::panic::catch_unwind(|| {
+            critical_section::with(|cs| POISONED.borrow_mut(cs, |_| panic!("torn update"), || {}))
+        });
+        critical_section::with(|cs| {
+            assert!(POISONED.is_poisoned(cs));
+            assert_eq!(POISONED.get_or_init(cs, || 2, |value| *value), 2);
+            assert!(!POISONED.is_poisoned(cs));
+        });
+    }
 }
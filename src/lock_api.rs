@@ -0,0 +1,108 @@
+//! Compatibility with `lock_api`
+//!
+//! `RawCsMutex` implements `lock_api::RawMutex` with the same critical sections used by
+//! `StaticRefCell`, so data can be handed to third-party crates that are generic over `lock_api`.
+//! A `StaticRefCell` converts into a `Mutex<Option<T>>` and back.
+//!
+//! Requires the `lock_api` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::lock_api::Mutex;
+//! use embedded_static_ref_cell::StaticRefCell;
+//!
+//! static COUNT: Mutex<u32> = Mutex::new(0);
+//!
+//! *COUNT.lock() += 1;
+//! assert_eq!(*COUNT.lock(), 1);
+//!
+//! // a cell converts to a mutex holding its Option, and back
+//! let mutex: Mutex<Option<u8>> = StaticRefCell::new_with(7).into();
+//! assert_eq!(*mutex.lock(), Some(7));
+//! let cell = StaticRefCell::from(mutex);
+//! assert_eq!(critical_section::with(|cs| cell.get(cs)), Some(7));
+//! ```
+
+use crate::StaticRefCell;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use critical_section::RestoreState;
+
+/// A `lock_api::RawMutex` that locks by entering a critical section, like `StaticRefCell`
+///
+/// The critical section is held for as long as the mutex is locked, so guards should be short
+/// lived, and guards of different mutexes should be dropped in the reverse order they were locked
+/// (as they are when they go out of scope), since critical sections must end in that order.
+/// Locking a mutex that is already locked (for example, from an ISR that preempted the owner before
+/// it locked) panics, because waiting for it would never end.
+pub struct RawCsMutex {
+    locked: AtomicBool,
+    restore: UnsafeCell<RestoreState>,
+}
+
+// SAFETY: `restore` is only accessed by the context holding the lock, inside its critical section
+unsafe impl Sync for RawCsMutex {}
+
+// SAFETY: while locked, the owner is inside a critical section, and `locked` makes every other
+// attempt to lock fail until `unlock`
+unsafe impl ::lock_api::RawMutex for RawCsMutex {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = Self {
+        locked: AtomicBool::new(false),
+        restore: UnsafeCell::new(RestoreState::invalid()),
+    };
+
+    type GuardMarker = ::lock_api::GuardNoSend;
+
+    fn lock(&self) {
+        assert!(self.try_lock(), "RawCsMutex is already locked");
+    }
+
+    fn try_lock(&self) -> bool {
+        // SAFETY: the critical section is released below, or by `unlock`
+        let restore = unsafe { critical_section::acquire() };
+        if self.locked.load(Ordering::Acquire) {
+            // SAFETY: this releases the critical section acquired above
+            unsafe { critical_section::release(restore) };
+            return false;
+        }
+        self.locked.store(true, Ordering::Release);
+        // SAFETY: the lock was just taken, so nothing else accesses `restore`
+        unsafe { *self.restore.get() = restore };
+        true
+    }
+
+    unsafe fn unlock(&self) {
+        // SAFETY: the caller holds the lock, so nothing else accesses `restore`
+        let restore = unsafe { *self.restore.get() };
+        self.locked.store(false, Ordering::Release);
+        // SAFETY: this releases the critical section acquired in `try_lock`
+        unsafe { critical_section::release(restore) };
+    }
+
+    fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Acquire)
+    }
+}
+
+/// A `lock_api` mutex that uses `RawCsMutex`
+pub type Mutex<T> = ::lock_api::Mutex<RawCsMutex, T>;
+
+/// A guard for a `Mutex`
+pub type MutexGuard<'a, T> = ::lock_api::MutexGuard<'a, RawCsMutex, T>;
+
+impl<T> From<StaticRefCell<T>> for Mutex<Option<T>> {
+    fn from(cell: StaticRefCell<T>) -> Self {
+        Mutex::new(cell.into_inner())
+    }
+}
+
+impl<T> From<Mutex<Option<T>>> for StaticRefCell<T> {
+    fn from(mutex: Mutex<Option<T>>) -> Self {
+        match mutex.into_inner() {
+            Some(value) => StaticRefCell::new_with(value),
+            None => StaticRefCell::new(),
+        }
+    }
+}
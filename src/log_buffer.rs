@@ -0,0 +1,143 @@
+//! Deferred logging from ISRs
+//!
+//! Formatting and printing log messages inside an ISR keeps interrupts masked for far too long. A
+//! `Logger` instead lets ISRs push small fixed-size `Record`s into a ring buffer, which the main
+//! loop drains and formats later, outside of interrupt context. Records that don't fit are dropped
+//! and counted.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::log_buffer::{Level, Logger};
+//!
+//! const ERR_FRAMING: u16 = 3;
+//!
+//! static LOG: Logger<8> = Logger::new();
+//!
+//! // in the UART ISR
+//! critical_section::with(|cs| LOG.log(cs, Level::Warn, ERR_FRAMING, 0x7e));
+//!
+//! // in the main loop
+//! let mut printed = 0;
+//! LOG.drain(|record| {
+//!     // format and print the record, e.g. "[WARN] 3: 0x7e"
+//!     assert_eq!((record.level, record.code, record.payload), (Level::Warn, ERR_FRAMING, 0x7e));
+//!     printed += 1;
+//! });
+//! assert_eq!(printed, 1);
+//! ```
+
+use crate::spsc::Queue;
+use core::fmt;
+use critical_section::CriticalSection;
+
+/// The severity of a `Record`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// A failure that needs attention
+    Error,
+    /// Something unexpected that was handled
+    Warn,
+    /// A notable event
+    Info,
+    /// Detailed information for debugging
+    Debug,
+    /// Very detailed information for debugging
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        })
+    }
+}
+
+/// A log entry: a level, an application-defined event code, and a small payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    /// The severity of the event
+    pub level: Level,
+    /// An application-defined code identifying the event
+    pub code: u16,
+    /// Extra data for the event, such as a register value
+    pub payload: u32,
+}
+
+impl fmt::Display for Record {
+    /// Formats the record as `[LEVEL] code: 0xpayload`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {:#x}", self.level, self.code, self.payload)
+    }
+}
+
+/// A buffer of up to `N` log records, written by ISRs and drained by the main loop
+pub struct Logger<const N: usize>(Queue<Record, N>);
+
+impl<const N: usize> Logger<N> {
+    /// Creates a new empty logger
+    pub const fn new() -> Self {
+        Self(Queue::new())
+    }
+
+    /// Adds a record, or returns false (and counts it as dropped) if the buffer is full
+    pub fn log(&self, cs: CriticalSection, level: Level, code: u16, payload: u32) -> bool {
+        let record = Record {
+            level,
+            code,
+            payload,
+        };
+        self.0.enqueue(cs, record).is_ok()
+    }
+
+    /// Removes the oldest record and returns it, or returns None if the buffer is empty
+    pub fn pop(&self, cs: CriticalSection) -> Option<Record> {
+        self.0.dequeue(cs)
+    }
+
+    /// Removes every record in order and passes each one to `func`, returning how many there were
+    ///
+    /// Each record is removed in its own critical section, and `func` is called outside of it, so
+    /// formatting and printing records doesn't keep interrupts masked. This must not be called from
+    /// within a critical section.
+    pub fn drain<F: FnMut(Record)>(&self, mut func: F) -> usize {
+        let mut count = 0;
+        while let Some(record) = critical_section::with(|cs| self.pop(cs)) {
+            func(record);
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns the number of records in the buffer
+    pub fn len(&self, cs: CriticalSection) -> usize {
+        self.0.len(cs)
+    }
+
+    /// Returns true if the buffer holds no records
+    pub fn is_empty(&self, cs: CriticalSection) -> bool {
+        self.0.is_empty(cs)
+    }
+
+    /// Returns the number of records dropped because the buffer was full (saturating at
+    /// `usize::MAX`)
+    pub fn dropped_count(&self, cs: CriticalSection) -> usize {
+        self.0.overflow_count(cs)
+    }
+
+    /// Returns the number of records dropped because the buffer was full, and resets it to zero
+    pub fn take_dropped_count(&self, cs: CriticalSection) -> usize {
+        self.0.take_overflow_count(cs)
+    }
+}
+
+impl<const N: usize> Default for Logger<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,117 @@
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+struct Slot<T> {
+    value: Option<T>,
+    missed: bool,
+}
+
+/// A single-slot mailbox that always holds the latest value posted to it
+///
+/// The producer (usually an ISR) posts values, overwriting any value that hasn't been taken yet,
+/// and the consumer (usually the main loop) takes them. Overwriting an unread value sets a flag so
+/// the consumer can tell that it missed one. This suits data where only the newest value matters,
+/// such as the latest ADC sample or the last received packet.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::Mailbox;
+/// #
+/// static LATEST_SAMPLE: Mailbox<u16> = Mailbox::new();
+///
+/// // in the ADC ISR
+/// critical_section::with(|cs| {
+///     LATEST_SAMPLE.post(cs, 511);
+///     LATEST_SAMPLE.post(cs, 512);
+/// });
+///
+/// // in the main loop
+/// critical_section::with(|cs| {
+///     assert_eq!(LATEST_SAMPLE.take(cs), Some(512));
+///     assert!(LATEST_SAMPLE.take_missed(cs));
+///     assert_eq!(LATEST_SAMPLE.take(cs), None);
+/// });
+/// ```
+pub struct Mailbox<T>(Mutex<RefCell<Slot<T>>>);
+
+impl<T> Mailbox<T> {
+    /// Creates a new empty mailbox
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(Slot {
+            value: None,
+            missed: false,
+        })))
+    }
+
+    /// Stores `value` in the mailbox, returning the unread value it overwrote (if any)
+    ///
+    /// Overwriting an unread value sets the missed flag.
+    pub fn post(&self, cs: CriticalSection, value: T) -> Option<T> {
+        let mut slot = self.0.borrow_ref_mut(cs);
+        let previous = slot.value.replace(value);
+        if previous.is_some() {
+            slot.missed = true;
+        }
+        previous
+    }
+
+    /// Moves the unread value out of the mailbox, or returns None if there isn't one
+    pub fn take(&self, cs: CriticalSection) -> Option<T> {
+        self.0.borrow_ref_mut(cs).value.take()
+    }
+
+    /// Passes an immutable reference to the unread value in `func` and returns the result, or
+    /// returns the result of `none_func` if there isn't one
+    ///
+    /// The value stays in the mailbox.
+    pub fn peek<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        match self.0.borrow_ref(cs).value.as_ref() {
+            Some(value) => func(value),
+            None => none_func(),
+        }
+    }
+
+    /// Returns true if the mailbox holds an unread value
+    pub fn has_value(&self, cs: CriticalSection) -> bool {
+        self.0.borrow_ref(cs).value.is_some()
+    }
+
+    /// Returns true if an unread value has been overwritten since the missed flag was last cleared
+    pub fn missed(&self, cs: CriticalSection) -> bool {
+        self.0.borrow_ref(cs).missed
+    }
+
+    /// Returns true if an unread value has been overwritten since the missed flag was last cleared,
+    /// and clears the flag
+    pub fn take_missed(&self, cs: CriticalSection) -> bool {
+        core::mem::take(&mut self.0.borrow_ref_mut(cs).missed)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T: ufmt::uDebug> ufmt::uDebug for Mailbox<T> {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        critical_section::with(|cs| {
+            let slot = self.0.borrow_ref(cs);
+            f.debug_struct("Mailbox")?
+                .field("value", &slot.value)?
+                .field("missed", &slot.missed)?
+                .finish()
+        })
+    }
+}
+
+impl<T> Default for Mailbox<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,177 @@
+use crate::pod::{bytes_of, crc32};
+use crate::Pod;
+use core::cell::UnsafeCell;
+use critical_section::CriticalSection;
+
+/// Marks a `NoinitCell` as holding a value, distinguishing it from the random contents of
+/// uninitialized memory
+const MAGIC: u32 = 0x4e4f_494e;
+
+/// Every field is valid for any bit pattern, so the data can live in memory that isn't initialized
+/// at startup
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Data<T> {
+    magic: u32,
+    crc: u32,
+    value: T,
+}
+
+/// A cell for plain data that survives a warm reset, such as reset counters and breadcrumbs
+///
+/// The static must be placed in a section that isn't zeroed or initialized at startup, which
+/// `noinit!` does on AVR (`.noinit`) and Cortex-M (`.uninit`, with `cortex-m-rt`). Alongside the
+/// value, the cell stores a marker and a CRC-32 of the value, so at startup `validate` can tell a
+/// warm reset (the value is kept) from a cold boot (memory holds random contents, so the value is
+/// reset to a default).
+///
+/// A `StaticRefCell` itself can't live in memory that isn't initialized, because its borrow state
+/// must be valid from the start, so this cell is restricted to `Pod` values and hands out copies.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::noinit;
+/// #
+/// noinit! {
+///     static RESETS: u32 = 0;
+/// }
+///
+/// // at startup
+/// critical_section::with(|cs| {
+///     if !RESETS.validate(cs, 0) {
+///         // cold boot, the count was reset to 0
+///     }
+///     RESETS.update(cs, |count| count + 1);
+/// });
+/// ```
+pub struct NoinitCell<T: Pod>(UnsafeCell<Data<T>>);
+
+// SAFETY: the data is only accessed within critical sections
+unsafe impl<T: Pod + Send> Sync for NoinitCell<T> {}
+
+impl<T: Pod> NoinitCell<T> {
+    /// Creates a new object marked as not holding a value
+    ///
+    /// When the static is placed in a `.noinit` section, this value is never actually written, so
+    /// `validate` must be called at startup before the value is used.
+    pub const fn new_noinit(value: T) -> Self {
+        Self(UnsafeCell::new(Data {
+            magic: 0,
+            crc: 0,
+            value,
+        }))
+    }
+
+    fn read(&self) -> Data<T> {
+        // SAFETY: the data is only accessed within critical sections, and any bit pattern is
+        // valid. The read is volatile because the memory may have changed outside the program's
+        // view (across a reset).
+        unsafe { self.0.get().read_volatile() }
+    }
+
+    /// Returns true if the stored marker and CRC match the value, as after a warm reset
+    pub fn is_valid(&self, _cs: CriticalSection) -> bool {
+        let data = self.read();
+        data.magic == MAGIC && data.crc == crc32(bytes_of(&data.value))
+    }
+
+    /// Checks the stored value, resetting it to `default` if it isn't valid
+    ///
+    /// Returns true if the value was kept (a warm reset), or false if it was reset (a cold boot).
+    pub fn validate(&self, cs: CriticalSection, default: T) -> bool {
+        let valid = self.is_valid(cs);
+        if !valid {
+            self.set(cs, default);
+        }
+        valid
+    }
+
+    /// Returns a copy of the stored value, or None if it isn't valid
+    pub fn get(&self, cs: CriticalSection) -> Option<T> {
+        if self.is_valid(cs) {
+            Some(self.read().value)
+        } else {
+            None
+        }
+    }
+
+    /// Sets the stored value, marking it as valid
+    pub fn set(&self, _cs: CriticalSection, value: T) {
+        let data = Data {
+            magic: MAGIC,
+            crc: crc32(bytes_of(&value)),
+            value,
+        };
+        // SAFETY: the data is only accessed within critical sections
+        unsafe { self.0.get().write_volatile(data) };
+    }
+
+    /// Computes a new stored value from the current one using `func`, returning false (and not
+    /// calling `func`) if the value isn't valid
+    pub fn update<F: FnOnce(T) -> T>(&self, cs: CriticalSection, func: F) -> bool {
+        match self.get(cs) {
+            Some(value) => {
+                self.set(cs, func(value));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Marks the stored value as not valid, so it is reset on the next startup
+    pub fn invalidate(&self, _cs: CriticalSection) {
+        // SAFETY: the data is only accessed within critical sections
+        unsafe { core::ptr::addr_of_mut!((*self.0.get()).magic).write_volatile(0) };
+    }
+}
+
+/// Declares `NoinitCell` statics, placed in memory that isn't initialized at startup
+///
+/// Each static is written as `static NAME: T = value;` and becomes a `NoinitCell<T>`, where
+/// `value` is only used when memory is initialized anyway (such as on the host). The static is
+/// placed in `.noinit` on AVR and in `.uninit` on Cortex-M (as set up by `cortex-m-rt`), and other
+/// targets need their own `#[link_section]` attribute. Attributes (such as doc comments) are
+/// applied to the static.
+#[macro_export]
+macro_rules! noinit {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $value:expr;)*) => {
+        $(
+            $(#[$attr])*
+            #[cfg_attr(target_arch = "avr", link_section = ".noinit")]
+            #[cfg_attr(all(target_arch = "arm", target_os = "none"), link_section = ".uninit.noinit")]
+            $vis static $name: $crate::NoinitCell<$ty> = $crate::NoinitCell::new_noinit($value);
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_detects_corruption() {
+        critical_section::with(|cs| {
+            let cell = NoinitCell::new_noinit(5u32);
+            assert_eq!(cell.get(cs), None);
+            assert!(!cell.validate(cs, 7));
+            assert!(cell.validate(cs, 0));
+            assert!(cell.update(cs, |count| count + 1));
+            assert_eq!(cell.get(cs), Some(8));
+
+            // a bit flipped in the value, as after power was lost
+            unsafe { (*cell.0.get()).value ^= 0x10 };
+            assert!(!cell.is_valid(cs));
+            assert!(!cell.validate(cs, 0));
+            assert_eq!(cell.get(cs), Some(0));
+
+            cell.invalidate(cs);
+            assert!(!cell.update(cs, |count| count + 1));
+        });
+    }
+
+    #[test]
+    fn crc32_matches_reference() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+}
@@ -0,0 +1,158 @@
+use core::cell::{Cell, RefCell};
+use critical_section::{CriticalSection, Mutex};
+
+/// A function called with the new value of an `ObservableCell`
+pub type Observer<T> = fn(&T);
+
+/// A cell that runs registered observers after its value changes
+///
+/// ISRs change the value as usual, which only marks it as changed. The main loop calls `poll`,
+/// which copies the value within a critical section and then calls every observer with the copy
+/// outside of it, so reactions to a change (which may be slow) never run with interrupts masked,
+/// and the code making a change doesn't need to know what reacts to it.
+///
+/// Up to `N` observers can be registered.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::ObservableCell;
+/// # use core::sync::atomic::{AtomicU16, Ordering};
+/// # static SHOWN: AtomicU16 = AtomicU16::new(0);
+/// #
+/// static RPM: ObservableCell<u16, 2> = ObservableCell::new(0);
+///
+/// fn update_display(rpm: &u16) {
+///     SHOWN.store(*rpm, Ordering::Relaxed);
+/// }
+///
+/// critical_section::with(|cs| RPM.subscribe(cs, update_display)).unwrap();
+///
+/// // in the tachometer ISR
+/// critical_section::with(|cs| RPM.set(cs, 1800));
+///
+/// // in the main loop
+/// assert!(RPM.poll());
+/// assert_eq!(SHOWN.load(Ordering::Relaxed), 1800);
+/// assert!(!RPM.poll());
+/// ```
+pub struct ObservableCell<T, const N: usize> {
+    value: Mutex<RefCell<T>>,
+    changed: Mutex<Cell<bool>>,
+    observers: Mutex<Cell<[Option<Observer<T>>; N]>>,
+}
+
+impl<T, const N: usize> ObservableCell<T, N> {
+    /// Creates a new object holding `value`, with no observers
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: Mutex::new(RefCell::new(value)),
+            changed: Mutex::new(Cell::new(false)),
+            observers: Mutex::new(Cell::new([None; N])),
+        }
+    }
+
+    /// Registers `observer`, or returns it back if `N` observers are already registered
+    pub fn subscribe(&self, cs: CriticalSection, observer: Observer<T>) -> Result<(), Observer<T>> {
+        let cell = self.observers.borrow(cs);
+        let mut observers = cell.get();
+        match observers.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(observer);
+                cell.set(observers);
+                Ok(())
+            }
+            None => Err(observer),
+        }
+    }
+
+    /// Removes every registration of `observer`, returning true if it was registered
+    pub fn unsubscribe(&self, cs: CriticalSection, observer: Observer<T>) -> bool {
+        let cell = self.observers.borrow(cs);
+        let mut observers = cell.get();
+        let mut found = false;
+        for slot in observers.iter_mut() {
+            if slot.is_some_and(|registered| core::ptr::fn_addr_eq(registered, observer)) {
+                *slot = None;
+                found = true;
+            }
+        }
+        cell.set(observers);
+        found
+    }
+
+    /// Passes an immutable reference to the stored value in `func` and returns the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn with<Y, F: FnOnce(&T) -> Y>(&self, cs: CriticalSection, func: F) -> Y {
+        func(&self.value.borrow_ref(cs))
+    }
+
+    /// Passes a mutable reference to the stored value in `func`, marks the value as changed, and
+    /// returns the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, cs: CriticalSection, func: F) -> Y {
+        let result = func(&mut self.value.borrow_ref_mut(cs));
+        self.changed.borrow(cs).set(true);
+        result
+    }
+
+    /// Sets the stored value, marking it as changed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn set(&self, cs: CriticalSection, value: T) {
+        self.with_mut(cs, |stored| *stored = value);
+    }
+
+    /// Returns true if the value changed since the last `poll`
+    pub fn is_changed(&self, cs: CriticalSection) -> bool {
+        self.changed.borrow(cs).get()
+    }
+}
+
+impl<T: Clone, const N: usize> ObservableCell<T, N> {
+    /// Calls every observer with a copy of the value if it changed since the last poll, returning
+    /// true if it did
+    ///
+    /// The copy is made in a critical section, but the observers are called outside of it, so
+    /// this must not be called from a critical section or an ISR. Changes made while the
+    /// observers run are picked up by the next poll.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn poll(&self) -> bool {
+        let changed = critical_section::with(|cs| {
+            if self.changed.borrow(cs).replace(false) {
+                Some((
+                    self.value.borrow_ref(cs).clone(),
+                    self.observers.borrow(cs).get(),
+                ))
+            } else {
+                None
+            }
+        });
+        match changed {
+            Some((value, observers)) => {
+                for observer in observers.iter().flatten() {
+                    observer(&value);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T: Default, const N: usize> Default for ObservableCell<T, N> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
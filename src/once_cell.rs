@@ -0,0 +1,90 @@
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, Ordering};
+use critical_section::CriticalSection;
+
+/// A static cell that can be initialized exactly once, handing out a `&'static mut T` to its value
+///
+/// This suits buffers and drivers that are set up once and then only ever used from a single
+/// context, so they don't need a critical section on every access the way `StaticRefCell` does.
+/// The reference is given to whoever calls `init`, so the payload does not need to be `Send`.
+///
+/// The value is never dropped, since the `&'static mut T` may still be in use.
+pub struct StaticOnceCell<T> {
+    value: UnsafeCell<MaybeUninit<T>>,
+    initialized: AtomicBool,
+}
+
+// SAFETY: the only access to `value` is through the single `&'static mut T` returned by `init`,
+// and `initialized` is checked and set inside a critical section so only one caller can get it
+unsafe impl<T> Sync for StaticOnceCell<T> {}
+
+impl<T> StaticOnceCell<T> {
+    /// Creates a new uninitialized object
+    pub const fn new() -> Self {
+        Self {
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            initialized: AtomicBool::new(false),
+        }
+    }
+
+    /// Stores `value` in this object and returns a mutable reference to it that lives forever
+    ///
+    /// # Panics
+    ///
+    /// Panics if this object was already initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticOnceCell;
+    /// #
+    /// static BUFFER: StaticOnceCell<[u8; 4]> = StaticOnceCell::new();
+    ///
+    /// let buffer: &'static mut [u8; 4] = critical_section::with(|cs| BUFFER.init(cs, [0; 4]));
+    /// buffer[0] = 1;
+    /// assert_eq!(buffer, &[1, 0, 0, 0]);
+    /// ```
+    pub fn init(&'static self, cs: CriticalSection, value: T) -> &'static mut T {
+        match self.try_init(cs, value) {
+            Ok(value) => value,
+            Err(_) => panic!("StaticOnceCell was already initialized"),
+        }
+    }
+
+    /// Stores `value` in this object and returns a mutable reference to it that lives forever, or
+    /// returns `value` back if this object was already initialized
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticOnceCell;
+    /// #
+    /// static COUNTER: StaticOnceCell<u32> = StaticOnceCell::new();
+    ///
+    /// assert!(critical_section::with(|cs| COUNTER.try_init(cs, 1)).is_ok());
+    /// assert_eq!(critical_section::with(|cs| COUNTER.try_init(cs, 2)), Err(2));
+    /// ```
+    #[allow(clippy::mut_from_ref)]
+    pub fn try_init(&'static self, _cs: CriticalSection, value: T) -> Result<&'static mut T, T> {
+        if self.initialized.load(Ordering::Acquire) {
+            return Err(value);
+        }
+        self.initialized.store(true, Ordering::Release);
+
+        // SAFETY: `initialized` was false, so no reference to `value` has been handed out yet, and
+        // the critical section prevents anyone else from claiming it before the flag was set
+        Ok(unsafe { (*self.value.get()).write(value) })
+    }
+
+    /// Returns true if this object has been initialized
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Default for StaticOnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
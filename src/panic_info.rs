@@ -0,0 +1,255 @@
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use critical_section::CriticalSection;
+
+/// Marks a `PanicInfoCell` as holding a report, distinguishing it from the random contents of
+/// uninitialized memory
+const MAGIC: u32 = 0x5041_4e43;
+
+/// The number of bytes of the source file path kept in a report (the end of the path is kept)
+const FILE_LEN: usize = 32;
+
+/// Every field is valid for any bit pattern, so the data can live in memory that isn't initialized
+/// at startup
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Data<const N: usize> {
+    magic: u32,
+    line: u32,
+    column: u32,
+    file_len: u16,
+    message_len: u16,
+    file: [u8; FILE_LEN],
+    message: [u8; N],
+}
+
+/// Storage for the location and message of a panic, meant to survive a reset
+///
+/// The panic handler calls `record`, and after the following (watchdog) reset, the application
+/// calls `take` to retrieve what happened. For the report to survive the reset, the static must be
+/// placed in a section that isn't zeroed or initialized at startup, such as `.noinit` on AVR with
+/// `#[link_section = ".noinit"]`. A marker value distinguishes a real report from the random
+/// contents of memory after power-up.
+///
+/// Up to `N` bytes of the panic message are kept, along with the last 32 bytes of the source
+/// file path. `record_panics!` defines a `#[panic_handler]` that records into a cell.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::PanicInfoCell;
+/// #
+/// #[link_section = ".noinit"]
+/// static LAST_PANIC: PanicInfoCell<32> = PanicInfoCell::new();
+///
+/// // at startup, report the panic that caused the last reset (if any)
+/// if let Some(report) = critical_section::with(|cs| LAST_PANIC.take(cs)) {
+///     // print `report.file()`, `report.line()`, and `report.message()`...
+/// }
+/// ```
+pub struct PanicInfoCell<const N: usize = 64>(UnsafeCell<Data<N>>);
+
+// SAFETY: the data is only accessed within critical sections
+unsafe impl<const N: usize> Sync for PanicInfoCell<N> {}
+
+/// Writes formatted text into a fixed buffer, silently truncating it
+struct Truncate<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for Truncate<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let count = s.len().min(self.buffer.len() - self.len);
+        self.buffer[self.len..self.len + count].copy_from_slice(&s.as_bytes()[..count]);
+        self.len += count;
+        Ok(())
+    }
+}
+
+impl<const N: usize> PanicInfoCell<N> {
+    /// Creates a new object holding no report
+    ///
+    /// When the static is placed in a `.noinit` section, this value is never actually written.
+    pub const fn new() -> Self {
+        assert!(N <= u16::MAX as usize);
+        Self(UnsafeCell::new(Data {
+            magic: 0,
+            line: 0,
+            column: 0,
+            file_len: 0,
+            message_len: 0,
+            file: [0; FILE_LEN],
+            message: [0; N],
+        }))
+    }
+
+    /// Stores the location and message of a panic, replacing any previous report
+    ///
+    /// This is meant to be called from the `#[panic_handler]`.
+    pub fn record(&self, _cs: CriticalSection, info: &PanicInfo<'_>) {
+        // SAFETY: the critical section guarantees exclusive access
+        let data = unsafe { &mut *self.0.get() };
+        data.magic = 0;
+
+        let (file, line, column) = match info.location() {
+            Some(location) => (location.file(), location.line(), location.column()),
+            None => ("", 0, 0),
+        };
+        let file = &file.as_bytes()[file.len().saturating_sub(FILE_LEN)..];
+        data.file[..file.len()].copy_from_slice(file);
+        data.file_len = file.len() as u16;
+        data.line = line;
+        data.column = column;
+
+        let mut message = Truncate {
+            buffer: &mut data.message,
+            len: 0,
+        };
+        let _ = write!(message, "{}", info.message());
+        data.message_len = message.len as u16;
+
+        data.magic = MAGIC;
+    }
+
+    /// Returns true if this object holds a report
+    pub fn has_report(&self, _cs: CriticalSection) -> bool {
+        // SAFETY: the critical section guarantees exclusive access
+        unsafe { (*self.0.get()).magic == MAGIC }
+    }
+
+    /// Removes the stored report and returns it, or returns None if there isn't one
+    pub fn take(&self, cs: CriticalSection) -> Option<PanicReport<N>> {
+        if !self.has_report(cs) {
+            return None;
+        }
+        // SAFETY: the critical section guarantees exclusive access
+        let data = unsafe { &mut *self.0.get() };
+        data.magic = 0;
+        Some(PanicReport(*data))
+    }
+}
+
+impl<const N: usize> Default for PanicInfoCell<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The location and message of a panic, retrieved from a `PanicInfoCell`
+#[derive(Clone, Copy)]
+pub struct PanicReport<const N: usize>(Data<N>);
+
+impl<const N: usize> PanicReport<N> {
+    /// Returns the end of the path of the source file that panicked
+    ///
+    /// If the path was cut in the middle of a character, this is empty.
+    pub fn file(&self) -> &str {
+        let len = (self.0.file_len as usize).min(FILE_LEN);
+        core::str::from_utf8(&self.0.file[..len]).unwrap_or("")
+    }
+
+    /// Returns the line number that panicked
+    pub fn line(&self) -> u32 {
+        self.0.line
+    }
+
+    /// Returns the column number that panicked
+    pub fn column(&self) -> u32 {
+        self.0.column
+    }
+
+    /// Returns the panic message, truncated to at most `N` bytes
+    pub fn message(&self) -> &str {
+        let bytes = &self.0.message[..(self.0.message_len as usize).min(N)];
+        match core::str::from_utf8(bytes) {
+            Ok(message) => message,
+            // the message was truncated in the middle of a character
+            Err(error) => core::str::from_utf8(&bytes[..error.valid_up_to()]).unwrap_or(""),
+        }
+    }
+}
+
+impl<const N: usize> fmt::Debug for PanicReport<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicReport")
+            .field("file", &self.file())
+            .field("line", &self.line())
+            .field("column", &self.column())
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+impl<const N: usize> fmt::Display for PanicReport<N> {
+    /// Formats the report like the standard panic message, `panicked at file:line:column: message`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "panicked at {}:{}:{}: {}",
+            self.file(),
+            self.line(),
+            self.column(),
+            self.message()
+        )
+    }
+}
+
+/// Defines a `#[panic_handler]` that records each panic into a `PanicInfoCell`
+///
+/// After recording, the handler runs the given block, which must diverge (for example, by
+/// spinning until the watchdog resets the MCU). Without a block, it spins forever.
+///
+/// # Examples
+///
+/// ```ignore
+/// use embedded_static_ref_cell::{record_panics, PanicInfoCell};
+///
+/// #[link_section = ".noinit"]
+/// static LAST_PANIC: PanicInfoCell = PanicInfoCell::new();
+///
+/// // with the watchdog enabled, spinning here leads to a reset
+/// record_panics!(LAST_PANIC);
+/// ```
+#[macro_export]
+macro_rules! record_panics {
+    ($cell:expr) => {
+        $crate::record_panics!($cell, {
+            loop {
+                ::core::hint::spin_loop();
+            }
+        });
+    };
+    ($cell:expr, $after:block) => {
+        #[panic_handler]
+        fn panic(info: &::core::panic::PanicInfo<'_>) -> ! {
+            $crate::__private::critical_section::with(|cs| $cell.record(cs, info));
+            $after
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_survives_garbage() {
+        let cell: PanicInfoCell<4> = PanicInfoCell::new();
+        critical_section::with(|cs| {
+            assert!(cell.take(cs).is_none());
+
+            // simulate memory that was found with a valid marker but nonsense contents
+            let data = unsafe { &mut *cell.0.get() };
+            data.magic = MAGIC;
+            data.file_len = u16::MAX;
+            data.message_len = 3;
+            data.message = [b'a', 0xe2, 0x82, 0];
+            let report = cell.take(cs).unwrap();
+            assert_eq!(report.message(), "a");
+            assert_eq!(report.file().len(), FILE_LEN);
+            assert!(!cell.has_report(cs));
+        });
+    }
+}
@@ -0,0 +1,300 @@
+use crate::pod::{bytes_of, bytes_of_mut};
+use crate::{BorrowError, Pod, StaticRefCell};
+use core::cell::Cell;
+use core::fmt;
+use core::mem::size_of;
+use core::ops::Deref;
+use critical_section::{CriticalSection, Mutex};
+use embedded_storage::Storage;
+
+/// The error returned by `PersistentCell::load` and `PersistentCell::commit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistentError<E> {
+    /// The storage returned an error
+    Storage(E),
+    /// The storage or the value couldn't be borrowed (for example, `set_storage` wasn't called
+    /// yet, or the value is uninitialized)
+    Borrow(BorrowError),
+}
+
+impl<E: fmt::Display> fmt::Display for PersistentError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistentError::Storage(error) => error.fmt(f),
+            PersistentError::Borrow(error) => error.fmt(f),
+        }
+    }
+}
+
+/// The slot holding the latest record, and its sequence number
+#[derive(Clone, Copy)]
+struct Latest {
+    slot: u32,
+    seq: u32,
+}
+
+/// A cell whose value is mirrored to non-volatile storage, such as calibration values that are
+/// shared with ISRs and must survive a power loss
+///
+/// The cell derefs to a `StaticRefCell<T>`, which ISRs and the main loop use as usual. At boot,
+/// `load` restores the value committed last, and `commit` saves the current value.
+///
+/// Commits are spread over `slots` records in the storage, starting at `offset`, to level the
+/// wear: each commit writes the slot after the latest one, with an incremented sequence number and
+/// a checksum. A commit interrupted by a power loss leaves a record with a bad checksum, so `load`
+/// falls back to the one committed before. Each record takes `size_of::<T>() + 6` bytes.
+///
+/// The storage is accessed inside the critical section passed to `load` and `commit`, so on slow
+/// storage (such as AVR EEPROM, at a few milliseconds per byte) these should be called where the
+/// interrupt latency doesn't matter, such as before enabling interrupts or before a shutdown.
+///
+/// Requires the `embedded-storage` feature.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::PersistentCell;
+/// # use embedded_storage::{ReadStorage, Storage};
+/// # struct Eeprom([u8; 64]);
+/// # impl ReadStorage for Eeprom {
+/// #     type Error = ();
+/// #     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+/// #         let offset = offset as usize;
+/// #         bytes.copy_from_slice(self.0.get(offset..offset + bytes.len()).ok_or(())?);
+/// #         Ok(())
+/// #     }
+/// #     fn capacity(&self) -> usize {
+/// #         self.0.len()
+/// #     }
+/// # }
+/// # impl Storage for Eeprom {
+/// #     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+/// #         let offset = offset as usize;
+/// #         self.0.get_mut(offset..offset + bytes.len()).ok_or(())?.copy_from_slice(bytes);
+/// #         Ok(())
+/// #     }
+/// # }
+/// #
+/// // the zero offset of an ADC, kept in 4 slots at the start of the EEPROM
+/// static ADC_OFFSET: PersistentCell<i16, Eeprom> = PersistentCell::new(0, 4);
+///
+/// // at boot (with a blank EEPROM)
+/// critical_section::with(|cs| {
+///     ADC_OFFSET.set_storage(cs, Eeprom([0xff; 64]));
+///     if !ADC_OFFSET.load(cs).unwrap() {
+///         ADC_OFFSET.init(cs, 0);
+///     }
+/// });
+///
+/// // after calibrating
+/// critical_section::with(|cs| {
+///     ADC_OFFSET.set(cs, -12);
+///     ADC_OFFSET.commit(cs).unwrap();
+/// });
+///
+/// // after a reboot, the value is restored
+/// critical_section::with(|cs| {
+///     let eeprom = ADC_OFFSET.take_storage(cs).unwrap();
+///     let rebooted: PersistentCell<i16, Eeprom> = PersistentCell::new(0, 4);
+///     rebooted.set_storage(cs, eeprom);
+///     assert!(rebooted.load(cs).unwrap());
+///     assert_eq!(rebooted.get(cs), Some(-12));
+/// });
+/// ```
+pub struct PersistentCell<T: Pod, S> {
+    value: StaticRefCell<T>,
+    storage: StaticRefCell<S>,
+    offset: u32,
+    slots: u32,
+    latest: Mutex<Cell<Option<Latest>>>,
+}
+
+impl<T: Pod, S> PersistentCell<T, S> {
+    /// Creates a new uninitialized object, keeping its records in `slots` slots (at least one)
+    /// starting at `offset` in the storage
+    pub const fn new(offset: u32, slots: u32) -> Self {
+        Self {
+            value: StaticRefCell::new(),
+            storage: StaticRefCell::new(),
+            offset,
+            slots: if slots == 0 { 1 } else { slots },
+            latest: Mutex::new(Cell::new(None)),
+        }
+    }
+
+    /// Sets the storage the value is mirrored to
+    pub fn set_storage(&self, cs: CriticalSection, storage: S) {
+        self.storage.init(cs, storage);
+    }
+
+    /// Moves the storage out of this object
+    pub fn take_storage(&self, cs: CriticalSection) -> Option<S> {
+        self.storage.take(cs)
+    }
+
+    const fn record_len() -> u32 {
+        (4 + size_of::<T>() + 2) as u32
+    }
+}
+
+impl<T: Pod, S: Storage> PersistentCell<T, S> {
+    /// Restores the value committed last from the storage, returning false (leaving the value
+    /// unchanged) if no valid record was found
+    pub fn load(&self, cs: CriticalSection) -> Result<bool, PersistentError<S::Error>> {
+        let latest = self
+            .storage
+            .try_borrow_mut(cs, |storage| {
+                let mut latest: Option<(Latest, T)> = None;
+                for slot in 0..self.slots {
+                    let Some((seq, value)) = self.read_record(storage, slot)? else {
+                        continue;
+                    };
+                    let newer = match latest {
+                        Some((current, _)) => (seq.wrapping_sub(current.seq) as i32) > 0,
+                        None => true,
+                    };
+                    if newer {
+                        latest = Some((Latest { slot, seq }, value));
+                    }
+                }
+                Ok(latest)
+            })
+            .map_err(PersistentError::Borrow)?
+            .map_err(PersistentError::Storage)?;
+
+        self.latest.borrow(cs).set(latest.map(|(latest, _)| latest));
+        match latest {
+            Some((_, value)) => {
+                self.value.init(cs, value);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Saves the current value to the storage, in the slot after the latest record
+    pub fn commit(&self, cs: CriticalSection) -> Result<(), PersistentError<S::Error>> {
+        let value = self
+            .value
+            .try_borrow(cs, |value| *value)
+            .map_err(PersistentError::Borrow)?;
+        let next = match self.latest.borrow(cs).get() {
+            Some(latest) => Latest {
+                slot: (latest.slot + 1) % self.slots,
+                seq: latest.seq.wrapping_add(1),
+            },
+            None => Latest { slot: 0, seq: 0 },
+        };
+        self.storage
+            .try_borrow_mut(cs, |storage| self.write_record(storage, next, &value))
+            .map_err(PersistentError::Borrow)?
+            .map_err(PersistentError::Storage)?;
+        self.latest.borrow(cs).set(Some(next));
+        Ok(())
+    }
+
+    /// Reads the record in `slot`, returning None if its checksum doesn't match
+    fn read_record(&self, storage: &mut S, slot: u32) -> Result<Option<(u32, T)>, S::Error> {
+        let offset = self.offset + slot * Self::record_len();
+        let mut seq = [0; 4];
+        storage.read(offset, &mut seq)?;
+        // SAFETY: `Pod` types are valid for any bit pattern, including all zeros
+        let mut value: T = unsafe { core::mem::zeroed() };
+        storage.read(offset + 4, bytes_of_mut(&mut value))?;
+        let mut checksum = [0; 2];
+        storage.read(offset + 4 + size_of::<T>() as u32, &mut checksum)?;
+
+        let valid = u16::from_le_bytes(checksum) == fletcher16(&[&seq, bytes_of(&value)]);
+        Ok(valid.then(|| (u32::from_le_bytes(seq), value)))
+    }
+
+    fn write_record(&self, storage: &mut S, record: Latest, value: &T) -> Result<(), S::Error> {
+        let offset = self.offset + record.slot * Self::record_len();
+        let seq = record.seq.to_le_bytes();
+        let checksum = fletcher16(&[&seq, bytes_of(value)]);
+        storage.write(offset, &seq)?;
+        storage.write(offset + 4, bytes_of(value))?;
+        storage.write(offset + 4 + size_of::<T>() as u32, &checksum.to_le_bytes())
+    }
+}
+
+impl<T: Pod, S> Deref for PersistentCell<T, S> {
+    type Target = StaticRefCell<T>;
+
+    fn deref(&self) -> &StaticRefCell<T> {
+        &self.value
+    }
+}
+
+/// Computes a Fletcher-16 checksum, starting from 1 so that blank (all 0x00 or all 0xff) storage
+/// never holds a matching checksum
+fn fletcher16(parts: &[&[u8]]) -> u16 {
+    let (mut low, mut high) = (1u16, 0u16);
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        low = (low + u16::from(*byte)) % 255;
+        high = (high + low) % 255;
+    }
+    (high << 8) | low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_storage::ReadStorage;
+
+    struct Memory([u8; 32]);
+
+    impl ReadStorage for Memory {
+        type Error = ();
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), ()> {
+            let offset = offset as usize;
+            bytes.copy_from_slice(self.0.get(offset..offset + bytes.len()).ok_or(())?);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    impl Storage for Memory {
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), ()> {
+            let offset = offset as usize;
+            self.0
+                .get_mut(offset..offset + bytes.len())
+                .ok_or(())?
+                .copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn torn_commit_falls_back_to_previous_record() {
+        let cell: PersistentCell<u16, Memory> = PersistentCell::new(2, 3);
+        critical_section::with(|cs| {
+            cell.set_storage(cs, Memory([0; 32]));
+            assert_eq!(cell.load(cs), Ok(false));
+
+            // wrap around the slots a few times
+            for value in 1..=5 {
+                cell.set(cs, value);
+                cell.commit(cs).unwrap();
+            }
+
+            let mut memory = cell.take_storage(cs).unwrap();
+            // the latest record (5) is in slot 1, at 2 + 8
+            assert_eq!(&memory.0[10..16], &[4, 0, 0, 0, 5, 0]);
+            memory.0[14] = 6;
+
+            let rebooted: PersistentCell<u16, Memory> = PersistentCell::new(2, 3);
+            rebooted.set_storage(cs, memory);
+            assert_eq!(rebooted.load(cs), Ok(true));
+            assert_eq!(rebooted.get(cs), Some(4));
+
+            // the next commit goes after the record that was loaded
+            rebooted.commit(cs).unwrap();
+            assert_eq!(rebooted.take_storage(cs).unwrap().0[10..14], [4, 0, 0, 0]);
+        });
+    }
+}
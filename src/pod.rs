@@ -0,0 +1,52 @@
+use core::mem::size_of;
+
+/// Plain data that can be handled as raw bytes, such as by `PersistentCell`, `NoinitCell`, and
+/// `CheckedCell`
+///
+/// # Safety
+///
+/// The type must have no padding bytes and no invalid bit patterns (any bytes read back from
+/// storage must make a valid value), such as integers, arrays of them, and `#[repr(C)]` structs
+/// of them laid out without padding.
+pub unsafe trait Pod: Copy {}
+
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(
+            // SAFETY: primitive integers and floats have no padding and no invalid bit patterns
+            unsafe impl Pod for $ty {}
+        )*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+// SAFETY: arrays have no padding between their elements
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+pub(crate) const fn bytes_of<T: Pod>(value: &T) -> &[u8] {
+    // SAFETY: `Pod` types have no padding, so every byte is initialized
+    unsafe { core::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+pub(crate) const fn bytes_of_mut<T: Pod>(value: &mut T) -> &mut [u8] {
+    // SAFETY: `Pod` types have no padding and no invalid bit patterns, so any bytes can be written
+    unsafe { core::slice::from_raw_parts_mut(value as *mut T as *mut u8, size_of::<T>()) }
+}
+
+/// Computes the CRC-32 (IEEE) of `bytes`, bit by bit to avoid a lookup table
+pub(crate) const fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    let mut index = 0;
+    while index < bytes.len() {
+        crc ^= bytes[index] as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+            bit += 1;
+        }
+        index += 1;
+    }
+    !crc
+}
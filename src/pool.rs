@@ -0,0 +1,171 @@
+//! A fixed-capacity object pool, for allocating values such as packet buffers or commands from
+//! both ISRs and the main loop without `alloc`
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::pool::Pool;
+//!
+//! static PACKETS: Pool<[u8; 8], 2> = Pool::new();
+//!
+//! // in the radio ISR
+//! let packet = critical_section::with(|cs| PACKETS.alloc(cs, *b"ping\0\0\0\0")).unwrap();
+//!
+//! // in the main loop, once the packet has been handed over (e.g. through a queue)
+//! critical_section::with(|cs| {
+//!     assert_eq!(PACKETS.borrow(cs, packet, |data| data[0], || 0), b'p');
+//!     assert_eq!(PACKETS.available(cs), 1);
+//!
+//!     assert!(PACKETS.free(cs, packet).is_some());
+//!     // the handle is stale once its value is freed
+//!     assert!(PACKETS.free(cs, packet).is_none());
+//! });
+//! ```
+
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+/// Refers to a value allocated in a `Pool`
+///
+/// A handle is only valid until its value is freed. Handles of freed values are detected (unless
+/// the same slot has been reallocated a multiple of 256 times since), and don't refer to
+/// whichever value reuses the slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handle {
+    index: usize,
+    generation: u8,
+}
+
+impl Handle {
+    /// Returns the index of the slot holding the value, below the capacity of the pool
+    pub const fn index(self) -> usize {
+        self.index
+    }
+}
+
+struct Slot<T> {
+    value: Option<T>,
+    /// Incremented each time the value is freed, to detect stale handles
+    generation: u8,
+}
+
+impl<T> Slot<T> {
+    fn get(&self, handle: Handle) -> Option<&T> {
+        self.value
+            .as_ref()
+            .filter(|_| self.generation == handle.generation)
+    }
+
+    fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        self.value
+            .as_mut()
+            .filter(|_| self.generation == handle.generation)
+    }
+}
+
+/// A pool of up to `N` values, allocated and freed in critical sections
+pub struct Pool<T, const N: usize>(Mutex<RefCell<[Slot<T>; N]>>);
+
+impl<T, const N: usize> Pool<T, N> {
+    /// Creates a new empty pool
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(
+            [const {
+                Slot {
+                    value: None,
+                    generation: 0,
+                }
+            }; N],
+        )))
+    }
+
+    /// Returns the maximum number of values this pool can hold, `N`
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Stores `value` in a free slot and returns a handle to it, or returns the value back if the
+    /// pool is full
+    pub fn alloc(&self, cs: CriticalSection, value: T) -> Result<Handle, T> {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        match slots
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| slot.value.is_none())
+        {
+            Some((index, slot)) => {
+                slot.value = Some(value);
+                Ok(Handle {
+                    index,
+                    generation: slot.generation,
+                })
+            }
+            None => Err(value),
+        }
+    }
+
+    /// Removes the value referred to by `handle` from the pool and returns it, or returns None if
+    /// the handle is stale
+    pub fn free(&self, cs: CriticalSection, handle: Handle) -> Option<T> {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        let slot = slots.get_mut(handle.index)?;
+        slot.get(handle)?;
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.value.take()
+    }
+
+    /// Passes an immutable reference to the value referred to by `handle` in `func` and returns the
+    /// result, or returns the result of `none_func` if the handle is stale
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool is currently mutably borrowed.
+    pub fn borrow<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        handle: Handle,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        let slots = self.0.borrow_ref(cs);
+        match slots.get(handle.index).and_then(|slot| slot.get(handle)) {
+            Some(value) => func(value),
+            None => none_func(),
+        }
+    }
+
+    /// Passes a mutable reference to the value referred to by `handle` in `func` and returns the
+    /// result, or returns the result of `none_func` if the handle is stale
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool is currently borrowed.
+    pub fn borrow_mut<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        handle: Handle,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        match slots
+            .get_mut(handle.index)
+            .and_then(|slot| slot.get_mut(handle))
+        {
+            Some(value) => func(value),
+            None => none_func(),
+        }
+    }
+
+    /// Returns the number of free slots
+    pub fn available(&self, cs: CriticalSection) -> usize {
+        let slots = self.0.borrow_ref(cs);
+        slots.iter().filter(|slot| slot.value.is_none()).count()
+    }
+}
+
+impl<T, const N: usize> Default for Pool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
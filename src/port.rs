@@ -0,0 +1,93 @@
+use critical_section::CriticalSection;
+
+/// A whole 8-bit GPIO port, such as `PORTB` on an AVR, shared between ISRs and the main loop
+///
+/// ISRs that drive different pins of the same port would otherwise race: each one's
+/// read-modify-write of the output register can undo another's. Every update here happens inside
+/// a critical section instead, so one static can stand in for the whole port, rather than one cell
+/// per pin.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::PortCell;
+/// #
+/// // stand in for PORTB and PINB, at 0x25 and 0x23 on an ATmega328P
+/// static mut PORTB: u8 = 0;
+/// static mut PINB: u8 = 0b0000_0001;
+///
+/// // SAFETY: these stand-ins are only accessed through the cell
+/// static LEDS: PortCell =
+///     unsafe { PortCell::new(core::ptr::addr_of_mut!(PORTB), core::ptr::addr_of!(PINB)) };
+///
+/// critical_section::with(|cs| {
+///     // in the timer ISR
+///     LEDS.toggle_bits(cs, 1 << 5);
+///     // in the pin-change ISR
+///     LEDS.set_bits(cs, 1 << 3);
+///     LEDS.clear_bits(cs, 1 << 5);
+///
+///     assert_eq!(LEDS.output(cs), 1 << 3);
+///     assert_eq!(LEDS.read(cs), 0b0000_0001);
+/// });
+/// ```
+pub struct PortCell {
+    port: *mut u8,
+    pin: *const u8,
+}
+
+// SAFETY: the registers are only accessed with volatile operations inside critical sections
+unsafe impl Send for PortCell {}
+unsafe impl Sync for PortCell {}
+
+impl PortCell {
+    /// Creates a cell for the port with the output register at `port` and the input register at
+    /// `pin` (such as `PORTB` and `PINB`)
+    ///
+    /// # Safety
+    ///
+    /// Both registers must be valid for volatile accesses (reads and writes for `port`, reads for
+    /// `pin`) for as long as the cell is used, and nothing else may write to `port` meanwhile.
+    pub const unsafe fn new(port: *mut u8, pin: *const u8) -> Self {
+        Self { port, pin }
+    }
+
+    /// Computes the new value of the output register from its current value using `func`
+    pub fn modify<F: FnOnce(u8) -> u8>(&self, _cs: CriticalSection, func: F) {
+        // SAFETY: the creator of the cell guarantees that the register is valid, and the critical
+        // section keeps other updates from interleaving
+        unsafe { self.port.write_volatile(func(self.port.read_volatile())) }
+    }
+
+    /// Drives the pins in `mask` high
+    pub fn set_bits(&self, cs: CriticalSection, mask: u8) {
+        self.modify(cs, |value| value | mask);
+    }
+
+    /// Drives the pins in `mask` low
+    pub fn clear_bits(&self, cs: CriticalSection, mask: u8) {
+        self.modify(cs, |value| value & !mask);
+    }
+
+    /// Inverts the output of the pins in `mask`
+    pub fn toggle_bits(&self, cs: CriticalSection, mask: u8) {
+        self.modify(cs, |value| value ^ mask);
+    }
+
+    /// Sets the whole output register
+    pub fn write(&self, cs: CriticalSection, value: u8) {
+        self.modify(cs, |_| value);
+    }
+
+    /// Returns the value of the output register
+    pub fn output(&self, _cs: CriticalSection) -> u8 {
+        // SAFETY: the creator of the cell guarantees that the register is valid
+        unsafe { self.port.read_volatile() }
+    }
+
+    /// Returns the levels of the pins, from the input register
+    pub fn read(&self, _cs: CriticalSection) -> u8 {
+        // SAFETY: the creator of the cell guarantees that the register is valid
+        unsafe { self.pin.read_volatile() }
+    }
+}
@@ -0,0 +1,268 @@
+use core::mem::{self, ManuallyDrop};
+use portable_atomic::{
+    AtomicBool, AtomicI16, AtomicI32, AtomicI8, AtomicU16, AtomicU32, AtomicU8, Ordering,
+};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A small `Copy` type that has a matching atomic type
+///
+/// This trait is sealed and implemented for `bool` and the 8, 16, and 32 bit integer types.
+pub trait Primitive: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    type Atomic;
+    #[doc(hidden)]
+    fn load(atomic: &Self::Atomic) -> Self;
+    #[doc(hidden)]
+    fn store(atomic: &Self::Atomic, value: Self);
+    #[doc(hidden)]
+    fn swap(atomic: &Self::Atomic, value: Self) -> Self;
+    #[doc(hidden)]
+    fn fetch_and(atomic: &Self::Atomic, value: Self) -> Self;
+    #[doc(hidden)]
+    fn fetch_or(atomic: &Self::Atomic, value: Self) -> Self;
+    #[doc(hidden)]
+    fn fetch_xor(atomic: &Self::Atomic, value: Self) -> Self;
+}
+
+/// An integer `Primitive`, supporting arithmetic operations
+///
+/// This trait is sealed and implemented for the 8, 16, and 32 bit integer types.
+pub trait Integer: Primitive {
+    #[doc(hidden)]
+    const ZERO: Self;
+    #[doc(hidden)]
+    fn fetch_add(atomic: &Self::Atomic, value: Self) -> Self;
+    #[doc(hidden)]
+    fn fetch_sub(atomic: &Self::Atomic, value: Self) -> Self;
+    #[doc(hidden)]
+    fn fetch_max(atomic: &Self::Atomic, value: Self) -> Self;
+    #[doc(hidden)]
+    fn fetch_min(atomic: &Self::Atomic, value: Self) -> Self;
+}
+
+/// A lock-free cell for sharing a small `Copy` value (flags, counters) between ISRs and the main loop
+///
+/// Unlike `StaticRefCell`, accesses do not need a critical section, so hot ISR paths can skip
+/// masking interrupts. All operations use sequentially consistent ordering.
+///
+/// The atomics are provided by `portable-atomic`. On targets without native atomic
+/// read-modify-write instructions (such as AVR or ARMv6-M), the `fetch_*` and `swap` operations
+/// fall back to a short critical section using `critical-section`, while `load` and `store` of
+/// natively sized values stay lock-free.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::StaticPrimitiveCell;
+/// #
+/// static BUTTON_PRESSED: StaticPrimitiveCell<bool> = StaticPrimitiveCell::new(false);
+/// static OVERFLOWS: StaticPrimitiveCell<u8> = StaticPrimitiveCell::new(0);
+///
+/// // in the ISR
+/// BUTTON_PRESSED.store(true);
+/// OVERFLOWS.fetch_add(1);
+///
+/// // in the main loop
+/// if BUTTON_PRESSED.swap(false) {
+///     assert_eq!(OVERFLOWS.load(), 1);
+/// }
+/// ```
+pub struct StaticPrimitiveCell<T: Primitive> {
+    atomic: T::Atomic,
+}
+
+/// Reinterprets a primitive as its atomic type in a const context
+union Transmute<T: Primitive> {
+    value: T,
+    atomic: ManuallyDrop<T::Atomic>,
+}
+
+impl<T: Primitive> StaticPrimitiveCell<T> {
+    /// Creates a new object holding `value`
+    pub const fn new(value: T) -> Self {
+        assert!(mem::size_of::<T>() == mem::size_of::<T::Atomic>());
+        // SAFETY: `Primitive` is sealed, and every `Atomic` type from `portable-atomic` has the same
+        // in-memory representation as its underlying type (checked for size above)
+        let atomic = unsafe { Transmute { value }.atomic };
+        Self {
+            atomic: ManuallyDrop::into_inner(atomic),
+        }
+    }
+
+    /// Returns the stored value
+    pub fn load(&self) -> T {
+        T::load(&self.atomic)
+    }
+
+    /// Sets the stored value
+    pub fn store(&self, value: T) {
+        T::store(&self.atomic, value)
+    }
+
+    /// Sets the stored value and returns the previous value
+    pub fn swap(&self, value: T) -> T {
+        T::swap(&self.atomic, value)
+    }
+
+    /// Bitwise (or logical, for `bool`) "and" with the stored value, returning the previous value
+    pub fn fetch_and(&self, value: T) -> T {
+        T::fetch_and(&self.atomic, value)
+    }
+
+    /// Bitwise (or logical, for `bool`) "or" with the stored value, returning the previous value
+    pub fn fetch_or(&self, value: T) -> T {
+        T::fetch_or(&self.atomic, value)
+    }
+
+    /// Bitwise (or logical, for `bool`) "xor" with the stored value, returning the previous value
+    pub fn fetch_xor(&self, value: T) -> T {
+        T::fetch_xor(&self.atomic, value)
+    }
+}
+
+impl<T: Integer> StaticPrimitiveCell<T> {
+    /// Adds to the stored value (wrapping on overflow), returning the previous value
+    pub fn fetch_add(&self, value: T) -> T {
+        T::fetch_add(&self.atomic, value)
+    }
+
+    /// Subtracts from the stored value (wrapping on overflow), returning the previous value
+    pub fn fetch_sub(&self, value: T) -> T {
+        T::fetch_sub(&self.atomic, value)
+    }
+
+    /// Sets the stored value to the maximum of itself and `value`, returning the previous value
+    pub fn fetch_max(&self, value: T) -> T {
+        T::fetch_max(&self.atomic, value)
+    }
+
+    /// Sets the stored value to the minimum of itself and `value`, returning the previous value
+    pub fn fetch_min(&self, value: T) -> T {
+        T::fetch_min(&self.atomic, value)
+    }
+}
+
+impl StaticPrimitiveCell<bool> {
+    /// Inverts the stored value, returning the previous value
+    pub fn fetch_not(&self) -> bool {
+        self.atomic.fetch_not(Ordering::SeqCst)
+    }
+}
+
+impl<T: Primitive + Default> Default for StaticPrimitiveCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+macro_rules! impl_primitive {
+    ($($ty:ty => $atomic:ty),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl Primitive for $ty {
+                type Atomic = $atomic;
+
+                fn load(atomic: &$atomic) -> Self {
+                    atomic.load(Ordering::SeqCst)
+                }
+
+                fn store(atomic: &$atomic, value: Self) {
+                    atomic.store(value, Ordering::SeqCst)
+                }
+
+                fn swap(atomic: &$atomic, value: Self) -> Self {
+                    atomic.swap(value, Ordering::SeqCst)
+                }
+
+                fn fetch_and(atomic: &$atomic, value: Self) -> Self {
+                    atomic.fetch_and(value, Ordering::SeqCst)
+                }
+
+                fn fetch_or(atomic: &$atomic, value: Self) -> Self {
+                    atomic.fetch_or(value, Ordering::SeqCst)
+                }
+
+                fn fetch_xor(atomic: &$atomic, value: Self) -> Self {
+                    atomic.fetch_xor(value, Ordering::SeqCst)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty => $atomic:ty),* $(,)?) => {
+        impl_primitive!($($ty => $atomic),*);
+
+        $(
+            impl Integer for $ty {
+                const ZERO: Self = 0;
+
+                fn fetch_add(atomic: &$atomic, value: Self) -> Self {
+                    atomic.fetch_add(value, Ordering::SeqCst)
+                }
+
+                fn fetch_sub(atomic: &$atomic, value: Self) -> Self {
+                    atomic.fetch_sub(value, Ordering::SeqCst)
+                }
+
+                fn fetch_max(atomic: &$atomic, value: Self) -> Self {
+                    atomic.fetch_max(value, Ordering::SeqCst)
+                }
+
+                fn fetch_min(atomic: &$atomic, value: Self) -> Self {
+                    atomic.fetch_min(value, Ordering::SeqCst)
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive! {
+    bool => AtomicBool,
+}
+
+impl_integer! {
+    u8 => AtomicU8,
+    i8 => AtomicI8,
+    u16 => AtomicU16,
+    i16 => AtomicI16,
+    u32 => AtomicU32,
+    i32 => AtomicI32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_operations() {
+        let cell: StaticPrimitiveCell<i8> = StaticPrimitiveCell::new(i8::MAX);
+
+        // fetch_add wraps on overflow
+        assert_eq!(cell.fetch_add(1), i8::MAX);
+        assert_eq!(cell.load(), i8::MIN);
+
+        assert_eq!(cell.fetch_max(3), i8::MIN);
+        assert_eq!(cell.fetch_min(-2), 3);
+        assert_eq!(cell.swap(0b0101), -2);
+        assert_eq!(cell.fetch_or(0b0010), 0b0101);
+        assert_eq!(cell.fetch_and(0b0110), 0b0111);
+        assert_eq!(cell.load(), 0b0110);
+    }
+
+    #[test]
+    fn bool_operations() {
+        let cell: StaticPrimitiveCell<bool> = StaticPrimitiveCell::new(true);
+        assert!(cell.load());
+
+        assert!(cell.fetch_not());
+        assert!(!cell.fetch_xor(true));
+        assert!(cell.fetch_and(false));
+        assert!(!cell.load());
+    }
+}
@@ -0,0 +1,189 @@
+use crate::HandlerSlot;
+use core::cell::Cell;
+use core::fmt;
+use critical_section::{CriticalSection, Mutex};
+
+static CYCLE_COUNTER: HandlerSlot<fn() -> u32> = HandlerSlot::new();
+static BUDGET_HANDLER: HandlerSlot<fn(CriticalSection, BudgetExceeded)> = HandlerSlot::new();
+
+/// Sets the function used to timestamp borrows with the `profile` feature, such as one reading
+/// the Cortex-M `DWT` cycle counter or a free-running hardware timer
+///
+/// The counter may wrap around, as long as no single borrow lasts a full period of it. Until a
+/// counter is set, borrows are not timed.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{set_cycle_counter, StaticRefCell};
+/// # use core::sync::atomic::{AtomicU32, Ordering};
+/// # static FAKE_CYCLES: AtomicU32 = AtomicU32::new(0);
+/// #
+/// fn cycles() -> u32 {
+///     // e.g. cortex_m::peripheral::DWT::cycle_count()
+///     FAKE_CYCLES.fetch_add(25, Ordering::Relaxed)
+/// }
+///
+/// static CELL: StaticRefCell<u32> = StaticRefCell::new_with(0);
+///
+/// critical_section::with(|cs| {
+///     set_cycle_counter(cs, cycles);
+///     CELL.borrow_mut(cs, |value| *value += 1, || {});
+///
+///     let stats = CELL.hold_stats(cs);
+///     assert_eq!((stats.count, stats.max), (1, 25));
+/// });
+/// ```
+pub fn set_cycle_counter(cs: CriticalSection, counter: fn() -> u32) {
+    CYCLE_COUNTER.register(cs, counter);
+}
+
+/// A closure borrow that took longer than the budget set with `StaticRefCell::with_latency_budget`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    /// The name of the cell, if it was given one with `StaticRefCell::named` (only kept with the
+    /// `debug-borrows` feature)
+    pub name: Option<&'static str>,
+    /// How long the borrow took
+    pub elapsed: u32,
+    /// The budget of the cell
+    pub budget: u32,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StaticRefCell ")?;
+        if let Some(name) = self.name {
+            write!(f, "`{name}` ")?;
+        }
+        write!(
+            f,
+            "was borrowed for {}, over its latency budget of {}",
+            self.elapsed, self.budget
+        )
+    }
+}
+
+/// Sets the function called when a closure borrow exceeds the budget of its cell, instead of
+/// failing a debug assertion
+///
+/// The handler runs inside the critical section of the borrow, which it is passed, and can be used
+/// to toggle a debug pin or record the overrun.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{set_budget_handler, set_cycle_counter};
+/// # use embedded_static_ref_cell::{BudgetExceeded, StaticRefCell};
+/// # use critical_section::CriticalSection;
+/// # use core::sync::atomic::{AtomicU32, Ordering};
+/// # static FAKE_CYCLES: AtomicU32 = AtomicU32::new(0);
+/// # fn cycles() -> u32 {
+/// #     FAKE_CYCLES.fetch_add(100, Ordering::Relaxed)
+/// # }
+/// #
+/// static OVERRUNS: StaticRefCell<u32> = StaticRefCell::new_with(0);
+///
+/// fn report(cs: CriticalSection, exceeded: BudgetExceeded) {
+///     assert_eq!((exceeded.elapsed, exceeded.budget), (100, 50));
+///     OVERRUNS.borrow_mut(cs, |overruns| *overruns += 1, || {});
+/// }
+///
+/// static BUFFER: StaticRefCell<[u8; 64]> = StaticRefCell::new_with([0; 64]).with_latency_budget(50);
+///
+/// critical_section::with(|cs| {
+///     set_cycle_counter(cs, cycles);
+///     set_budget_handler(cs, report);
+///     BUFFER.borrow_mut(cs, |buffer| buffer.fill(0xff), || {});
+///
+///     assert_eq!(BUFFER.hold_stats(cs).over_budget, 1);
+///     assert_eq!(OVERRUNS.get(cs), Some(1));
+/// });
+/// ```
+pub fn set_budget_handler(cs: CriticalSection, handler: fn(CriticalSection, BudgetExceeded)) {
+    BUDGET_HANDLER.register(cs, handler);
+}
+
+/// How long the closure borrows of a `StaticRefCell` took, recorded with the `profile` feature
+///
+/// Durations are in the units of the counter passed to `set_cycle_counter`, and the counters
+/// saturate instead of wrapping. See `StaticRefCell::hold_stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HoldStats {
+    /// The number of borrows timed
+    pub count: u32,
+    /// The total duration of the borrows timed
+    pub total: u64,
+    /// The duration of the longest borrow timed
+    pub max: u32,
+    /// The number of borrows that took longer than the budget set with
+    /// `StaticRefCell::with_latency_budget`
+    pub over_budget: u32,
+}
+
+/// The hold times and budget of one cell
+pub(crate) struct Profile {
+    stats: Mutex<Cell<HoldStats>>,
+    pub(crate) budget: Option<u32>,
+}
+
+impl Profile {
+    pub(crate) const fn new() -> Self {
+        Self {
+            stats: Mutex::new(Cell::new(HoldStats {
+                count: 0,
+                total: 0,
+                max: 0,
+                over_budget: 0,
+            })),
+            budget: None,
+        }
+    }
+
+    /// Calls `func`, recording how long it took
+    pub(crate) fn time<Y, F: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        name: Option<&'static str>,
+        func: F,
+    ) -> Y {
+        let Some(counter) = CYCLE_COUNTER.get(cs) else {
+            return func();
+        };
+        let start = counter();
+        let result = func();
+        let elapsed = counter().wrapping_sub(start);
+
+        let over_budget = self.budget.filter(|&budget| elapsed > budget);
+        let cell = self.stats.borrow(cs);
+        let mut stats = cell.get();
+        stats.count = stats.count.saturating_add(1);
+        stats.total = stats.total.saturating_add(u64::from(elapsed));
+        stats.max = stats.max.max(elapsed);
+        stats.over_budget = stats
+            .over_budget
+            .saturating_add(u32::from(over_budget.is_some()));
+        cell.set(stats);
+
+        if let Some(budget) = over_budget {
+            let exceeded = BudgetExceeded {
+                name,
+                elapsed,
+                budget,
+            };
+            match BUDGET_HANDLER.get(cs) {
+                Some(handler) => handler(cs, exceeded),
+                None => debug_assert!(false, "{}", exceeded),
+            }
+        }
+        result
+    }
+
+    pub(crate) fn stats(&self, cs: CriticalSection) -> HoldStats {
+        self.stats.borrow(cs).get()
+    }
+
+    pub(crate) fn reset(&self, cs: CriticalSection) -> HoldStats {
+        self.stats.borrow(cs).replace(HoldStats::default())
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(target_arch = "avr")]
+use core::mem::{size_of, MaybeUninit};
+
+/// A read-only value kept in flash rather than SRAM, such as a lookup table used by both ISRs and
+/// the main loop
+///
+/// On AVR, statics live in SRAM unless placed in the `.progmem.data` section, and data placed there
+/// can only be read with the `lpm` instruction. A `ProgmemCell` is declared with `progmem!`, which
+/// places it in that section, and its accessors read it with `lpm`, so the table never takes up
+/// SRAM. On other targets (where flash is memory-mapped), it is an ordinary static.
+///
+/// No critical section is needed, since the value never changes. On AVR, only the first 64 KiB of
+/// flash can be read this way, which `lpm` can reach (the linker places `.progmem.data` first).
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{progmem, ProgmemCell};
+/// #
+/// progmem! {
+///     /// A quarter sine wave, for driving a PWM DAC
+///     static SINE: [u8; 8] = [0, 49, 90, 117, 127, 117, 90, 49];
+/// }
+///
+/// // in the timer ISR
+/// assert_eq!(SINE.get(2), Some(90));
+/// assert_eq!(SINE.get(8), None);
+///
+/// // in the main loop
+/// assert_eq!(SINE.load()[4], 127);
+/// ```
+pub struct ProgmemCell<T>(T);
+
+impl<T> ProgmemCell<T> {
+    /// Wraps `value`; use `progmem!` rather than calling this directly
+    ///
+    /// # Safety
+    ///
+    /// On AVR, the object must be a static placed in the `.progmem.data` section, as `progmem!`
+    /// does, since it is read with `lpm`.
+    pub const unsafe fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Copy> ProgmemCell<T> {
+    /// Returns a copy of the value
+    ///
+    /// For large tables, `get` avoids copying the whole table into SRAM.
+    pub fn load(&self) -> T {
+        // SAFETY: `new` guarantees that the value can be read from flash
+        unsafe { read(&self.0) }
+    }
+}
+
+impl<E: Copy, const N: usize> ProgmemCell<[E; N]> {
+    /// Returns a copy of the element at `index`, or None if `index` is out of bounds
+    pub fn get(&self, index: usize) -> Option<E> {
+        // SAFETY: `new` guarantees that the value can be read from flash
+        self.0.get(index).map(|element| unsafe { read(element) })
+    }
+
+    /// Returns the number of elements, `N`
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if there are no elements
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+}
+
+/// Reads a value from flash with `lpm`
+///
+/// # Safety
+///
+/// `src` must point into the first 64 KiB of flash.
+#[cfg(target_arch = "avr")]
+unsafe fn read<T: Copy>(src: *const T) -> T {
+    let mut value = MaybeUninit::<T>::uninit();
+    let dst = value.as_mut_ptr().cast::<u8>();
+    for offset in 0..size_of::<T>() {
+        let byte: u8;
+        // SAFETY: the caller guarantees that the address is in reach of `lpm`
+        unsafe {
+            core::arch::asm!(
+                "lpm {byte}, Z",
+                byte = out(reg) byte,
+                in("Z") src.cast::<u8>().add(offset),
+                options(pure, readonly, nostack, preserves_flags),
+            );
+            dst.add(offset).write(byte);
+        }
+    }
+    // SAFETY: every byte was copied from a valid `T`
+    unsafe { value.assume_init() }
+}
+
+/// Reads a value from (memory-mapped) flash
+///
+/// # Safety
+///
+/// `src` must be valid for reads.
+#[cfg(not(target_arch = "avr"))]
+unsafe fn read<T: Copy>(src: *const T) -> T {
+    // SAFETY: the caller guarantees that `src` is valid
+    unsafe { src.read() }
+}
+
+/// Declares `ProgmemCell` statics, placed in flash on AVR
+///
+/// Each static is written as `static NAME: T = value;` and becomes a `ProgmemCell<T>`. Attributes
+/// (such as doc comments) are applied to the static.
+#[macro_export]
+macro_rules! progmem {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $value:expr;)*) => {
+        $(
+            $(#[$attr])*
+            #[cfg_attr(target_arch = "avr", link_section = ".progmem.data")]
+            // SAFETY: the static is placed in `.progmem.data` on AVR
+            $vis static $name: $crate::ProgmemCell<$ty> = unsafe { $crate::ProgmemCell::new($value) };
+        )*
+    };
+}
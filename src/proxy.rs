@@ -0,0 +1,700 @@
+//! `embedded-hal` implementations for peripherals shared through a `StaticRefCell`
+//!
+//! A proxy owns a `StaticRefCell` holding a HAL peripheral, and implements the matching
+//! `embedded-hal` traits for a shared reference to itself by borrowing the peripheral in a short
+//! critical section per call. This lets an ISR and a generic HAL-consuming driver use the same
+//! peripheral: the ISR borrows the cell as usual (the proxy derefs to its `StaticRefCell`), and
+//! the driver is handed `&PROXY`.
+//!
+//! Requires the `embedded-hal` feature. With the `embedded-hal-async` feature, `InputPinCell` also
+//! implements the async `Wait` trait.
+//! With the `embedded-hal-02` feature, `WatchdogCell` implements the `embedded-hal` 0.2 watchdog
+//! traits.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_hal::digital::OutputPin;
+//! use embedded_static_ref_cell::proxy::OutputPinCell;
+//! # use core::convert::Infallible;
+//! # struct Led(bool);
+//! # impl embedded_hal::digital::ErrorType for Led {
+//! #     type Error = Infallible;
+//! # }
+//! # impl OutputPin for Led {
+//! #     fn set_low(&mut self) -> Result<(), Infallible> {
+//! #         self.0 = false;
+//! #         Ok(())
+//! #     }
+//! #     fn set_high(&mut self) -> Result<(), Infallible> {
+//! #         self.0 = true;
+//! #         Ok(())
+//! #     }
+//! # }
+//!
+//! static LED: OutputPinCell<Led> = OutputPinCell::new();
+//!
+//! // a generic driver that knows nothing about the cell
+//! fn blink(mut pin: impl OutputPin) {
+//!     pin.set_high().unwrap();
+//! }
+//!
+//! critical_section::with(|cs| LED.init(cs, Led(false)));
+//! blink(&LED);
+//!
+//! // in the ISR, the pin is borrowed directly
+//! critical_section::with(|cs| LED.borrow_mut(cs, |led| assert!(led.0), || {}));
+//! ```
+
+#[cfg(feature = "embedded-hal-async")]
+use crate::WakerCell;
+use crate::{BorrowError, StaticRefCell};
+use core::fmt;
+use core::ops::Deref;
+#[cfg(feature = "embedded-hal-async")]
+use core::task::Poll;
+#[cfg(feature = "embedded-hal-async")]
+use critical_section::CriticalSection;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, InputPin, OutputPin, StatefulOutputPin};
+use embedded_hal::i2c::{self, AddressMode, I2c};
+use embedded_hal::spi::{self, SpiBus, SpiDevice};
+
+/// The error returned by a proxy: either the peripheral's own error, or the reason the peripheral
+/// couldn't be borrowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyError<E> {
+    /// The peripheral returned an error
+    Peripheral(E),
+    /// The cell holding the peripheral is uninitialized, already borrowed, or poisoned
+    Borrow(BorrowError),
+}
+
+impl<E: fmt::Display> fmt::Display for ProxyError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyError::Peripheral(error) => error.fmt(f),
+            ProxyError::Borrow(error) => error.fmt(f),
+        }
+    }
+}
+
+impl<E: digital::Error> digital::Error for ProxyError<E> {
+    fn kind(&self) -> digital::ErrorKind {
+        match self {
+            ProxyError::Peripheral(error) => error.kind(),
+            ProxyError::Borrow(_) => digital::ErrorKind::Other,
+        }
+    }
+}
+
+impl<E: i2c::Error> i2c::Error for ProxyError<E> {
+    fn kind(&self) -> i2c::ErrorKind {
+        match self {
+            ProxyError::Peripheral(error) => error.kind(),
+            ProxyError::Borrow(_) => i2c::ErrorKind::Other,
+        }
+    }
+}
+
+/// Borrows the peripheral in `cell` in a critical section and passes it to `func`
+fn lock<P, Y, E, F: FnOnce(&mut P) -> Result<Y, E>>(
+    cell: &StaticRefCell<P>,
+    func: F,
+) -> Result<Y, ProxyError<E>> {
+    critical_section::with(|cs| cell.try_borrow_mut(cs, func))
+        .map_err(ProxyError::Borrow)?
+        .map_err(ProxyError::Peripheral)
+}
+
+/// An output pin in a `StaticRefCell`, implementing `OutputPin` for `&OutputPinCell<P>`
+///
+/// `StatefulOutputPin` is also implemented if the pin implements it.
+pub struct OutputPinCell<P> {
+    cell: StaticRefCell<P>,
+}
+
+impl<P> OutputPinCell<P> {
+    /// Creates a new uninitialized object (stored value as None)
+    pub const fn new() -> Self {
+        Self {
+            cell: StaticRefCell::new(),
+        }
+    }
+}
+
+impl<P> Default for OutputPinCell<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Deref for OutputPinCell<P> {
+    type Target = StaticRefCell<P>;
+
+    fn deref(&self) -> &StaticRefCell<P> {
+        &self.cell
+    }
+}
+
+impl<P: OutputPin> digital::ErrorType for &OutputPinCell<P> {
+    type Error = ProxyError<P::Error>;
+}
+
+impl<P: OutputPin> OutputPin for &OutputPinCell<P> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        lock(&self.cell, P::set_low)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        lock(&self.cell, P::set_high)
+    }
+}
+
+impl<P: StatefulOutputPin> StatefulOutputPin for &OutputPinCell<P> {
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        lock(&self.cell, P::is_set_high)
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        lock(&self.cell, P::is_set_low)
+    }
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        lock(&self.cell, P::toggle)
+    }
+}
+
+/// An input pin in a `StaticRefCell`, implementing `InputPin` for `&InputPinCell<P>`
+///
+/// With the `embedded-hal-async` feature, the async `Wait` trait is also implemented. Waiting
+/// tasks check the pin level whenever they are woken, so the pin-change ISR must call `notify`
+/// after each change. Edges are detected by seeing the level change between two checks, so pulses
+/// shorter than the time it takes the waiting task to be polled may be missed.
+///
+/// # Examples
+///
+/// ```
+/// use embedded_hal::digital::InputPin;
+/// use embedded_static_ref_cell::proxy::InputPinCell;
+/// # use core::convert::Infallible;
+/// # struct Button;
+/// # impl embedded_hal::digital::ErrorType for Button {
+/// #     type Error = Infallible;
+/// # }
+/// # impl InputPin for Button {
+/// #     fn is_high(&mut self) -> Result<bool, Infallible> {
+/// #         Ok(true)
+/// #     }
+/// #     fn is_low(&mut self) -> Result<bool, Infallible> {
+/// #         Ok(false)
+/// #     }
+/// # }
+///
+/// static BUTTON: InputPinCell<Button> = InputPinCell::new();
+///
+/// critical_section::with(|cs| BUTTON.init(cs, Button));
+/// assert!((&BUTTON).is_high().unwrap());
+/// ```
+pub struct InputPinCell<P> {
+    cell: StaticRefCell<P>,
+    #[cfg(feature = "embedded-hal-async")]
+    waker: WakerCell,
+}
+
+impl<P> InputPinCell<P> {
+    /// Creates a new uninitialized object (stored value as None)
+    pub const fn new() -> Self {
+        Self {
+            cell: StaticRefCell::new(),
+            #[cfg(feature = "embedded-hal-async")]
+            waker: WakerCell::new(),
+        }
+    }
+
+    /// Wakes the task waiting for the pin (if any) so it checks the level again
+    ///
+    /// This should be called from the pin-change ISR. Requires the `embedded-hal-async` feature.
+    #[cfg(feature = "embedded-hal-async")]
+    pub fn notify(&self, cs: CriticalSection) {
+        self.waker.wake(cs);
+    }
+}
+
+#[cfg(feature = "embedded-hal-async")]
+impl<P: InputPin> InputPinCell<P> {
+    /// Waits until the pin is at the given level
+    async fn wait_for_level(&self, high: bool) -> Result<(), ProxyError<P::Error>> {
+        core::future::poll_fn(|cx| {
+            critical_section::with(|cs| match self.cell.try_borrow_mut(cs, P::is_high) {
+                Ok(Ok(level)) if level != high => {
+                    self.waker.register(cs, cx.waker());
+                    Poll::Pending
+                }
+                Ok(Ok(_)) => Poll::Ready(Ok(())),
+                Ok(Err(error)) => Poll::Ready(Err(ProxyError::Peripheral(error))),
+                Err(error) => Poll::Ready(Err(ProxyError::Borrow(error))),
+            })
+        })
+        .await
+    }
+}
+
+impl<P> Default for InputPinCell<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Deref for InputPinCell<P> {
+    type Target = StaticRefCell<P>;
+
+    fn deref(&self) -> &StaticRefCell<P> {
+        &self.cell
+    }
+}
+
+impl<P: InputPin> digital::ErrorType for &InputPinCell<P> {
+    type Error = ProxyError<P::Error>;
+}
+
+impl<P: InputPin> InputPin for &InputPinCell<P> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        lock(&self.cell, P::is_high)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        lock(&self.cell, P::is_low)
+    }
+}
+
+#[cfg(feature = "embedded-hal-async")]
+impl<P: InputPin> embedded_hal_async::digital::Wait for &InputPinCell<P> {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level(true).await
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level(false).await
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level(false).await?;
+        self.wait_for_level(true).await
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level(true).await?;
+        self.wait_for_level(false).await
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        if lock(&self.cell, P::is_high)? {
+            self.wait_for_level(false).await
+        } else {
+            self.wait_for_level(true).await
+        }
+    }
+}
+
+/// An I2C bus in a `StaticRefCell`, shared by any number of `I2cProxy`s
+///
+/// Each driver on the bus gets its own proxy, which implements `I2c` by borrowing the bus in a
+/// critical section for the whole transaction, so transactions from different drivers (and from
+/// ISRs borrowing the bus directly) never interleave. Interrupts stay masked for the duration of
+/// each transaction, which should be kept in mind for long transfers.
+///
+/// # Examples
+///
+/// ```
+/// use embedded_hal::i2c::{I2c, Operation};
+/// use embedded_static_ref_cell::proxy::I2cBusCell;
+/// # use core::convert::Infallible;
+/// # struct Bus(u32);
+/// # impl embedded_hal::i2c::ErrorType for Bus {
+/// #     type Error = Infallible;
+/// # }
+/// # impl I2c for Bus {
+/// #     fn transaction(&mut self, _: u8, _: &mut [Operation<'_>]) -> Result<(), Infallible> {
+/// #         self.0 += 1;
+/// #         Ok(())
+/// #     }
+/// # }
+///
+/// static I2C: I2cBusCell<Bus> = I2cBusCell::new();
+///
+/// struct Sensor<I>(I);
+/// struct Display<I>(I);
+///
+/// critical_section::with(|cs| I2C.init(cs, Bus(0)));
+/// let mut sensor = Sensor(I2C.proxy());
+/// let mut display = Display(I2C.proxy());
+///
+/// sensor.0.write(0x48, &[0x01]).unwrap();
+/// display.0.write(0x3c, &[0xaf]).unwrap();
+/// assert_eq!(critical_section::with(|cs| I2C.borrow(cs, |bus| bus.0, || 0)), 2);
+/// ```
+pub struct I2cBusCell<I> {
+    cell: StaticRefCell<I>,
+}
+
+impl<I> I2cBusCell<I> {
+    /// Creates a new uninitialized object (stored value as None)
+    pub const fn new() -> Self {
+        Self {
+            cell: StaticRefCell::new(),
+        }
+    }
+
+    /// Returns a new proxy for a driver on this bus
+    pub const fn proxy(&self) -> I2cProxy<'_, I> {
+        I2cProxy { bus: self }
+    }
+}
+
+impl<I> Default for I2cBusCell<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I> Deref for I2cBusCell<I> {
+    type Target = StaticRefCell<I>;
+
+    fn deref(&self) -> &StaticRefCell<I> {
+        &self.cell
+    }
+}
+
+/// A handle to an `I2cBusCell` for one driver, created by `I2cBusCell::proxy`
+pub struct I2cProxy<'a, I> {
+    bus: &'a I2cBusCell<I>,
+}
+
+impl<I> Clone for I2cProxy<'_, I> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I> Copy for I2cProxy<'_, I> {}
+
+impl<I: i2c::ErrorType> i2c::ErrorType for I2cProxy<'_, I> {
+    type Error = ProxyError<I::Error>;
+}
+
+impl<A: AddressMode, I: I2c<A>> I2c<A> for I2cProxy<'_, I> {
+    fn read(&mut self, address: A, read: &mut [u8]) -> Result<(), Self::Error> {
+        lock(&self.bus.cell, |bus| bus.read(address, read))
+    }
+
+    fn write(&mut self, address: A, write: &[u8]) -> Result<(), Self::Error> {
+        lock(&self.bus.cell, |bus| bus.write(address, write))
+    }
+
+    fn write_read(&mut self, address: A, write: &[u8], read: &mut [u8]) -> Result<(), Self::Error> {
+        lock(&self.bus.cell, |bus| bus.write_read(address, write, read))
+    }
+
+    fn transaction(
+        &mut self,
+        address: A,
+        operations: &mut [i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        lock(&self.bus.cell, |bus| bus.transaction(address, operations))
+    }
+}
+
+/// The error returned by an `SpiDeviceProxy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpiProxyError<B, C> {
+    /// The SPI bus returned an error
+    Spi(B),
+    /// The chip select pin returned an error
+    ChipSelect(C),
+    /// The cell holding the bus is uninitialized, already borrowed, or poisoned
+    Borrow(BorrowError),
+}
+
+impl<B: fmt::Display, C: fmt::Display> fmt::Display for SpiProxyError<B, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpiProxyError::Spi(error) => error.fmt(f),
+            SpiProxyError::ChipSelect(error) => error.fmt(f),
+            SpiProxyError::Borrow(error) => error.fmt(f),
+        }
+    }
+}
+
+impl<B: spi::Error, C: fmt::Debug> spi::Error for SpiProxyError<B, C> {
+    fn kind(&self) -> spi::ErrorKind {
+        match self {
+            SpiProxyError::Spi(error) => error.kind(),
+            SpiProxyError::ChipSelect(_) => spi::ErrorKind::ChipSelectFault,
+            SpiProxyError::Borrow(_) => spi::ErrorKind::Other,
+        }
+    }
+}
+
+/// A placeholder delay for `SpiDeviceProxy`s whose drivers don't use delay operations
+///
+/// # Panics
+///
+/// Panics if a delay is requested.
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {
+        panic!("SPI delay operation requested, but the SpiDeviceProxy was created without a delay");
+    }
+}
+
+/// An SPI bus in a `StaticRefCell`, shared by any number of `SpiDeviceProxy`s
+///
+/// Each device on the bus gets its own proxy, which owns the device's chip select pin and
+/// implements `SpiDevice` by borrowing the bus in a critical section for the whole transaction
+/// (including asserting and deasserting chip select). Interrupts stay masked for the duration of
+/// each transaction, including any delay operations.
+///
+/// # Examples
+///
+/// ```
+/// use embedded_hal::spi::SpiDevice;
+/// use embedded_static_ref_cell::proxy::SpiBusCell;
+/// # use core::convert::Infallible;
+/// # struct Bus(u32);
+/// # impl embedded_hal::spi::ErrorType for Bus {
+/// #     type Error = Infallible;
+/// # }
+/// # impl embedded_hal::spi::SpiBus for Bus {
+/// #     fn read(&mut self, _: &mut [u8]) -> Result<(), Infallible> { Ok(()) }
+/// #     fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+/// #         self.0 += words.len() as u32;
+/// #         Ok(())
+/// #     }
+/// #     fn transfer(&mut self, _: &mut [u8], _: &[u8]) -> Result<(), Infallible> { Ok(()) }
+/// #     fn transfer_in_place(&mut self, _: &mut [u8]) -> Result<(), Infallible> { Ok(()) }
+/// #     fn flush(&mut self) -> Result<(), Infallible> { Ok(()) }
+/// # }
+/// # struct Pin;
+/// # impl embedded_hal::digital::ErrorType for Pin {
+/// #     type Error = Infallible;
+/// # }
+/// # impl embedded_hal::digital::OutputPin for Pin {
+/// #     fn set_low(&mut self) -> Result<(), Infallible> { Ok(()) }
+/// #     fn set_high(&mut self) -> Result<(), Infallible> { Ok(()) }
+/// # }
+/// # let (flash_cs, display_cs) = (Pin, Pin);
+///
+/// static SPI: SpiBusCell<Bus> = SpiBusCell::new();
+///
+/// critical_section::with(|cs| SPI.init(cs, Bus(0)));
+/// let mut flash = SPI.device(flash_cs);
+/// let mut display = SPI.device(display_cs);
+///
+/// flash.write(&[0x9f]).unwrap();
+/// display.write(&[0x2c, 0x00]).unwrap();
+/// assert_eq!(critical_section::with(|cs| SPI.borrow(cs, |bus| bus.0, || 0)), 3);
+/// ```
+pub struct SpiBusCell<B> {
+    cell: StaticRefCell<B>,
+}
+
+impl<B> SpiBusCell<B> {
+    /// Creates a new uninitialized object (stored value as None)
+    pub const fn new() -> Self {
+        Self {
+            cell: StaticRefCell::new(),
+        }
+    }
+
+    /// Returns a new proxy for the device selected by `cs_pin`
+    ///
+    /// The proxy panics if a driver requests a delay operation; use `device_with_delay` for
+    /// drivers that do.
+    pub fn device<C: OutputPin>(&self, cs_pin: C) -> SpiDeviceProxy<'_, B, C> {
+        self.device_with_delay(cs_pin, NoDelay)
+    }
+
+    /// Returns a new proxy for the device selected by `cs_pin`, using `delay` for delay operations
+    pub fn device_with_delay<C: OutputPin, D: DelayNs>(
+        &self,
+        cs_pin: C,
+        delay: D,
+    ) -> SpiDeviceProxy<'_, B, C, D> {
+        SpiDeviceProxy {
+            bus: self,
+            cs_pin,
+            delay,
+        }
+    }
+}
+
+impl<B> Default for SpiBusCell<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B> Deref for SpiBusCell<B> {
+    type Target = StaticRefCell<B>;
+
+    fn deref(&self) -> &StaticRefCell<B> {
+        &self.cell
+    }
+}
+
+/// A handle to an `SpiBusCell` for one device, created by `SpiBusCell::device`
+pub struct SpiDeviceProxy<'a, B, C, D = NoDelay> {
+    bus: &'a SpiBusCell<B>,
+    cs_pin: C,
+    delay: D,
+}
+
+impl<B: spi::ErrorType, C: OutputPin, D> spi::ErrorType for SpiDeviceProxy<'_, B, C, D> {
+    type Error = SpiProxyError<B::Error, C::Error>;
+}
+
+impl<W, B, C, D> SpiDevice<W> for SpiDeviceProxy<'_, B, C, D>
+where
+    W: Copy + 'static,
+    B: SpiBus<W>,
+    C: OutputPin,
+    D: DelayNs,
+{
+    fn transaction(&mut self, operations: &mut [spi::Operation<'_, W>]) -> Result<(), Self::Error> {
+        let Self { bus, cs_pin, delay } = self;
+        critical_section::with(|cs| {
+            bus.cell.try_borrow_mut(cs, |bus| {
+                cs_pin.set_low().map_err(SpiProxyError::ChipSelect)?;
+                let result = operations
+                    .iter_mut()
+                    .try_for_each(|operation| match operation {
+                        spi::Operation::Read(words) => bus.read(words),
+                        spi::Operation::Write(words) => bus.write(words),
+                        spi::Operation::Transfer(read, write) => bus.transfer(read, write),
+                        spi::Operation::TransferInPlace(words) => bus.transfer_in_place(words),
+                        spi::Operation::DelayNs(ns) => {
+                            bus.flush()?;
+                            delay.delay_ns(*ns);
+                            Ok(())
+                        }
+                    });
+                // deassert chip select even if an operation failed
+                let result = result.and_then(|()| bus.flush());
+                let deselected = cs_pin.set_high();
+                result.map_err(SpiProxyError::Spi)?;
+                deselected.map_err(SpiProxyError::ChipSelect)
+            })
+        })
+        .map_err(SpiProxyError::Borrow)?
+    }
+}
+
+/// A watchdog in a `StaticRefCell`, implementing the `embedded-hal` 0.2 watchdog traits for
+/// `&WatchdogCell<W>`
+///
+/// `embedded-hal` 1.0 has no watchdog traits, so this requires the `embedded-hal-02` feature.
+/// This lets both the main loop and a health-check ISR feed the same watchdog peripheral.
+///
+/// The 0.2 traits can't report errors, so feeding through the trait does nothing if the watchdog
+/// can't be borrowed (which lets it expire, as it would if nothing fed it). `try_feed` reports
+/// why instead.
+///
+/// # Examples
+///
+/// ```
+/// use embedded_hal_02::watchdog::Watchdog;
+/// use embedded_static_ref_cell::proxy::WatchdogCell;
+/// # struct Iwdg(u32);
+/// # impl Watchdog for Iwdg {
+/// #     fn feed(&mut self) {
+/// #         self.0 += 1;
+/// #     }
+/// # }
+///
+/// static WATCHDOG: WatchdogCell<Iwdg> = WatchdogCell::new();
+///
+/// critical_section::with(|cs| WATCHDOG.init(cs, Iwdg(0)));
+///
+/// // in the main loop
+/// (&WATCHDOG).feed();
+///
+/// // in the health-check ISR
+/// WATCHDOG.try_feed().unwrap();
+///
+/// assert_eq!(critical_section::with(|cs| WATCHDOG.borrow(cs, |iwdg| iwdg.0, || 0)), 2);
+/// ```
+#[cfg(feature = "embedded-hal-02")]
+pub struct WatchdogCell<W> {
+    cell: StaticRefCell<W>,
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<W> WatchdogCell<W> {
+    /// Creates a new uninitialized object (stored value as None)
+    pub const fn new() -> Self {
+        Self {
+            cell: StaticRefCell::new(),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<W: embedded_hal_02::watchdog::Watchdog> WatchdogCell<W> {
+    /// Feeds the watchdog, or returns why it couldn't be borrowed
+    pub fn try_feed(&self) -> Result<(), BorrowError> {
+        critical_section::with(|cs| self.cell.try_borrow_mut(cs, W::feed))
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<W> Default for WatchdogCell<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<W> Deref for WatchdogCell<W> {
+    type Target = StaticRefCell<W>;
+
+    fn deref(&self) -> &StaticRefCell<W> {
+        &self.cell
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<W: embedded_hal_02::watchdog::Watchdog> embedded_hal_02::watchdog::Watchdog
+    for &WatchdogCell<W>
+{
+    fn feed(&mut self) {
+        let _ = self.try_feed();
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<W: embedded_hal_02::watchdog::WatchdogEnable> embedded_hal_02::watchdog::WatchdogEnable
+    for &WatchdogCell<W>
+{
+    type Time = W::Time;
+
+    /// Starts the watchdog, or does nothing if it can't be borrowed
+    fn start<T: Into<W::Time>>(&mut self, period: T) {
+        let period = period.into();
+        let _ = critical_section::with(|cs| {
+            self.cell
+                .try_borrow_mut(cs, |watchdog| watchdog.start(period))
+        });
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<W: embedded_hal_02::watchdog::WatchdogDisable> embedded_hal_02::watchdog::WatchdogDisable
+    for &WatchdogCell<W>
+{
+    /// Disables the watchdog, or does nothing if it can't be borrowed
+    fn disable(&mut self) {
+        let _ = critical_section::with(|cs| self.cell.try_borrow_mut(cs, W::disable));
+    }
+}
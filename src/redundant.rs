@@ -0,0 +1,187 @@
+use crate::pod::{bytes_of, bytes_of_mut};
+use crate::{Corrupted, Pod};
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+/// Returns `value` with every bit inverted
+const fn invert<T: Pod>(mut value: T) -> T {
+    let bytes = bytes_of_mut(&mut value);
+    let mut index = 0;
+    while index < bytes.len() {
+        bytes[index] = !bytes[index];
+        index += 1;
+    }
+    value
+}
+
+/// A cell for safety-critical plain data that keeps two copies of its value and compares them
+/// on every access
+///
+/// Every change writes both copies, and every access first checks that they agree. If they
+/// don't, the access returns `Corrupted` without running its closure, after calling the fault
+/// handler (if one was set with `on_fault`), which runs inside the critical section and can
+/// restore a safe value with `set` or trigger a fault reaction.
+///
+/// With `new_inverted`, the second copy is stored with every bit inverted, so a fault that sets
+/// or clears a whole region of memory (or a stuck bus line) corrupts the copies differently and
+/// is still detected.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::RedundantCell;
+/// # use critical_section::CriticalSection;
+/// #
+/// fn disarm(cs: CriticalSection) {
+///     ARMED.set(cs, 0);
+/// }
+///
+/// static ARMED: RedundantCell<u8> = RedundantCell::new_inverted(0).on_fault(disarm);
+///
+/// critical_section::with(|cs| {
+///     ARMED.set(cs, 1);
+///     assert_eq!(ARMED.get(cs), Ok(1));
+/// });
+/// ```
+pub struct RedundantCell<T: Pod> {
+    copies: Mutex<RefCell<[T; 2]>>,
+    inverted: bool,
+    on_fault: Option<fn(CriticalSection)>,
+}
+
+impl<T: Pod> RedundantCell<T> {
+    /// Creates a new object holding two identical copies of `value`, with no fault handler
+    pub const fn new(value: T) -> Self {
+        Self {
+            copies: Mutex::new(RefCell::new([value, value])),
+            inverted: false,
+            on_fault: None,
+        }
+    }
+
+    /// Creates a new object holding `value` and an inverted copy of it, with no fault handler
+    pub const fn new_inverted(value: T) -> Self {
+        Self {
+            copies: Mutex::new(RefCell::new([value, invert(value)])),
+            inverted: true,
+            on_fault: None,
+        }
+    }
+
+    /// Sets the function called (inside the critical section) when the copies disagree
+    pub const fn on_fault(mut self, handler: fn(CriticalSection)) -> Self {
+        self.on_fault = Some(handler);
+        self
+    }
+
+    /// Returns the second copy to store for `value`
+    fn shadow(&self, value: T) -> T {
+        if self.inverted {
+            invert(value)
+        } else {
+            value
+        }
+    }
+
+    /// Checks that the two copies agree, calling the fault handler if they don't
+    ///
+    /// This can be called periodically to find faults in values that are rarely accessed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn verify(&self, cs: CriticalSection) -> Result<(), Corrupted> {
+        let agree = {
+            let copies = self.copies.borrow_ref(cs);
+            bytes_of(&self.shadow(copies[0])) == bytes_of(&copies[1])
+        };
+        if agree {
+            return Ok(());
+        }
+        if let Some(handler) = self.on_fault {
+            handler(cs);
+        }
+        Err(Corrupted)
+    }
+
+    /// Passes an immutable reference to the verified value in `func` and returns the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn with<Y, F: FnOnce(&T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, Corrupted> {
+        self.verify(cs)?;
+        Ok(func(&self.copies.borrow_ref(cs)[0]))
+    }
+
+    /// Passes a mutable reference to the verified value in `func`, updates the second copy, and
+    /// returns the result
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn with_mut<Y, F: FnOnce(&mut T) -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F,
+    ) -> Result<Y, Corrupted> {
+        self.verify(cs)?;
+        let mut copies = self.copies.borrow_ref_mut(cs);
+        let result = func(&mut copies[0]);
+        copies[1] = self.shadow(copies[0]);
+        Ok(result)
+    }
+
+    /// Returns a copy of the verified value
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed.
+    pub fn get(&self, cs: CriticalSection) -> Result<T, Corrupted> {
+        self.with(cs, |value| *value)
+    }
+
+    /// Sets both copies of the stored value, without checking the old ones
+    ///
+    /// This is how a faulted cell is recovered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    pub fn set(&self, cs: CriticalSection, value: T) {
+        *self.copies.borrow_ref_mut(cs) = [value, self.shadow(value)];
+    }
+}
+
+impl<T: Pod + Default> Default for RedundantCell<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverted_copy_detects_stuck_bits() {
+        critical_section::with(|cs| {
+            let cell = RedundantCell::new_inverted(0x0fu8);
+            assert_eq!(cell.copies.borrow_ref(cs)[1], 0xf0);
+            cell.with_mut(cs, |value| *value = 0x3c).unwrap();
+            assert_eq!(cell.copies.borrow_ref(cs)[1], 0xc3);
+
+            // both copies read back as zero (e.g. the memory was cleared), which a plain copy
+            // would not notice
+            *cell.copies.borrow_ref_mut(cs) = [0, 0];
+            assert_eq!(cell.get(cs), Err(Corrupted));
+
+            cell.set(cs, 1);
+            assert_eq!(cell.get(cs), Ok(1));
+        });
+    }
+}
@@ -0,0 +1,70 @@
+//! Compatibility with RTIC
+//!
+//! `Resource` lends a `StaticRefCell` to code written against RTIC's `Mutex` trait (from
+//! `rtic-core`), such as drivers that take their state as `impl Mutex<T = State>`. The cell stays a
+//! regular static, so ISRs that aren't RTIC tasks can keep using it directly while a codebase is
+//! migrated.
+//!
+//! Requires the `rtic` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::StaticRefCell;
+//! use rtic_core::Mutex;
+//!
+//! static COUNT: StaticRefCell<u32> = StaticRefCell::new_with(0);
+//!
+//! // a function written for RTIC resources
+//! fn bump(mut count: impl Mutex<T = u32>) {
+//!     count.lock(|count| *count += 1);
+//! }
+//!
+//! bump(COUNT.resource());
+//!
+//! // legacy ISR code still uses the cell directly
+//! assert_eq!(critical_section::with(|cs| COUNT.get(cs)), Some(1));
+//! ```
+
+use crate::StaticRefCell;
+
+/// A handle to a `StaticRefCell` that implements RTIC's `Mutex` trait
+///
+/// Locking enters a critical section and mutably borrows the stored value.
+pub struct Resource<T: 'static> {
+    cell: &'static StaticRefCell<T>,
+}
+
+impl<T> Clone for Resource<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Resource<T> {}
+
+impl<T> StaticRefCell<T> {
+    /// Returns a handle to this object that implements RTIC's `Mutex` trait
+    ///
+    /// Requires the `rtic` feature.
+    pub const fn resource(&'static self) -> Resource<T> {
+        Resource { cell: self }
+    }
+}
+
+impl<T> rtic_core::Mutex for Resource<T> {
+    type T = T;
+
+    /// Passes a mutable reference to the stored value in `f` within a critical section
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is uninitialized or already borrowed, since RTIC resources always hold a
+    /// value.
+    fn lock<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        critical_section::with(|cs| {
+            self.cell
+                .borrow_mut(cs, f, || panic!("RTIC resource cell is not initialized"))
+        })
+    }
+}
@@ -0,0 +1,282 @@
+//! A minimal cooperative scheduler that runs tasks to completion in the main loop
+//!
+//! This formalizes the usual pattern of ISRs setting flags and the main loop doing the work: each
+//! task is a plain `fn()`, either triggered by events (an ISR calls `Scheduler::mark_ready`) or run
+//! periodically (the tick ISR calls `Scheduler::tick`). The main loop calls
+//! `Scheduler::run_pending`, which runs every ready task outside of any critical section, one
+//! after another. Tasks never preempt each other, so they can share state through cells without
+//! conflicting borrows.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::sched::Scheduler;
+//! # use embedded_static_ref_cell::StaticPrimitiveCell;
+//! # static RUNS: StaticPrimitiveCell<u8> = StaticPrimitiveCell::new(0);
+//!
+//! static TASKS: Scheduler<4> = Scheduler::new();
+//!
+//! fn handle_rx() {
+//!     // parse the received bytes...
+//!     # RUNS.fetch_add(1);
+//! }
+//!
+//! fn blink() {
+//!     // toggle the LED...
+//!     # RUNS.fetch_add(10);
+//! }
+//!
+//! let rx = critical_section::with(|cs| TASKS.add_event(cs, handle_rx)).unwrap();
+//! critical_section::with(|cs| TASKS.add_periodic(cs, 2, blink)).unwrap();
+//!
+//! // in the UART ISR
+//! critical_section::with(|cs| TASKS.mark_ready(cs, rx));
+//!
+//! // in the tick ISR
+//! for _ in 0..2 {
+//!     critical_section::with(|cs| TASKS.tick(cs));
+//! }
+//!
+//! // in the main loop
+//! assert_eq!(TASKS.run_pending(), 2);
+//! assert_eq!(TASKS.run_pending(), 0);
+//! # assert_eq!(RUNS.load(), 11);
+//! ```
+
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+/// Identifies a task added to a `Scheduler`
+///
+/// Once a task has been removed, its slot may be reused by a new task. Ids of removed tasks are
+/// detected (unless the same slot has been reused a multiple of 256 times since), and don't refer
+/// to whichever task reuses the slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskId {
+    index: usize,
+    generation: u8,
+}
+
+struct Task {
+    func: fn(),
+    /// Zero for event-triggered tasks
+    period: u32,
+    remaining: u32,
+    ready: bool,
+}
+
+struct Slot {
+    task: Option<Task>,
+    /// Incremented each time the task is removed, to detect stale ids
+    generation: u8,
+}
+
+impl Slot {
+    fn get(&self, id: TaskId) -> Option<&Task> {
+        self.task
+            .as_ref()
+            .filter(|_| self.generation == id.generation)
+    }
+
+    fn get_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.task
+            .as_mut()
+            .filter(|_| self.generation == id.generation)
+    }
+}
+
+/// A table of up to `N` tasks, run in the main loop when ISRs or the tick mark them ready
+///
+/// Tasks with lower ids (added earlier, unless slots were reused) run first.
+pub struct Scheduler<const N: usize>(Mutex<RefCell<[Slot; N]>>);
+
+impl<const N: usize> Scheduler<N> {
+    /// Creates a new scheduler with no tasks
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(
+            [const {
+                Slot {
+                    task: None,
+                    generation: 0,
+                }
+            }; N],
+        )))
+    }
+
+    fn insert(&self, cs: CriticalSection, task: Task) -> Option<TaskId> {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        let index = slots.iter().position(|slot| slot.task.is_none())?;
+        slots[index].task = Some(task);
+        Some(TaskId {
+            index,
+            generation: slots[index].generation,
+        })
+    }
+
+    /// Adds `func` as a task that runs whenever it is marked ready
+    ///
+    /// Returns None if all `N` slots are in use.
+    pub fn add_event(&self, cs: CriticalSection, func: fn()) -> Option<TaskId> {
+        self.insert(
+            cs,
+            Task {
+                func,
+                period: 0,
+                remaining: 0,
+                ready: false,
+            },
+        )
+    }
+
+    /// Adds `func` as a task that becomes ready every `period` ticks, starting `period` ticks from
+    /// now (it can also be marked ready in between)
+    ///
+    /// A `period` of zero is treated as one tick. Returns None if all `N` slots are in use.
+    pub fn add_periodic(&self, cs: CriticalSection, period: u32, func: fn()) -> Option<TaskId> {
+        let period = period.max(1);
+        self.insert(
+            cs,
+            Task {
+                func,
+                period,
+                remaining: period,
+                ready: false,
+            },
+        )
+    }
+
+    /// Removes a task, returning false if there was no task with that id
+    pub fn remove(&self, cs: CriticalSection, id: TaskId) -> bool {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        let Some(slot) = slots.get_mut(id.index) else {
+            return false;
+        };
+        if slot.get(id).is_none() {
+            return false;
+        }
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.task.take().is_some()
+    }
+
+    /// Marks a task as ready to run, returning false if there was no task with that id
+    ///
+    /// Marking a task that is already ready has no further effect: it still runs once.
+    pub fn mark_ready(&self, cs: CriticalSection, id: TaskId) -> bool {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        match slots.get_mut(id.index).and_then(|slot| slot.get_mut(id)) {
+            Some(task) => {
+                task.ready = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if a task is waiting to run
+    pub fn is_ready(&self, cs: CriticalSection, id: TaskId) -> bool {
+        let slots = self.0.borrow_ref(cs);
+        matches!(slots.get(id.index).and_then(|slot| slot.get(id)), Some(task) if task.ready)
+    }
+
+    /// Advances every periodic task by one tick, marking the ones whose period elapsed as ready
+    ///
+    /// This is meant to be called from the tick ISR, and does not run any tasks.
+    pub fn tick(&self, cs: CriticalSection) {
+        let mut slots = self.0.borrow_ref_mut(cs);
+        let tasks = slots.iter_mut().filter_map(|slot| slot.task.as_mut());
+        for task in tasks.filter(|task| task.period > 0) {
+            task.remaining -= 1;
+            if task.remaining == 0 {
+                task.ready = true;
+                task.remaining = task.period;
+            }
+        }
+    }
+
+    /// Runs every ready task once, in order of id, and returns how many ran
+    ///
+    /// Each task is taken from the table in a short critical section and runs outside of it, so
+    /// ISRs stay responsive. A task that becomes ready again after its turn in this pass runs in
+    /// the next call.
+    pub fn run_pending(&self) -> usize {
+        let mut count = 0;
+        for index in 0..N {
+            let func = critical_section::with(|cs| {
+                let mut slots = self.0.borrow_ref_mut(cs);
+                match &mut slots[index].task {
+                    Some(task) if task.ready => {
+                        task.ready = false;
+                        Some(task.func)
+                    }
+                    _ => None,
+                }
+            });
+            if let Some(func) = func {
+                func();
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+impl<const N: usize> Default for Scheduler<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SELF_READYING: Scheduler<1> = Scheduler::new();
+
+    const FIRST: TaskId = TaskId {
+        index: 0,
+        generation: 0,
+    };
+
+    fn ready_again() {
+        critical_section::with(|cs| SELF_READYING.mark_ready(cs, FIRST));
+    }
+
+    #[test]
+    fn task_readied_while_running_waits_for_next_pass() {
+        critical_section::with(|cs| {
+            let id = SELF_READYING.add_event(cs, ready_again).unwrap();
+            assert_eq!(SELF_READYING.add_event(cs, ready_again), None);
+            SELF_READYING.mark_ready(cs, id);
+        });
+        assert_eq!(SELF_READYING.run_pending(), 1);
+        assert!(critical_section::with(
+            |cs| SELF_READYING.is_ready(cs, FIRST)
+        ));
+
+        critical_section::with(|cs| {
+            assert!(SELF_READYING.remove(cs, FIRST));
+            assert!(!SELF_READYING.mark_ready(cs, FIRST));
+        });
+        assert_eq!(SELF_READYING.run_pending(), 0);
+    }
+
+    fn nothing() {}
+
+    #[test]
+    fn stale_id_does_not_refer_to_reused_slot() {
+        let tasks: Scheduler<1> = Scheduler::new();
+        critical_section::with(|cs| {
+            let old = tasks.add_event(cs, nothing).unwrap();
+            assert!(tasks.remove(cs, old));
+            let new = tasks.add_event(cs, nothing).unwrap();
+            assert_ne!(old, new);
+
+            assert!(!tasks.mark_ready(cs, old));
+            assert!(!tasks.is_ready(cs, new));
+            assert!(!tasks.remove(cs, old));
+            assert!(tasks.mark_ready(cs, new));
+            assert!(!tasks.is_ready(cs, old));
+            assert!(tasks.is_ready(cs, new));
+        });
+    }
+}
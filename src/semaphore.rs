@@ -0,0 +1,94 @@
+use core::cell::Cell;
+use critical_section::{CriticalSection, Mutex};
+
+/// A counting semaphore, given by ISRs and taken by the main loop
+///
+/// Each `give` adds one to the count, up to the maximum set when the semaphore is created, and each
+/// `try_take` removes one. This counts events (such as timer ticks or received frames) without
+/// storing anything per event.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::StaticSemaphore;
+/// #
+/// static FRAMES: StaticSemaphore = StaticSemaphore::new(2);
+///
+/// // in the RX ISR
+/// critical_section::with(|cs| {
+///     assert!(FRAMES.give(cs));
+///     assert!(FRAMES.give(cs));
+///     // the count saturates at the maximum
+///     assert!(!FRAMES.give(cs));
+/// });
+///
+/// // in the main loop
+/// let mut handled = 0;
+/// while critical_section::with(|cs| FRAMES.try_take(cs)) {
+///     handled += 1;
+/// }
+/// assert_eq!(handled, 2);
+/// ```
+pub struct StaticSemaphore {
+    count: Mutex<Cell<usize>>,
+    max: usize,
+}
+
+impl StaticSemaphore {
+    /// Creates a new semaphore with a count of zero that saturates at `max`
+    pub const fn new(max: usize) -> Self {
+        Self {
+            count: Mutex::new(Cell::new(0)),
+            max,
+        }
+    }
+
+    /// Returns the maximum count
+    pub const fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Adds one to the count, or returns false if it is already at the maximum
+    pub fn give(&self, cs: CriticalSection) -> bool {
+        let count = self.count.borrow(cs);
+        if count.get() >= self.max {
+            return false;
+        }
+        count.set(count.get() + 1);
+        true
+    }
+
+    /// Removes one from the count, or returns false if it is zero
+    pub fn try_take(&self, cs: CriticalSection) -> bool {
+        let count = self.count.borrow(cs);
+        if count.get() == 0 {
+            return false;
+        }
+        count.set(count.get() - 1);
+        true
+    }
+
+    /// Sets the count to zero, returning what it was
+    pub fn take_all(&self, cs: CriticalSection) -> usize {
+        self.count.borrow(cs).replace(0)
+    }
+
+    /// Returns the current count
+    pub fn count(&self, cs: CriticalSection) -> usize {
+        self.count.borrow(cs).get()
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl ufmt::uDebug for StaticSemaphore {
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        let count = critical_section::with(|cs| self.count(cs));
+        f.debug_struct("StaticSemaphore")?
+            .field("count", &count)?
+            .field("max", &self.max)?
+            .finish()
+    }
+}
@@ -0,0 +1,89 @@
+use core::cell::UnsafeCell;
+use critical_section::CriticalSection;
+use portable_atomic::{fence, AtomicU8, Ordering};
+
+/// A sequence-locked cell for `Copy` data that can be read without masking interrupts
+///
+/// Writers still use a critical section, but readers never do: the value is kept in two slots, a
+/// write goes to the slot that isn't current and then bumps a sequence counter, and a read copies
+/// the current slot and retries if the counter changed meanwhile. An ISR reading while the main
+/// loop writes always finds the other slot intact, so it never has to retry (or wait for the
+/// main loop, which couldn't run until the ISR returns). A reader only retries when writes
+/// complete while it is copying, such as when the main loop reads and a writing ISR preempts it.
+///
+/// This suits telemetry-style data written rarely and read often, especially from ISRs.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::SeqCell;
+/// #
+/// #[derive(Clone, Copy, PartialEq, Debug)]
+/// struct Setpoint {
+///     target: i16,
+///     gain: u8,
+/// }
+///
+/// static SETPOINT: SeqCell<Setpoint> = SeqCell::new(Setpoint { target: 0, gain: 1 });
+///
+/// // in the main loop
+/// critical_section::with(|cs| SETPOINT.write(cs, Setpoint { target: 250, gain: 4 }));
+///
+/// // in the control loop ISR, without a critical section
+/// assert_eq!(SETPOINT.read(), Setpoint { target: 250, gain: 4 });
+/// ```
+pub struct SeqCell<T: Copy> {
+    slots: [UnsafeCell<T>; 2],
+    /// The number of completed writes (wrapping), whose lowest bit selects the current slot
+    seq: AtomicU8,
+}
+
+// SAFETY: writes are serialized by critical sections and only touch the slot readers aren't
+// directed to, and readers detect (and retry) copies that overlapped a write
+unsafe impl<T: Copy + Send> Sync for SeqCell<T> {}
+
+impl<T: Copy> SeqCell<T> {
+    /// Creates a new object holding `value`
+    pub const fn new(value: T) -> Self {
+        Self {
+            slots: [UnsafeCell::new(value), UnsafeCell::new(value)],
+            seq: AtomicU8::new(0),
+        }
+    }
+
+    /// Returns a copy of the stored value, without a critical section
+    ///
+    /// This retries until it copies the value without a write completing meanwhile. The counter
+    /// wraps after 256 writes, so a torn copy could only go unnoticed if exactly a multiple of 256
+    /// writes completed during a single copy.
+    pub fn read(&self) -> T {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            // SAFETY: writers never write to the current slot, and if the slot stopped being
+            // current during the copy, the counter changed and the copy is discarded
+            let value = unsafe { self.slots[usize::from(seq & 1)].get().read_volatile() };
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == seq {
+                return value;
+            }
+        }
+    }
+
+    /// Sets the stored value
+    pub fn write(&self, _cs: CriticalSection, value: T) {
+        let next = self.seq.load(Ordering::Relaxed).wrapping_add(1);
+        // SAFETY: the critical section serializes writers, and readers are directed to the other
+        // slot until the counter is bumped below
+        unsafe {
+            self.slots[usize::from(next & 1)]
+                .get()
+                .write_volatile(value)
+        };
+        self.seq.store(next, Ordering::Release);
+    }
+
+    /// Computes a new stored value from the current one using `func`
+    pub fn update<F: FnOnce(T) -> T>(&self, cs: CriticalSection, func: F) {
+        self.write(cs, func(self.read()));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{BorrowError, StaticRefCell};
+use core::fmt;
+use core::ops::Deref;
+
+/// A serial writer (such as a UART) in a `StaticRefCell`, writable from any context through
+/// `SerialWriter`s
+///
+/// `writer()` returns a proxy implementing `core::fmt::Write` (and `ufmt::uWrite` with the `ufmt`
+/// feature) for the stored writer, so `write!` / `uwrite!` can be used on the same UART from the
+/// main loop and from ISRs. Each string written is written in its own critical section, so output
+/// from an ISR can only land between the pieces of a formatted message, never inside one piece.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::SerialCell;
+/// use core::fmt::Write;
+/// # struct Uart(usize);
+/// # impl Write for Uart {
+/// #     fn write_str(&mut self, s: &str) -> core::fmt::Result {
+/// #         self.0 += s.len();
+/// #         Ok(())
+/// #     }
+/// # }
+///
+/// static SERIAL: SerialCell<Uart> = SerialCell::new();
+///
+/// critical_section::with(|cs| SERIAL.init(cs, Uart(0)));
+///
+/// // in the main loop or an ISR
+/// writeln!(SERIAL.writer(), "adc = {}", 512).unwrap();
+/// assert_eq!(critical_section::with(|cs| SERIAL.borrow(cs, |uart| uart.0, || 0)), 10);
+/// ```
+pub struct SerialCell<W> {
+    cell: StaticRefCell<W>,
+}
+
+impl<W> SerialCell<W> {
+    /// Creates a new uninitialized object (stored value as None)
+    pub const fn new() -> Self {
+        Self {
+            cell: StaticRefCell::new(),
+        }
+    }
+
+    /// Returns a proxy that writes to the stored writer
+    pub const fn writer(&self) -> SerialWriter<'_, W> {
+        SerialWriter { serial: self }
+    }
+}
+
+impl<W> Default for SerialCell<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W> Deref for SerialCell<W> {
+    type Target = StaticRefCell<W>;
+
+    fn deref(&self) -> &StaticRefCell<W> {
+        &self.cell
+    }
+}
+
+/// A handle to a `SerialCell` that writes to it, created by `SerialCell::writer`
+pub struct SerialWriter<'a, W> {
+    serial: &'a SerialCell<W>,
+}
+
+impl<W> Clone for SerialWriter<'_, W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<W> Copy for SerialWriter<'_, W> {}
+
+impl<W: fmt::Write> fmt::Write for SerialWriter<'_, W> {
+    /// Writes `s` to the stored writer, or fails if it can't be borrowed
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        critical_section::with(|cs| self.serial.cell.try_borrow_mut(cs, |w| w.write_str(s)))
+            .map_err(|_| fmt::Error)?
+    }
+}
+
+/// The error returned by a `SerialWriter` through `ufmt::uWrite`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialError<E> {
+    /// The stored writer returned an error
+    Write(E),
+    /// The cell holding the writer is uninitialized, already borrowed, or poisoned
+    Borrow(BorrowError),
+}
+
+#[cfg(feature = "ufmt")]
+impl<W: ufmt::uWrite> ufmt::uWrite for SerialWriter<'_, W> {
+    type Error = SerialError<W::Error>;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        critical_section::with(|cs| self.serial.cell.try_borrow_mut(cs, |w| w.write_str(s)))
+            .map_err(SerialError::Borrow)?
+            .map_err(SerialError::Write)
+    }
+}
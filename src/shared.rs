@@ -0,0 +1,141 @@
+use crate::{
+    BorrowError, Primitive, SeqCell, StaticPrimitiveCell, StaticRefCell, StaticUnsafeCell,
+};
+use core::cell::RefCell;
+
+/// An interrupt-safe container for a `T`, implemented by the cells of this crate, for drivers
+/// that are generic over how their state is shared
+///
+/// Each method enters whatever lock the container needs (such as a critical section) for the
+/// duration of `func`. `with` and `with_mut` return None if the container holds no value; the
+/// `try_` methods also report why a value couldn't be borrowed.
+///
+/// It is also implemented for a plain `RefCell`, so drivers can be tested on the host with a local
+/// value.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{SeqCell, Shared, StaticRefCell};
+/// # use core::cell::RefCell;
+/// #
+/// fn record_pulse(count: &impl Shared<u32>) {
+///     count.with_mut(|count| *count += 1);
+/// }
+///
+/// static COUNT: StaticRefCell<u32> = StaticRefCell::new_with(0);
+/// static SEQ_COUNT: SeqCell<u32> = SeqCell::new(0);
+///
+/// record_pulse(&COUNT);
+/// record_pulse(&SEQ_COUNT);
+///
+/// // a host-side test
+/// let local = RefCell::new(0);
+/// record_pulse(&local);
+/// assert_eq!(local.into_inner(), 1);
+/// ```
+pub trait Shared<T> {
+    /// Passes an immutable reference to the value in `func` and returns the result, or returns
+    /// why it couldn't be borrowed
+    fn try_with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Result<Y, BorrowError>;
+
+    /// Passes a mutable reference to the value in `func` and returns the result, or returns why it
+    /// couldn't be borrowed
+    fn try_with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Result<Y, BorrowError>;
+
+    /// Passes an immutable reference to the value in `func` and returns the result, or returns None
+    /// if there is no value
+    ///
+    /// The default implementation also returns None if the value couldn't be borrowed for another
+    /// reason, but `StaticRefCell` panics on conflicting borrows, as its own `with` does.
+    fn with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Option<Y> {
+        self.try_with(func).ok()
+    }
+
+    /// Passes a mutable reference to the value in `func` and returns the result, or returns None if
+    /// there is no value
+    ///
+    /// The default implementation also returns None if the value couldn't be borrowed for another
+    /// reason, but `StaticRefCell` panics on conflicting borrows, as its own `with_mut` does.
+    fn with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Option<Y> {
+        self.try_with_mut(func).ok()
+    }
+}
+
+impl<T> Shared<T> for StaticRefCell<T> {
+    fn try_with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        critical_section::with(|cs| self.try_borrow(cs, func))
+    }
+
+    fn try_with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        critical_section::with(|cs| self.try_borrow_mut(cs, func))
+    }
+
+    fn with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Option<Y> {
+        StaticRefCell::<T>::with(self, func)
+    }
+
+    fn with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Option<Y> {
+        StaticRefCell::<T>::with_mut(self, func)
+    }
+}
+
+/// Borrows are not tracked, so the only error is `BorrowError::Uninitialized`
+impl<T> Shared<T> for StaticUnsafeCell<T> {
+    fn try_with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        StaticUnsafeCell::with(self, func).ok_or(BorrowError::Uninitialized)
+    }
+
+    fn try_with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        StaticUnsafeCell::with_mut(self, func).ok_or(BorrowError::Uninitialized)
+    }
+}
+
+/// `func` works on a copy of the value, which `try_with_mut` writes back in a critical section
+impl<T: Copy> Shared<T> for SeqCell<T> {
+    fn try_with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        Ok(func(&self.read()))
+    }
+
+    fn try_with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        critical_section::with(|cs| {
+            let mut value = self.read();
+            let result = func(&mut value);
+            self.write(cs, value);
+            Ok(result)
+        })
+    }
+}
+
+/// `func` works on a copy of the value, which `try_with_mut` writes back in a critical section
+impl<T: Primitive> Shared<T> for StaticPrimitiveCell<T> {
+    fn try_with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        Ok(func(&self.load()))
+    }
+
+    fn try_with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        critical_section::with(|_| {
+            let mut value = self.load();
+            let result = func(&mut value);
+            self.store(value);
+            Ok(result)
+        })
+    }
+}
+
+/// For host-side tests, where no locking is needed
+impl<T> Shared<T> for RefCell<T> {
+    fn try_with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        let value = self
+            .try_borrow()
+            .map_err(|_| BorrowError::AlreadyBorrowed)?;
+        Ok(func(&value))
+    }
+
+    fn try_with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Result<Y, BorrowError> {
+        let mut value = self
+            .try_borrow_mut()
+            .map_err(|_| BorrowError::AlreadyBorrowed)?;
+        Ok(func(&mut value))
+    }
+}
@@ -0,0 +1,554 @@
+//! A fixed-capacity queue for passing values from an ISR (the producer) to the main loop (the
+//! consumer)
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::spsc::Queue;
+//!
+//! static READINGS: Queue<u16, 4> = Queue::new();
+//!
+//! // in the ADC ISR
+//! critical_section::with(|cs| {
+//!     for reading in [10, 20, 30, 40, 50] {
+//!         // the last reading doesn't fit, and is counted as an overflow
+//!         let _ = READINGS.enqueue(cs, reading);
+//!     }
+//! });
+//!
+//! // in the main loop
+//! let mut sum = 0;
+//! while let Some(reading) = critical_section::with(|cs| READINGS.dequeue(cs)) {
+//!     sum += reading;
+//! }
+//! assert_eq!(sum, 100);
+//! assert_eq!(critical_section::with(|cs| READINGS.take_overflow_count(cs)), 1);
+//! ```
+//!
+//! For UART receive buffers, `ByteQueue` avoids monomorphizing a generic queue and adds line
+//! reading and a choice of overflow policy.
+//!
+//! With the `heapless` feature, `split` stores the halves of a `heapless::spsc::Queue` in an
+//! `IsrProducer` and a `MainConsumer`.
+
+#[cfg(feature = "heapless")]
+use crate::StaticRefCell;
+use core::cell::RefCell;
+use critical_section::{CriticalSection, Mutex};
+
+struct Ring<T, const N: usize> {
+    buffer: [Option<T>; N],
+    head: usize,
+    len: usize,
+    overflows: usize,
+}
+
+/// A first-in, first-out queue holding up to `N` values, usable as a static
+///
+/// Every operation takes a `CriticalSection`, so each one is atomic with respect to interrupts.
+/// Values that don't fit because the queue is full are handed back and counted as overflows.
+pub struct Queue<T, const N: usize>(Mutex<RefCell<Ring<T, N>>>);
+
+impl<T, const N: usize> Queue<T, N> {
+    /// Creates a new empty queue
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(Ring {
+            buffer: [const { None }; N],
+            head: 0,
+            len: 0,
+            overflows: 0,
+        })))
+    }
+
+    /// Returns the maximum number of values this queue can hold, `N`
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Adds `value` to the back of the queue, or returns it back (and counts an overflow) if the
+    /// queue is full
+    pub fn enqueue(&self, cs: CriticalSection, value: T) -> Result<(), T> {
+        let mut ring = self.0.borrow_ref_mut(cs);
+        if ring.len == N {
+            ring.overflows = ring.overflows.saturating_add(1);
+            return Err(value);
+        }
+        let tail = (ring.head + ring.len) % N;
+        ring.buffer[tail] = Some(value);
+        ring.len += 1;
+        Ok(())
+    }
+
+    /// Removes the value at the front of the queue and returns it, or returns None if the queue is
+    /// empty
+    pub fn dequeue(&self, cs: CriticalSection) -> Option<T> {
+        let mut ring = self.0.borrow_ref_mut(cs);
+        if ring.len == 0 {
+            return None;
+        }
+        let head = ring.head;
+        ring.head = (head + 1) % N;
+        ring.len -= 1;
+        ring.buffer[head].take()
+    }
+
+    /// Passes an immutable reference to the value at the front of the queue in `func` and returns
+    /// the result, or returns the result of `none_func` if the queue is empty
+    pub fn peek<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        let ring = self.0.borrow_ref(cs);
+        match ring.buffer.get(ring.head).and_then(Option::as_ref) {
+            Some(value) if ring.len > 0 => func(value),
+            _ => none_func(),
+        }
+    }
+
+    /// Returns the number of values in the queue
+    pub fn len(&self, cs: CriticalSection) -> usize {
+        self.0.borrow_ref(cs).len
+    }
+
+    /// Returns true if the queue holds no values
+    pub fn is_empty(&self, cs: CriticalSection) -> bool {
+        self.len(cs) == 0
+    }
+
+    /// Returns true if the queue cannot hold any more values
+    pub fn is_full(&self, cs: CriticalSection) -> bool {
+        self.len(cs) == N
+    }
+
+    /// Removes every value from the queue
+    pub fn clear(&self, cs: CriticalSection) {
+        let mut ring = self.0.borrow_ref_mut(cs);
+        ring.buffer.iter_mut().for_each(|slot| *slot = None);
+        ring.head = 0;
+        ring.len = 0;
+    }
+
+    /// Returns the number of values rejected because the queue was full (saturating at
+    /// `usize::MAX`)
+    pub fn overflow_count(&self, cs: CriticalSection) -> usize {
+        self.0.borrow_ref(cs).overflows
+    }
+
+    /// Returns the number of values rejected because the queue was full, and resets it to zero
+    pub fn take_overflow_count(&self, cs: CriticalSection) -> usize {
+        core::mem::take(&mut self.0.borrow_ref_mut(cs).overflows)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<T, const N: usize> ufmt::uDebug for Queue<T, N> {
+    /// Formats the length, capacity, and overflow count of the queue (but not its values)
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        let (len, overflows) = critical_section::with(|cs| (self.len(cs), self.overflow_count(cs)));
+        f.debug_struct("Queue")?
+            .field("len", &len)?
+            .field("capacity", &N)?
+            .field("overflows", &overflows)?
+            .finish()
+    }
+}
+
+impl<T, const N: usize> Default for Queue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a `ByteQueue` does with a new byte when it is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the new byte, keeping the oldest data
+    DropNewest,
+    /// Discard the oldest byte to make room for the new one, keeping the most recent data
+    DropOldest,
+}
+
+struct ByteRing<const N: usize> {
+    buffer: [u8; N],
+    head: usize,
+    len: usize,
+    overflows: usize,
+}
+
+impl<const N: usize> ByteRing<N> {
+    fn get(&self, offset: usize) -> u8 {
+        self.buffer[(self.head + offset) % N]
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buffer[self.head];
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(byte)
+    }
+}
+
+/// A first-in, first-out queue of up to `N` bytes, such as data received by a UART RX ISR
+///
+/// # Examples
+///
+/// ```
+/// use embedded_static_ref_cell::spsc::{ByteQueue, OverflowPolicy};
+///
+/// static RX: ByteQueue<16> = ByteQueue::new(OverflowPolicy::DropNewest);
+///
+/// // in the USART_RX ISR
+/// critical_section::with(|cs| {
+///     for byte in b"on\noff" {
+///         RX.push_byte(cs, *byte);
+///     }
+/// });
+///
+/// // in the main loop
+/// let mut line = [0; 8];
+/// let len = critical_section::with(|cs| RX.read_line(cs, &mut line));
+/// assert_eq!(len, Some(2));
+/// assert_eq!(&line[..2], b"on");
+///
+/// // "off" has no line ending yet
+/// assert_eq!(critical_section::with(|cs| RX.read_line(cs, &mut line)), None);
+/// ```
+pub struct ByteQueue<const N: usize> {
+    ring: Mutex<RefCell<ByteRing<N>>>,
+    policy: OverflowPolicy,
+}
+
+impl<const N: usize> ByteQueue<N> {
+    /// Creates a new empty queue that handles overflows according to `policy`
+    pub const fn new(policy: OverflowPolicy) -> Self {
+        Self {
+            ring: Mutex::new(RefCell::new(ByteRing {
+                buffer: [0; N],
+                head: 0,
+                len: 0,
+                overflows: 0,
+            })),
+            policy,
+        }
+    }
+
+    /// Returns the maximum number of bytes this queue can hold, `N`
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Adds `byte` to the back of the queue
+    ///
+    /// Returns false if the queue was full, in which case a byte was dropped according to the
+    /// overflow policy and an overflow is counted.
+    pub fn push_byte(&self, cs: CriticalSection, byte: u8) -> bool {
+        let mut ring = self.ring.borrow_ref_mut(cs);
+        let full = ring.len == N;
+        if full {
+            ring.overflows = ring.overflows.saturating_add(1);
+            if N == 0 || self.policy == OverflowPolicy::DropNewest {
+                return false;
+            }
+            ring.pop();
+        }
+        let tail = (ring.head + ring.len) % N;
+        ring.buffer[tail] = byte;
+        ring.len += 1;
+        !full
+    }
+
+    /// Removes the byte at the front of the queue and returns it, or returns None if the queue is
+    /// empty
+    pub fn pop_byte(&self, cs: CriticalSection) -> Option<u8> {
+        self.ring.borrow_ref_mut(cs).pop()
+    }
+
+    /// Removes a complete line from the front of the queue and copies it into `buf`, returning the
+    /// number of bytes copied
+    ///
+    /// A line ends with `\n`, which is removed but not copied (a `\r` before it is dropped too).
+    /// If the line is longer than `buf`, the rest of it is discarded. Returns None, leaving the
+    /// queue unchanged, if it doesn't hold a complete line yet.
+    ///
+    /// If the queue is full without holding a `\n`, no more bytes could complete the line, so its
+    /// whole contents are removed and returned as an unterminated line instead.
+    pub fn read_line(&self, cs: CriticalSection, buf: &mut [u8]) -> Option<usize> {
+        let mut ring = self.ring.borrow_ref_mut(cs);
+        let (removed, content_len) = match (0..ring.len).find(|&offset| ring.get(offset) == b'\n') {
+            Some(0) => (1, 0),
+            Some(line_len) if ring.get(line_len - 1) == b'\r' => (line_len + 1, line_len - 1),
+            Some(line_len) => (line_len + 1, line_len),
+            None if N > 0 && ring.len == N => (N, N),
+            None => return None,
+        };
+
+        let copied = content_len.min(buf.len());
+        for (offset, byte) in buf[..copied].iter_mut().enumerate() {
+            *byte = ring.get(offset);
+        }
+        ring.head = (ring.head + removed) % N;
+        ring.len -= removed;
+        Some(copied)
+    }
+
+    /// Returns the number of bytes in the queue
+    pub fn len(&self, cs: CriticalSection) -> usize {
+        self.ring.borrow_ref(cs).len
+    }
+
+    /// Returns true if the queue holds no bytes
+    pub fn is_empty(&self, cs: CriticalSection) -> bool {
+        self.len(cs) == 0
+    }
+
+    /// Removes every byte from the queue
+    pub fn clear(&self, cs: CriticalSection) {
+        let mut ring = self.ring.borrow_ref_mut(cs);
+        ring.head = 0;
+        ring.len = 0;
+    }
+
+    /// Returns the number of bytes dropped because the queue was full (saturating at
+    /// `usize::MAX`)
+    pub fn overflow_count(&self, cs: CriticalSection) -> usize {
+        self.ring.borrow_ref(cs).overflows
+    }
+
+    /// Returns the number of bytes dropped because the queue was full, and resets it to zero
+    pub fn take_overflow_count(&self, cs: CriticalSection) -> usize {
+        core::mem::take(&mut self.ring.borrow_ref_mut(cs).overflows)
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<const N: usize> ufmt::uDebug for ByteQueue<N> {
+    /// Formats the length, capacity, and overflow count of the queue (but not its bytes)
+    fn fmt<W: ufmt::uWrite + ?Sized>(
+        &self,
+        f: &mut ufmt::Formatter<'_, W>,
+    ) -> Result<(), W::Error> {
+        let (len, overflows) = critical_section::with(|cs| (self.len(cs), self.overflow_count(cs)));
+        f.debug_struct("ByteQueue")?
+            .field("len", &len)?
+            .field("capacity", &N)?
+            .field("overflows", &overflows)?
+            .finish()
+    }
+}
+
+/// The producer half of a `heapless::spsc::Queue`, kept in a static for the ISR that fills the
+/// queue
+///
+/// Requires the `heapless` feature. The halves are placed in their cells by `split`, after which
+/// the producer cell can only enqueue and the consumer cell can only dequeue, so the queue keeps
+/// a single producer and a single consumer even though both cells are statics.
+///
+/// # Examples
+///
+/// ```
+/// use embedded_static_ref_cell::spsc::{self, IsrProducer, MainConsumer};
+///
+/// static mut QUEUE: heapless::spsc::Queue<u16, 4> = heapless::spsc::Queue::new();
+/// static SAMPLES_TX: IsrProducer<u16, 4> = IsrProducer::new();
+/// static SAMPLES_RX: MainConsumer<u16, 4> = MainConsumer::new();
+///
+/// // at startup, before enabling the ISR
+/// // SAFETY: this is the only reference ever made to QUEUE
+/// let queue = unsafe { &mut *core::ptr::addr_of_mut!(QUEUE) };
+/// critical_section::with(|cs| spsc::split(cs, queue, &SAMPLES_TX, &SAMPLES_RX));
+///
+/// // in the ADC ISR
+/// critical_section::with(|cs| SAMPLES_TX.enqueue_from_isr(cs, 512)).unwrap();
+///
+/// // in the main loop
+/// assert_eq!(critical_section::with(|cs| SAMPLES_RX.dequeue_in_main(cs)), Some(512));
+/// ```
+#[cfg(feature = "heapless")]
+pub struct IsrProducer<T: 'static, const N: usize>(
+    StaticRefCell<heapless::spsc::Producer<'static, T, N>>,
+);
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> IsrProducer<T, N> {
+    /// Creates a new object with no producer yet
+    pub const fn new() -> Self {
+        Self(StaticRefCell::new())
+    }
+
+    /// Adds `item` to the back of the queue, or returns it back if the queue is full or hasn't
+    /// been split yet
+    pub fn enqueue_from_isr(&self, cs: CriticalSection, item: T) -> Result<(), T> {
+        self.0.borrow_mut_option(cs, |producer| match producer {
+            Some(producer) => producer.enqueue(item),
+            None => Err(item),
+        })
+    }
+
+    /// Returns true if there is room in the queue (false if it hasn't been split yet)
+    pub fn ready(&self, cs: CriticalSection) -> bool {
+        self.0.borrow(cs, |producer| producer.ready(), || false)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> Default for IsrProducer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The consumer half of a `heapless::spsc::Queue`, kept in a static for the main loop that
+/// drains the queue
+///
+/// Requires the `heapless` feature. See `IsrProducer`.
+#[cfg(feature = "heapless")]
+pub struct MainConsumer<T: 'static, const N: usize>(
+    StaticRefCell<heapless::spsc::Consumer<'static, T, N>>,
+);
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> MainConsumer<T, N> {
+    /// Creates a new object with no consumer yet
+    pub const fn new() -> Self {
+        Self(StaticRefCell::new())
+    }
+
+    /// Removes the item at the front of the queue and returns it, or returns None if the queue is
+    /// empty or hasn't been split yet
+    pub fn dequeue_in_main(&self, cs: CriticalSection) -> Option<T> {
+        self.0
+            .borrow_mut(cs, |consumer| consumer.dequeue(), || None)
+    }
+
+    /// Returns the number of items in the queue
+    pub fn len(&self, cs: CriticalSection) -> usize {
+        self.0.borrow(cs, |consumer| consumer.len(), || 0)
+    }
+
+    /// Returns true if the queue holds no items
+    pub fn is_empty(&self, cs: CriticalSection) -> bool {
+        self.len(cs) == 0
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> Default for MainConsumer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits `queue` and stores its producer in `producer` and its consumer in `consumer`
+///
+/// Requires the `heapless` feature.
+///
+/// # Panics
+///
+/// Panics if either cell already holds a half of a queue, since replacing it would give its queue
+/// a second producer or consumer.
+#[cfg(feature = "heapless")]
+pub fn split<T, const N: usize>(
+    cs: CriticalSection,
+    queue: &'static mut heapless::spsc::Queue<T, N>,
+    producer: &IsrProducer<T, N>,
+    consumer: &MainConsumer<T, N>,
+) {
+    assert!(
+        !producer.0.is_initialized(cs) && !consumer.0.is_initialized(cs),
+        "spsc::split called with a cell that already holds a queue half"
+    );
+    let (tx, rx) = queue.split();
+    producer.0.init(cs, tx);
+    consumer.0.init(cs, rx);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_around() {
+        let queue: Queue<u8, 3> = Queue::new();
+
+        critical_section::with(|cs| {
+            for round in 0..5 {
+                assert_eq!(queue.enqueue(cs, round), Ok(()));
+                assert_eq!(queue.enqueue(cs, round + 10), Ok(()));
+                assert_eq!(queue.peek(cs, |value| *value, || 0), round);
+                assert_eq!(queue.dequeue(cs), Some(round));
+                assert_eq!(queue.dequeue(cs), Some(round + 10));
+                assert!(queue.is_empty(cs));
+            }
+
+            queue.enqueue(cs, 1).unwrap();
+            queue.enqueue(cs, 2).unwrap();
+            queue.enqueue(cs, 3).unwrap();
+            assert!(queue.is_full(cs));
+            assert_eq!(queue.enqueue(cs, 4), Err(4));
+            assert_eq!(queue.overflow_count(cs), 1);
+
+            queue.clear(cs);
+            assert_eq!(queue.dequeue(cs), None);
+            assert_eq!(queue.peek(cs, |value| *value, || 0), 0);
+        });
+    }
+
+    #[test]
+    fn byte_queue_overflow_policies() {
+        let newest: ByteQueue<4> = ByteQueue::new(OverflowPolicy::DropNewest);
+        let oldest: ByteQueue<4> = ByteQueue::new(OverflowPolicy::DropOldest);
+        let mut line = [0; 8];
+
+        critical_section::with(|cs| {
+            for byte in b"ab\r\ncd" {
+                newest.push_byte(cs, *byte);
+                oldest.push_byte(cs, *byte);
+            }
+            assert_eq!(newest.overflow_count(cs), 2);
+            assert_eq!(oldest.take_overflow_count(cs), 2);
+            assert_eq!(oldest.overflow_count(cs), 0);
+
+            assert_eq!(newest.read_line(cs, &mut line), Some(2));
+            assert_eq!(&line[..2], b"ab");
+            assert!(newest.is_empty(cs));
+
+            // only the end of the first line is left
+            assert_eq!(oldest.read_line(cs, &mut line), Some(0));
+            assert_eq!(oldest.read_line(cs, &mut line), None);
+            assert_eq!(oldest.pop_byte(cs), Some(b'c'));
+            assert_eq!(oldest.pop_byte(cs), Some(b'd'));
+            assert_eq!(oldest.pop_byte(cs), None);
+        });
+    }
+
+    #[test]
+    fn full_byte_queue_without_newline_returns_partial_line() {
+        let queue: ByteQueue<4> = ByteQueue::new(OverflowPolicy::DropNewest);
+        let mut line = [0; 8];
+
+        critical_section::with(|cs| {
+            for byte in b"abc" {
+                queue.push_byte(cs, *byte);
+            }
+            assert_eq!(queue.read_line(cs, &mut line), None);
+
+            for byte in b"de\n" {
+                queue.push_byte(cs, *byte);
+            }
+            assert_eq!(queue.read_line(cs, &mut line), Some(4));
+            assert_eq!(&line[..4], b"abcd");
+            assert!(queue.is_empty(cs));
+
+            queue.push_byte(cs, b'\n');
+            assert_eq!(queue.read_line(cs, &mut line), Some(0));
+        });
+    }
+}
@@ -0,0 +1,93 @@
+use core::cell::Cell;
+use critical_section::{CriticalSection, Mutex};
+
+/// The aggregate of the values recorded in a `StatsCell`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats<T> {
+    /// The number of values recorded (saturating)
+    pub count: u32,
+    /// The sum of the values recorded (saturating)
+    pub sum: i64,
+    /// The smallest value recorded
+    pub min: T,
+    /// The largest value recorded
+    pub max: T,
+}
+
+impl<T> Stats<T> {
+    /// Returns the mean of the values recorded, rounded toward zero
+    pub fn mean(&self) -> i64 {
+        self.sum / i64::from(self.count)
+    }
+}
+
+/// A cell aggregating the count, sum, minimum, and maximum of values recorded by an ISR, such as
+/// ADC readings or ISR execution times
+///
+/// Only the aggregate is stored, so recording a value takes constant time and memory. The main
+/// loop periodically calls `take_stats`, which returns the aggregate and starts a new one.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::StatsCell;
+/// #
+/// static ISR_CYCLES: StatsCell<u16> = StatsCell::new();
+///
+/// // at the end of the ISR
+/// critical_section::with(|cs| {
+///     for cycles in [120, 80, 100] {
+///         ISR_CYCLES.record(cs, cycles);
+///     }
+/// });
+///
+/// // in the main loop, once a second
+/// let stats = critical_section::with(|cs| ISR_CYCLES.take_stats(cs)).unwrap();
+/// assert_eq!((stats.count, stats.min, stats.max, stats.mean()), (3, 80, 120, 100));
+/// assert_eq!(critical_section::with(|cs| ISR_CYCLES.take_stats(cs)), None);
+/// ```
+pub struct StatsCell<T>(Mutex<Cell<Option<Stats<T>>>>);
+
+impl<T> StatsCell<T> {
+    /// Creates a new object with no values recorded
+    pub const fn new() -> Self {
+        Self(Mutex::new(Cell::new(None)))
+    }
+}
+
+impl<T: Copy + Ord + Into<i64>> StatsCell<T> {
+    /// Adds `value` to the aggregate
+    pub fn record(&self, cs: CriticalSection, value: T) {
+        let stats = self.0.borrow(cs);
+        stats.set(Some(match stats.get() {
+            Some(stats) => Stats {
+                count: stats.count.saturating_add(1),
+                sum: stats.sum.saturating_add(value.into()),
+                min: stats.min.min(value),
+                max: stats.max.max(value),
+            },
+            None => Stats {
+                count: 1,
+                sum: value.into(),
+                min: value,
+                max: value,
+            },
+        }));
+    }
+
+    /// Returns the aggregate of the values recorded, or None if none were
+    pub fn stats(&self, cs: CriticalSection) -> Option<Stats<T>> {
+        self.0.borrow(cs).get()
+    }
+
+    /// Returns the aggregate of the values recorded (or None if none were), and resets it
+    pub fn take_stats(&self, cs: CriticalSection) -> Option<Stats<T>> {
+        self.0.borrow(cs).take()
+    }
+}
+
+impl<T> Default for StatsCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
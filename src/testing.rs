@@ -0,0 +1,350 @@
+//! A host-side harness for testing ISR/main-loop interaction
+//!
+//! A `Harness` holds closures standing in for ISRs and fires them at chosen points between the
+//! steps of a simulated main loop, so the logic shared between interrupts and the main loop can be
+//! unit-tested on the host. Like the hardware would, the harness never fires an ISR in the middle
+//! of a main-loop step, and runs each ISR inside its own critical section, passing the
+//! `CriticalSection` token in. Since every access to a cell requires such a token, code that
+//! compiles against the harness only touches cells inside critical sections.
+//!
+//! For checking a queue or mailbox against every preemption an ISR could make rather than a few
+//! chosen ones, an `Explorer` exhaustively runs all interleavings instead.
+//!
+//! This module requires `std` and is enabled by the `testing` feature, which also enables the
+//! `std` implementation of `critical-section`.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::testing::Harness;
+//! use embedded_static_ref_cell::StaticRefCell;
+//!
+//! static PRESSES: StaticRefCell<u8> = StaticRefCell::new_with(0);
+//!
+//! let mut harness = Harness::new();
+//! let button = harness.isr("BUTTON", |cs| {
+//!     PRESSES.borrow_mut(cs, |presses| *presses += 1, || {});
+//! });
+//! harness.fire_at(button, 2).fire_at(button, 3);
+//!
+//! let mut seen = Vec::new();
+//! harness.run(5, |_| seen.push(critical_section::with(|cs| PRESSES.get_or(cs, 0))));
+//!
+//! assert_eq!(seen, [0, 0, 1, 2, 2]);
+//! assert_eq!(harness.fired(), ["BUTTON", "BUTTON"]);
+//! ```
+
+extern crate std;
+
+use critical_section::CriticalSection;
+use std::boxed::Box;
+use std::vec::Vec;
+
+/// A closure standing in for an ISR
+type Handler<'a> = Box<dyn FnMut(CriticalSection) + 'a>;
+
+/// Identifies an ISR registered with a `Harness`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IsrId(usize);
+
+/// A registered ISR and when it fires
+struct Isr<'a> {
+    name: &'static str,
+    handler: Handler<'a>,
+    steps: Vec<usize>,
+    period: Option<usize>,
+    count: usize,
+}
+
+/// A simulated main loop with fake ISRs that fire between its steps
+///
+/// ISRs due before the same step fire in the order they were registered.
+pub struct Harness<'a> {
+    isrs: Vec<Isr<'a>>,
+    step: usize,
+    fired: Vec<&'static str>,
+}
+
+impl<'a> Harness<'a> {
+    /// Creates a harness with no ISRs, before its first step
+    pub fn new() -> Self {
+        Self {
+            isrs: Vec::new(),
+            step: 0,
+            fired: Vec::new(),
+        }
+    }
+
+    /// Registers `handler` as an ISR called `name`, which only fires once scheduled
+    pub fn isr<F: FnMut(CriticalSection) + 'a>(&mut self, name: &'static str, handler: F) -> IsrId {
+        self.isrs.push(Isr {
+            name,
+            handler: Box::new(handler),
+            steps: Vec::new(),
+            period: None,
+            count: 0,
+        });
+        IsrId(self.isrs.len() - 1)
+    }
+
+    /// Schedules `isr` to fire right before main-loop step `step` (counting from zero)
+    pub fn fire_at(&mut self, isr: IsrId, step: usize) -> &mut Self {
+        self.isrs[isr.0].steps.push(step);
+        self
+    }
+
+    /// Schedules `isr` to fire right before every `period`th main-loop step (steps `period`,
+    /// `2 * period`, and so on), like a timer interrupt
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn fire_every(&mut self, isr: IsrId, period: usize) -> &mut Self {
+        assert!(period > 0, "ISR period must not be zero");
+        self.isrs[isr.0].period = Some(period);
+        self
+    }
+
+    /// Fires `isr` immediately, inside its own critical section
+    pub fn fire(&mut self, isr: IsrId) {
+        let isr = &mut self.isrs[isr.0];
+        critical_section::with(|cs| (isr.handler)(cs));
+        isr.count += 1;
+        self.fired.push(isr.name);
+    }
+
+    /// Fires the ISRs due before the current step, then runs `main` as that step, passing it the
+    /// step number
+    pub fn step<F: FnOnce(usize)>(&mut self, main: F) {
+        let step = self.step;
+        for index in 0..self.isrs.len() {
+            let isr = &self.isrs[index];
+            let periodic = isr
+                .period
+                .is_some_and(|period| step > 0 && step.is_multiple_of(period));
+            if periodic || isr.steps.contains(&step) {
+                self.fire(IsrId(index));
+            }
+        }
+        main(step);
+        self.step += 1;
+    }
+
+    /// Runs `steps` main-loop steps with `main`, firing ISRs between them
+    pub fn run<F: FnMut(usize)>(&mut self, steps: usize, mut main: F) {
+        for _ in 0..steps {
+            self.step(&mut main);
+        }
+    }
+
+    /// Returns the number of main-loop steps run so far
+    pub fn steps(&self) -> usize {
+        self.step
+    }
+
+    /// Returns the names of the ISRs fired so far, in order
+    pub fn fired(&self) -> &[&'static str] {
+        &self.fired
+    }
+
+    /// Returns how many times `isr` has fired
+    pub fn fire_count(&self, isr: IsrId) -> usize {
+        self.isrs[isr.0].count
+    }
+}
+
+impl Default for Harness<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A choice made at a preemption point: an ISR fired at a point in the main program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preemption {
+    /// The number of the preemption point, counting from zero in each run
+    pub point: usize,
+    /// The index of the ISR that fired, in registration order
+    pub isr: usize,
+}
+
+/// An exhaustive explorer of ISR preemptions
+///
+/// Where a `Harness` fires ISRs at fixed steps, an `Explorer` runs the main program again and
+/// again, firing the registered ISRs at every combination of the preemption points the program
+/// marks with `Preemptions::point` (each point standing for the gap between two critical
+/// sections), until every interleaving has been checked. Each ISR fires up to `max_fires` times
+/// per run, and several ISRs can fire one after the other at the same point, in any order.
+///
+/// Before each run, `setup` resets the shared state. The main program must be deterministic, so
+/// that replaying the same choices reaches the same points. If a run panics, the interleaving that
+/// caused it is printed before the panic continues.
+///
+/// # Examples
+///
+/// Checking that no byte is lost or duplicated when an RX ISR may preempt the main loop anywhere
+///
+/// ```
+/// use embedded_static_ref_cell::spsc::Queue;
+/// use embedded_static_ref_cell::testing::Explorer;
+///
+/// static RX: Queue<u8, 4> = Queue::new();
+///
+/// let mut explorer = Explorer::new();
+/// explorer
+///     .isr(|cs| drop(RX.enqueue(cs, 1)))
+///     .isr(|cs| drop(RX.enqueue(cs, 2)));
+///
+/// let runs = explorer.run(
+///     || critical_section::with(|cs| while RX.dequeue(cs).is_some() {}),
+///     |preemptions| {
+///         let mut received = 0;
+///         for _ in 0..2 {
+///             preemptions.point();
+///             received += critical_section::with(|cs| RX.dequeue(cs)).map_or(0, u32::from);
+///         }
+///         preemptions.point();
+///         let mut left = 0;
+///         while let Some(byte) = critical_section::with(|cs| RX.dequeue(cs)) {
+///             left += u32::from(byte);
+///         }
+///         // every byte that was enqueued was received exactly once, or is still queued
+///         let sent: u32 = preemptions.schedule().iter().map(|p| p.isr as u32 + 1).sum();
+///         assert_eq!(received + left, sent);
+///     },
+/// );
+/// assert!(runs > 1);
+/// ```
+pub struct Explorer<'h> {
+    isrs: Vec<Handler<'h>>,
+    max_fires: usize,
+}
+
+/// The preemption points of one run of the main program under an `Explorer`
+pub struct Preemptions<'e, 'h> {
+    isrs: &'e mut [Handler<'h>],
+    fires: Vec<usize>,
+    max_fires: usize,
+    /// The choices made so far, as (chosen option, number of options), replayed from earlier runs
+    path: &'e mut Vec<(usize, usize)>,
+    position: usize,
+    point: usize,
+    schedule: Vec<Preemption>,
+}
+
+impl Preemptions<'_, '_> {
+    /// Marks a point where ISRs may preempt the main program
+    ///
+    /// This must not be called within a critical section, since interrupts can't fire there.
+    pub fn point(&mut self) {
+        loop {
+            // option 0 continues the main program, the others fire an ISR with fires left
+            let ready: Vec<usize> = (0..self.isrs.len())
+                .filter(|&isr| self.fires[isr] < self.max_fires)
+                .collect();
+            let options = ready.len() + 1;
+            let choice = match self.path.get(self.position) {
+                Some(&(choice, recorded)) => {
+                    assert_eq!(recorded, options, "the main program is not deterministic");
+                    choice
+                }
+                None => {
+                    self.path.push((0, options));
+                    0
+                }
+            };
+            self.position += 1;
+            if choice == 0 {
+                break;
+            }
+            let isr = ready[choice - 1];
+            critical_section::with(|cs| (self.isrs[isr])(cs));
+            self.fires[isr] += 1;
+            self.schedule.push(Preemption {
+                point: self.point,
+                isr,
+            });
+        }
+        self.point += 1;
+    }
+
+    /// Returns the ISRs fired so far in this run, in order
+    pub fn schedule(&self) -> &[Preemption] {
+        &self.schedule
+    }
+}
+
+impl<'h> Explorer<'h> {
+    /// Creates an explorer with no ISRs, where each ISR fires at most once per run
+    pub fn new() -> Self {
+        Self {
+            isrs: Vec::new(),
+            max_fires: 1,
+        }
+    }
+
+    /// Registers `handler` as an ISR, which runs inside its own critical section when fired
+    pub fn isr<F: FnMut(CriticalSection) + 'h>(&mut self, handler: F) -> &mut Self {
+        self.isrs.push(Box::new(handler));
+        self
+    }
+
+    /// Sets how many times each ISR may fire per run
+    ///
+    /// The number of interleavings grows quickly with this, so it should stay small.
+    pub fn max_fires(&mut self, max_fires: usize) -> &mut Self {
+        self.max_fires = max_fires;
+        self
+    }
+
+    /// Runs `main` once per interleaving of ISRs at its preemption points, calling `setup` before
+    /// each run, and returns the number of runs
+    pub fn run<S: FnMut(), M: FnMut(&mut Preemptions<'_, 'h>)>(
+        &mut self,
+        mut setup: S,
+        mut main: M,
+    ) -> usize {
+        let mut path = Vec::new();
+        let mut runs = 0;
+        loop {
+            setup();
+            let mut preemptions = Preemptions {
+                fires: std::vec![0; self.isrs.len()],
+                isrs: &mut self.isrs,
+                max_fires: self.max_fires,
+                path: &mut path,
+                position: 0,
+                point: 0,
+                schedule: Vec::new(),
+            };
+            let result =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| main(&mut preemptions)));
+            if let Err(panic) = result {
+                std::eprintln!("failing interleaving: {:?}", preemptions.schedule);
+                std::panic::resume_unwind(panic);
+            }
+            // choices past the end of this run were never reached, so they are left out
+            let reached = preemptions.position;
+            path.truncate(reached);
+            runs += 1;
+
+            // move on to the next unexplored choice, depth first
+            while let Some(&(choice, options)) = path.last() {
+                if choice + 1 < options {
+                    break;
+                }
+                path.pop();
+            }
+            match path.last_mut() {
+                Some((choice, _)) => *choice += 1,
+                None => return runs,
+            }
+        }
+    }
+}
+
+impl Default for Explorer<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,92 @@
+use crate::StaticPrimitiveCell;
+use critical_section::CriticalSection;
+
+/// A monotonic tick counter, incremented by a timer ISR
+///
+/// The count is a `u32` that wraps on overflow, and `elapsed_since` uses wrapping arithmetic, so
+/// measuring an interval stays correct across the wrap as long as it is shorter than `u32::MAX`
+/// ticks (about 49 days at 1 kHz).
+///
+/// On targets where 32 bit atomics are native, `now_lock_free` reads the count without a critical
+/// section.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::TickCounter;
+/// #
+/// static MILLIS: TickCounter = TickCounter::new();
+///
+/// let start = critical_section::with(|cs| MILLIS.now(cs));
+///
+/// // in the 1 ms timer ISR
+/// for _ in 0..25 {
+///     critical_section::with(|cs| MILLIS.increment(cs));
+/// }
+///
+/// critical_section::with(|cs| {
+///     assert_eq!(MILLIS.elapsed_since(cs, start), 25);
+///     assert!(MILLIS.has_elapsed(cs, start, 20));
+/// });
+/// ```
+pub struct TickCounter(StaticPrimitiveCell<u32>);
+
+impl TickCounter {
+    /// Creates a new counter starting at zero
+    pub const fn new() -> Self {
+        Self(StaticPrimitiveCell::new(0))
+    }
+
+    /// Adds one tick to the count, wrapping on overflow
+    pub fn increment(&self, _cs: CriticalSection) {
+        // the critical section rules out a concurrent increment, so no read-modify-write is needed
+        self.0.store(self.0.load().wrapping_add(1));
+    }
+
+    /// Returns the current count
+    pub fn now(&self, _cs: CriticalSection) -> u32 {
+        self.0.load()
+    }
+
+    /// Returns the current count without a critical section
+    #[cfg(target_has_atomic = "32")]
+    pub fn now_lock_free(&self) -> u32 {
+        self.0.load()
+    }
+
+    /// Returns the number of ticks since the count was `start`, accounting for wrapping
+    pub fn elapsed_since(&self, cs: CriticalSection, start: u32) -> u32 {
+        self.now(cs).wrapping_sub(start)
+    }
+
+    /// Returns true if at least `duration` ticks have passed since the count was `start`
+    pub fn has_elapsed(&self, cs: CriticalSection, start: u32, duration: u32) -> bool {
+        self.elapsed_since(cs, start) >= duration
+    }
+}
+
+impl Default for TickCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elapsed_across_wrap() {
+        let counter = TickCounter(StaticPrimitiveCell::new(u32::MAX - 1));
+
+        critical_section::with(|cs| {
+            let start = counter.now(cs);
+            counter.increment(cs);
+            counter.increment(cs);
+            counter.increment(cs);
+            assert_eq!(counter.now(cs), 1);
+            assert_eq!(counter.elapsed_since(cs, start), 3);
+            assert!(!counter.has_elapsed(cs, start, 4));
+        });
+    }
+}
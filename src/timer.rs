@@ -0,0 +1,232 @@
+//! Software timers driven by a periodic tick interrupt
+//!
+//! A `Wheel` holds up to `N` one-shot or periodic timers. It is meant to be stored in a
+//! `StaticRefCell`: the main loop schedules timers, the tick ISR calls `Wheel::tick`, and the main
+//! loop dispatches the callbacks of expired timers with `Wheel::poll` (or `Wheel::next_expired`, to
+//! run them outside of the critical section). Callbacks never run in the ISR.
+//!
+//! Callbacks are plain function pointers taking a `usize` context (such as an index into a table
+//! of LEDs), so no pointers to the caller's state need to be stored.
+//!
+//! # Examples
+//!
+//! ```
+//! use embedded_static_ref_cell::timer::Wheel;
+//! use embedded_static_ref_cell::StaticRefCell;
+//! # use embedded_static_ref_cell::StaticPrimitiveCell;
+//! # static BLINKS: StaticPrimitiveCell<u8> = StaticPrimitiveCell::new(0);
+//!
+//! static TIMERS: StaticRefCell<Wheel<4>> = StaticRefCell::new_with(Wheel::new());
+//!
+//! fn blink(led: usize) {
+//!     // toggle the LED at index `led`...
+//!     # BLINKS.fetch_add(led as u8);
+//! }
+//!
+//! critical_section::with(|cs| {
+//!     TIMERS.borrow_mut(cs, |timers| timers.schedule_periodic(2, blink, 1), || None)
+//! });
+//!
+//! // in the tick ISR
+//! for _ in 0..4 {
+//!     critical_section::with(|cs| TIMERS.borrow_mut(cs, |timers| timers.tick(), || {}));
+//! }
+//!
+//! // in the main loop
+//! let dispatched =
+//!     critical_section::with(|cs| TIMERS.borrow_mut(cs, |timers| timers.poll(), || 0));
+//! assert_eq!(dispatched, 2);
+//! # assert_eq!(BLINKS.load(), 2);
+//! ```
+
+/// Identifies a timer scheduled on a `Wheel`
+///
+/// Once a one-shot timer has been dispatched or any timer has been canceled, its slot may be reused
+/// by a new timer, so an old id should not be kept around after that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerId(usize);
+
+/// The callback and context of an expired timer, returned by `Wheel::next_expired`
+#[derive(Debug, Clone, Copy)]
+pub struct Expired {
+    /// The function to call
+    pub callback: fn(usize),
+    /// The context to pass to `callback`
+    pub context: usize,
+}
+
+impl Expired {
+    /// Calls the callback with its context
+    pub fn dispatch(self) {
+        (self.callback)(self.context)
+    }
+}
+
+struct Timer {
+    callback: fn(usize),
+    context: usize,
+    remaining: u32,
+    /// Zero for one-shot timers
+    period: u32,
+    /// Expirations that haven't been dispatched yet
+    pending: u32,
+}
+
+/// A set of up to `N` software timers, counted in ticks
+pub struct Wheel<const N: usize> {
+    timers: [Option<Timer>; N],
+}
+
+impl<const N: usize> Wheel<N> {
+    /// Creates a new wheel with no timers scheduled
+    pub const fn new() -> Self {
+        Self {
+            timers: [const { None }; N],
+        }
+    }
+
+    fn insert(&mut self, timer: Timer) -> Option<TimerId> {
+        let index = self.timers.iter().position(Option::is_none)?;
+        self.timers[index] = Some(timer);
+        Some(TimerId(index))
+    }
+
+    /// Schedules `callback` to be called with `context` once, `delay` ticks from now
+    ///
+    /// A `delay` of zero is treated as one tick. Returns None if all `N` timers are in use.
+    pub fn schedule(&mut self, delay: u32, callback: fn(usize), context: usize) -> Option<TimerId> {
+        self.insert(Timer {
+            callback,
+            context,
+            remaining: delay.max(1),
+            period: 0,
+            pending: 0,
+        })
+    }
+
+    /// Schedules `callback` to be called with `context` every `period` ticks, starting `period`
+    /// ticks from now
+    ///
+    /// A `period` of zero is treated as one tick. Returns None if all `N` timers are in use.
+    pub fn schedule_periodic(
+        &mut self,
+        period: u32,
+        callback: fn(usize),
+        context: usize,
+    ) -> Option<TimerId> {
+        let period = period.max(1);
+        self.insert(Timer {
+            callback,
+            context,
+            remaining: period,
+            period,
+            pending: 0,
+        })
+    }
+
+    /// Removes a timer, including any expirations that haven't been dispatched yet
+    ///
+    /// Returns false if there was no timer scheduled with that id.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        self.timers.get_mut(id.0).and_then(Option::take).is_some()
+    }
+
+    /// Returns true if the timer is still scheduled or has expirations that haven't been
+    /// dispatched yet
+    pub fn is_scheduled(&self, id: TimerId) -> bool {
+        matches!(self.timers.get(id.0), Some(Some(_)))
+    }
+
+    /// Returns the number of timers in use
+    pub fn len(&self) -> usize {
+        self.timers.iter().filter(|timer| timer.is_some()).count()
+    }
+
+    /// Returns true if no timers are in use
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Advances every timer by one tick, marking the ones that expire for dispatch
+    ///
+    /// This is meant to be called from the tick ISR, and does not call any callbacks.
+    pub fn tick(&mut self) {
+        for timer in self.timers.iter_mut().flatten() {
+            if timer.remaining == 0 {
+                continue;
+            }
+            timer.remaining -= 1;
+            if timer.remaining == 0 {
+                timer.pending = timer.pending.saturating_add(1);
+                timer.remaining = timer.period;
+            }
+        }
+    }
+
+    /// Removes one pending expiration and returns its callback, or returns None if no timer has
+    /// expired
+    ///
+    /// This allows running the callback after the critical section borrowing the wheel has ended.
+    pub fn next_expired(&mut self) -> Option<Expired> {
+        let slot = self
+            .timers
+            .iter_mut()
+            .find(|timer| matches!(timer, Some(timer) if timer.pending > 0))?;
+        let timer = slot.as_mut()?;
+        timer.pending -= 1;
+        let expired = Expired {
+            callback: timer.callback,
+            context: timer.context,
+        };
+        if timer.period == 0 {
+            *slot = None;
+        }
+        Some(expired)
+    }
+
+    /// Calls the callbacks of every expired timer, returning how many were called
+    pub fn poll(&mut self) -> usize {
+        let mut count = 0;
+        while let Some(expired) = self.next_expired() {
+            expired.dispatch();
+            count += 1;
+        }
+        count
+    }
+}
+
+impl<const N: usize> Default for Wheel<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop(_: usize) {}
+
+    #[test]
+    fn one_shot_and_periodic() {
+        let mut wheel: Wheel<2> = Wheel::new();
+        let once = wheel.schedule(3, noop, 7).unwrap();
+        let periodic = wheel.schedule_periodic(2, noop, 8).unwrap();
+        assert_eq!(wheel.schedule(1, noop, 9), None);
+
+        wheel.tick();
+        wheel.tick();
+        assert_eq!(wheel.next_expired().map(|expired| expired.context), Some(8));
+        assert!(wheel.next_expired().is_none());
+
+        wheel.tick();
+        wheel.tick();
+        assert_eq!(wheel.poll(), 2);
+        assert!(!wheel.is_scheduled(once));
+        assert!(wheel.is_scheduled(periodic));
+
+        assert!(wheel.cancel(periodic));
+        assert!(!wheel.cancel(periodic));
+        assert!(wheel.is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+/// Proof that a set of cells shared with interrupts has been initialized
+///
+/// Implementations are generated by `interrupt_cells!`, and the token can only be obtained from
+/// the generated `init` function, which initializes every cell in the set. Requiring a token in a
+/// function signature (such as `enable_interrupts`) turns the "initialize before enabling
+/// interrupts" convention into a compile-time guarantee.
+///
+/// # Safety
+///
+/// A type may only implement this trait if a value of it can only exist once the cells it stands
+/// for have been initialized.
+pub unsafe trait InitToken {}
+
+/// The field of the tokens generated by `interrupt_cells!`, which only unsafe code can create
+#[doc(hidden)]
+pub struct Sealed(());
+
+impl Sealed {
+    /// Creates the field of a token
+    ///
+    /// # Safety
+    ///
+    /// Only `interrupt_cells!` may call this, once it has initialized the cells of the token.
+    #[doc(hidden)]
+    pub const unsafe fn new() -> Self {
+        Self(())
+    }
+}
+
+/// Declares a set of `StaticRefCell` statics along with a zero-sized `InitToken` type that can only
+/// be created by initializing all of them
+///
+/// For each `NAME: T` entry, a `static NAME: StaticRefCell<T>` is declared with the visibility of
+/// the struct. The struct gets an `init(cs, name, ...)` function taking one value per cell (named
+/// after the static in lowercase) that initializes every cell and returns the token.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{interrupt_cells, InitToken};
+/// #
+/// struct Led;
+/// struct Uart;
+///
+/// interrupt_cells! {
+///     pub struct IsrCellsReady {
+///         LED: Led,
+///         UART: Uart,
+///     }
+/// }
+///
+/// fn start_interrupts(_ready: impl InitToken) {
+///     // with the `avr` or `cortex-m` feature, this could call `enable_interrupts`
+/// }
+///
+/// let ready = critical_section::with(|cs| IsrCellsReady::init(cs, Led, Uart));
+/// assert!(critical_section::with(|cs| LED.is_initialized(cs) && UART.is_initialized(cs)));
+///
+/// // this is the only way to get an `IsrCellsReady`, so interrupts can't be started without it
+/// start_interrupts(ready);
+/// ```
+///
+/// The token can't be built by safe code, even in the module calling the macro:
+///
+/// ```compile_fail
+/// # use embedded_static_ref_cell::interrupt_cells;
+/// #
+/// interrupt_cells! {
+///     pub struct IsrCellsReady {
+///         LED: u8,
+///     }
+/// }
+///
+/// let forged = IsrCellsReady { _sealed: embedded_static_ref_cell::__private::Sealed::new() };
+/// ```
+#[macro_export]
+macro_rules! interrupt_cells {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $token:ident {
+            $($name:ident: $ty:ty),+ $(,)?
+        }
+    ) => {
+        $(
+            $vis static $name: $crate::StaticRefCell<$ty> = $crate::StaticRefCell::new().named(stringify!($name));
+        )+
+
+        $(#[$attr])*
+        $vis struct $token {
+            _sealed: $crate::__private::Sealed,
+        }
+
+        $crate::__private::paste! {
+            impl $token {
+                /// Initializes every cell in this set and returns the token proving it
+                #[allow(clippy::too_many_arguments)]
+                #[track_caller]
+                $vis fn init(
+                    cs: $crate::__private::critical_section::CriticalSection,
+                    $([<$name:lower>]: $ty,)+
+                ) -> Self {
+                    $($name.init(cs, [<$name:lower>]);)+
+                    // SAFETY: every cell was just initialized
+                    $token { _sealed: unsafe { $crate::__private::Sealed::new() } }
+                }
+            }
+        }
+
+        // SAFETY: the token holds a `Sealed`, which safe code can't create, so `init` (which
+        // initializes every cell) is the only safe way to create the token
+        unsafe impl $crate::InitToken for $token {}
+    };
+}
+
+/// Enables interrupts globally, which requires proof that the cells used by the ISRs have been
+/// initialized
+///
+/// This is available with the `avr` feature when building for AVR (using `avr-device`), and with
+/// the `cortex-m` feature when building for ARM (using `cortex-m`).
+///
+/// # Safety
+///
+/// This must not be called from within a critical section, since that would end the critical
+/// section early.
+#[cfg(any(
+    all(feature = "avr", target_arch = "avr"),
+    all(feature = "cortex-m", target_arch = "arm")
+))]
+pub unsafe fn enable_interrupts<T: InitToken>(_token: T) {
+    #[cfg(target_arch = "avr")]
+    avr_device::interrupt::enable();
+    #[cfg(target_arch = "arm")]
+    cortex_m::interrupt::enable();
+}
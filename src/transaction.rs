@@ -0,0 +1,135 @@
+use crate::{BorrowError, StaticRefCell};
+use core::cell::RefMut;
+use critical_section::CriticalSection;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A tuple of borrow guards that another guard can be appended to
+///
+/// This trait is sealed and implemented for tuples of up to 8 elements.
+pub trait Append<N>: sealed::Sealed {
+    #[doc(hidden)]
+    type Output;
+    #[doc(hidden)]
+    fn append(self, next: N) -> Self::Output;
+}
+
+/// A set of cells to be mutably borrowed together, created by `transaction`
+///
+/// Each call to `with` borrows one more cell. If any cell is uninitialized or already borrowed, the
+/// transaction records the first error and `run` returns it without calling its closure, so a
+/// coordinated update either happens for every cell or for none of them.
+pub struct Transaction<'cs, L> {
+    cs: CriticalSection<'cs>,
+    guards: Result<L, BorrowError>,
+}
+
+/// Starts a transaction that borrows several cells mutably within the critical section `cs`
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{transaction, BorrowError, StaticRefCell};
+/// #
+/// static BALANCE_A: StaticRefCell<u32> = StaticRefCell::new_with(10);
+/// static BALANCE_B: StaticRefCell<u32> = StaticRefCell::new_with(0);
+/// static UNUSED: StaticRefCell<u32> = StaticRefCell::new();
+///
+/// let moved = critical_section::with(|cs| {
+///     transaction(cs)
+///         .with(&BALANCE_A)
+///         .with(&BALANCE_B)
+///         .run(|(mut a, mut b)| {
+///             *a -= 4;
+///             *b += 4;
+///         })
+/// });
+/// assert_eq!(moved, Ok(()));
+/// assert_eq!(critical_section::with(|cs| BALANCE_B.get(cs)), Some(4));
+///
+/// // nothing is updated if any of the cells can't be borrowed
+/// let moved = critical_section::with(|cs| {
+///     transaction(cs)
+///         .with(&BALANCE_A)
+///         .with(&UNUSED)
+///         .run(|(mut a, _)| *a = 0)
+/// });
+/// assert_eq!(moved, Err(BorrowError::Uninitialized));
+/// assert_eq!(critical_section::with(|cs| BALANCE_A.get(cs)), Some(6));
+/// ```
+pub fn transaction<'cs>(cs: CriticalSection<'cs>) -> Transaction<'cs, ()> {
+    Transaction { cs, guards: Ok(()) }
+}
+
+impl<'cs, L> Transaction<'cs, L> {
+    /// Adds `cell` to this transaction, borrowing its stored data mutably
+    pub fn with<U>(self, cell: &'cs StaticRefCell<U>) -> Transaction<'cs, L::Output>
+    where
+        L: Append<RefMut<'cs, U>>,
+    {
+        let cs = self.cs;
+        let guards = self
+            .guards
+            .and_then(|guards| Ok(guards.append(cell.try_borrow_ref_mut(cs)?)));
+        Transaction { cs, guards }
+    }
+
+    /// Passes the borrow guards of all cells in this transaction to `func` and returns the result,
+    /// or returns the first `BorrowError` encountered while borrowing them
+    pub fn run<Y, F: FnOnce(L) -> Y>(self, func: F) -> Result<Y, BorrowError> {
+        self.guards.map(func)
+    }
+}
+
+macro_rules! impl_append {
+    ($(($($name:ident),*)),* $(,)?) => {
+        $(
+            impl<$($name,)*> sealed::Sealed for ($($name,)*) {}
+
+            impl<$($name,)* N> Append<N> for ($($name,)*) {
+                type Output = ($($name,)* N,);
+
+                #[allow(non_snake_case)]
+                fn append(self, next: N) -> Self::Output {
+                    let ($($name,)*) = self;
+                    ($($name,)* next,)
+                }
+            }
+        )*
+    };
+}
+
+impl_append! {
+    (),
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicting_borrow_is_reported() {
+        let cell: StaticRefCell<u8> = StaticRefCell::new_with(1);
+
+        let result = critical_section::with(|cs| {
+            transaction(cs)
+                .with(&cell)
+                .with(&cell)
+                .run(|(mut a, mut b)| {
+                    *a = 2;
+                    *b = 3;
+                })
+        });
+        assert_eq!(result, Err(BorrowError::AlreadyBorrowed));
+        assert_eq!(critical_section::with(|cs| cell.get(cs)), Some(1));
+    }
+}
@@ -0,0 +1,159 @@
+use core::cell::UnsafeCell;
+use portable_atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// Set in `TripleBuffer::middle` when the middle slot holds a frame the consumer hasn't seen
+const FRESH: u8 = 0b100;
+/// Selects the slot index in `TripleBuffer::middle`
+const INDEX: u8 = 0b011;
+
+/// A wait-free triple buffer, for producers (such as audio or ADC ISRs) that must never block or
+/// skip a frame
+///
+/// Of the three slots, the producer always owns one to write into, the consumer owns one to read
+/// from, and the third holds the latest complete frame. Publishing a frame exchanges the
+/// producer's slot with the middle one, and reading picks up the middle slot if it is newer, so
+/// neither side ever waits for the other. Frames the consumer is too slow to read are replaced by
+/// newer ones.
+///
+/// The slot bookkeeping is a single byte, exchanged with `portable-atomic`. On targets without
+/// native atomic swaps (such as AVR), that exchange is a few instructions in a critical section,
+/// and an AVR ISR (which runs with interrupts disabled) never waits at all.
+///
+/// `split` hands out the producer and consumer once each.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::TripleBuffer;
+/// #
+/// static FRAMES: TripleBuffer<[i16; 4]> = TripleBuffer::new([0; 4]);
+///
+/// let (mut producer, mut consumer) = FRAMES.split().unwrap();
+///
+/// // in the ISR
+/// producer.write(|frame| frame.fill(100));
+/// producer.publish();
+///
+/// // in the main loop
+/// assert!(consumer.has_new());
+/// assert_eq!(consumer.read()[0], 100);
+/// assert!(!consumer.has_new());
+/// ```
+pub struct TripleBuffer<T> {
+    slots: [UnsafeCell<T>; 3],
+    /// The index of the middle slot, plus `FRESH`
+    middle: AtomicU8,
+    split: AtomicBool,
+}
+
+// SAFETY: each slot is only accessed by the single handle that owns it at the time, and ownership
+// only changes through the atomic exchange of `middle`
+unsafe impl<T: Send> Sync for TripleBuffer<T> {}
+
+impl<T: Copy> TripleBuffer<T> {
+    /// Creates a new triple buffer with every slot holding `value`
+    pub const fn new(value: T) -> Self {
+        Self {
+            slots: [
+                UnsafeCell::new(value),
+                UnsafeCell::new(value),
+                UnsafeCell::new(value),
+            ],
+            middle: AtomicU8::new(1),
+            split: AtomicBool::new(false),
+        }
+    }
+}
+
+impl<T> TripleBuffer<T> {
+    /// Returns the producer and consumer for this buffer, or None if they were already taken
+    pub fn split(&self) -> Option<(TripleProducer<'_, T>, TripleConsumer<'_, T>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        let producer = TripleProducer {
+            buffer: self,
+            back: 0,
+        };
+        let consumer = TripleConsumer {
+            buffer: self,
+            front: 2,
+        };
+        Some((producer, consumer))
+    }
+}
+
+/// The writing side of a `TripleBuffer`
+pub struct TripleProducer<'a, T> {
+    buffer: &'a TripleBuffer<T>,
+    back: u8,
+}
+
+// SAFETY: the producer only accesses the slot it owns
+unsafe impl<T: Send> Send for TripleProducer<'_, T> {}
+
+impl<T> TripleProducer<'_, T> {
+    /// Passes a mutable reference to the slot being written in `func` and returns the result
+    ///
+    /// The slot holds whatever frame was last in it, not necessarily the latest one.
+    pub fn write<Y, F: FnOnce(&mut T) -> Y>(&mut self, func: F) -> Y {
+        // SAFETY: the producer owns its slot until it publishes it
+        func(unsafe { &mut *self.buffer.slots[usize::from(self.back)].get() })
+    }
+
+    /// Makes the written slot the latest complete frame, and takes over a free slot to write next
+    pub fn publish(&mut self) {
+        let old = self.buffer.middle.swap(self.back | FRESH, Ordering::AcqRel);
+        self.back = old & INDEX;
+    }
+}
+
+/// The reading side of a `TripleBuffer`
+pub struct TripleConsumer<'a, T> {
+    buffer: &'a TripleBuffer<T>,
+    front: u8,
+}
+
+// SAFETY: the consumer only accesses the slot it owns
+unsafe impl<T: Send> Send for TripleConsumer<'_, T> {}
+
+impl<T> TripleConsumer<'_, T> {
+    /// Returns true if a frame was published since the last `read`
+    pub fn has_new(&self) -> bool {
+        self.buffer.middle.load(Ordering::Acquire) & FRESH != 0
+    }
+
+    /// Returns the latest complete frame, picking up a newly published one if there is one
+    pub fn read(&mut self) -> &T {
+        if self.has_new() {
+            let old = self.buffer.middle.swap(self.front, Ordering::AcqRel);
+            self.front = old & INDEX;
+        }
+        // SAFETY: the consumer owns its slot until it exchanges it again, which requires `&mut`
+        unsafe { &*self.buffer.slots[usize::from(self.front)].get() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slots_rotate_without_overlap() {
+        let buffer = TripleBuffer::new(0u32);
+        let (mut producer, mut consumer) = buffer.split().unwrap();
+        assert!(buffer.split().is_none());
+
+        for frame in 1..=10 {
+            producer.write(|value| *value = frame);
+            producer.publish();
+            // the consumer skips every other frame, and keeps its slot while nothing is new
+            if frame % 2 == 0 {
+                assert_eq!(*consumer.read(), frame);
+                assert_eq!(*consumer.read(), frame);
+            }
+            assert_ne!(producer.back, consumer.front);
+            assert_ne!(producer.back, buffer.middle.load(Ordering::Relaxed) & INDEX);
+        }
+    }
+}
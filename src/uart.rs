@@ -0,0 +1,210 @@
+//! Interrupt-driven UART transmission
+//!
+//! `TxQueue` buffers the bytes the main loop writes, and the UART's data register empty (UDRE) or
+//! TX ISR sends them one by one, so writing never waits for the UART. Together with
+//! `spsc::ByteQueue` for received bytes, this covers both directions of a serial port.
+//!
+//! # Examples
+//!
+//! ```
+//! use core::fmt::Write;
+//! use critical_section::CriticalSection;
+//! use embedded_static_ref_cell::uart::TxQueue;
+//!
+//! fn enable_udre_interrupt(_cs: CriticalSection) {
+//!     // e.g. set UDRIE0 in UCSR0B
+//! }
+//!
+//! static TX: TxQueue<32> = TxQueue::new().on_start(enable_udre_interrupt);
+//!
+//! // in the main loop
+//! write!(TX.writer(), "t={}\n", 42).unwrap();
+//!
+//! // in the USART_UDRE ISR
+//! let mut sent = [0; 8];
+//! let mut len = 0;
+//! critical_section::with(|cs| {
+//!     while let Some(byte) = TX.next_byte(cs) {
+//!         // write `byte` to UDR0
+//!         sent[len] = byte;
+//!         len += 1;
+//!     }
+//!     // once `next_byte` returns None, the ISR disables UDRIE0 again
+//!     assert!(TX.is_idle(cs));
+//! });
+//! assert_eq!(&sent[..len], b"t=42\n");
+//! ```
+
+use crate::spsc::{ByteQueue, OverflowPolicy};
+use core::cell::Cell;
+use core::fmt;
+use critical_section::{CriticalSection, Mutex};
+
+/// A queue of up to `N` bytes waiting to be sent by a UART ISR
+///
+/// Writing bytes while the transmitter is idle calls the start function set with `on_start`,
+/// which should enable the UART's data register empty interrupt. The ISR then calls `next_byte`
+/// for each byte to send, until it returns None, at which point the ISR should disable the
+/// interrupt again and the queue counts as idle. The last byte may still be shifting out of the
+/// UART at that point, so power-down code should also wait for the UART's transmit complete flag.
+pub struct TxQueue<const N: usize> {
+    bytes: ByteQueue<N>,
+    busy: Mutex<Cell<bool>>,
+    start: Option<fn(CriticalSection)>,
+}
+
+impl<const N: usize> TxQueue<N> {
+    /// Creates a new empty queue, with no start function
+    pub const fn new() -> Self {
+        Self {
+            bytes: ByteQueue::new(OverflowPolicy::DropNewest),
+            busy: Mutex::new(Cell::new(false)),
+            start: None,
+        }
+    }
+
+    /// Sets the function called (inside the critical section) when bytes are written while the
+    /// queue is idle, such as one enabling the data register empty interrupt
+    pub const fn on_start(mut self, start: fn(CriticalSection)) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Queues as many of `bytes` as fit, returning how many were queued
+    pub fn write(&self, cs: CriticalSection, bytes: &[u8]) -> usize {
+        let count = bytes.len().min(self.free(cs));
+        for &byte in &bytes[..count] {
+            self.bytes.push_byte(cs, byte);
+        }
+        if count > 0 && !self.busy.borrow(cs).replace(true) {
+            if let Some(start) = self.start {
+                start(cs);
+            }
+        }
+        count
+    }
+
+    /// Queues all of `bytes`, waiting for the ISR to make room as needed
+    ///
+    /// Each part that fits is queued in its own critical section, so this must not be called from
+    /// a critical section or an ISR, or with the UART interrupt disabled.
+    pub fn write_all(&self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let count = critical_section::with(|cs| self.write(cs, bytes));
+            bytes = &bytes[count..];
+        }
+    }
+
+    /// Removes the next byte to send and returns it, or returns None (marking the queue idle) if
+    /// there are none, for the UART ISR
+    pub fn next_byte(&self, cs: CriticalSection) -> Option<u8> {
+        let byte = self.bytes.pop_byte(cs);
+        if byte.is_none() {
+            self.busy.borrow(cs).set(false);
+        }
+        byte
+    }
+
+    /// Returns true if every queued byte was handed to the ISR and it found the queue empty
+    pub fn is_idle(&self, cs: CriticalSection) -> bool {
+        !self.busy.borrow(cs).get()
+    }
+
+    /// Waits until the queue is idle
+    ///
+    /// Like `write_all`, this must not be called from a critical section or an ISR.
+    pub fn flush(&self) {
+        while !critical_section::with(|cs| self.is_idle(cs)) {}
+    }
+
+    /// Returns the number of bytes waiting to be sent
+    pub fn len(&self, cs: CriticalSection) -> usize {
+        self.bytes.len(cs)
+    }
+
+    /// Returns true if no bytes are waiting to be sent
+    pub fn is_empty(&self, cs: CriticalSection) -> bool {
+        self.bytes.is_empty(cs)
+    }
+
+    /// Returns the number of bytes that can be queued without waiting
+    pub fn free(&self, cs: CriticalSection) -> usize {
+        N - self.len(cs)
+    }
+
+    /// Returns a writer that queues everything written to it with `write_all`
+    pub fn writer(&self) -> TxWriter<'_, N> {
+        TxWriter { queue: self }
+    }
+}
+
+impl<const N: usize> Default for TxQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A handle to a `TxQueue` that queues the text written to it, created by `TxQueue::writer`
+///
+/// It implements `core::fmt::Write` (and `ufmt::uWrite` with the `ufmt` feature), waiting for
+/// room in the queue like `TxQueue::write_all`.
+pub struct TxWriter<'a, const N: usize> {
+    queue: &'a TxQueue<N>,
+}
+
+impl<const N: usize> Clone for TxWriter<'_, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<const N: usize> Copy for TxWriter<'_, N> {}
+
+impl<const N: usize> fmt::Write for TxWriter<'_, N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.queue.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ufmt")]
+impl<const N: usize> ufmt::uWrite for TxWriter<'_, N> {
+    type Error = core::convert::Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.queue.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static STARTS: AtomicU32 = AtomicU32::new(0);
+
+    fn start(_cs: CriticalSection) {
+        STARTS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn starts_only_when_idle() {
+        let queue: TxQueue<4> = TxQueue::new().on_start(start);
+        critical_section::with(|cs| {
+            assert_eq!(queue.write(cs, b"abc"), 3);
+            assert_eq!(queue.write(cs, b"de"), 1);
+            assert_eq!(STARTS.load(Ordering::Relaxed), 1);
+
+            assert_eq!(queue.next_byte(cs), Some(b'a'));
+            assert!(!queue.is_idle(cs));
+            assert_eq!(queue.write(cs, b"e"), 1);
+            assert_eq!(STARTS.load(Ordering::Relaxed), 1);
+
+            while queue.next_byte(cs).is_some() {}
+            assert!(queue.is_idle(cs));
+            queue.write(cs, b"f");
+            assert_eq!(STARTS.load(Ordering::Relaxed), 2);
+        });
+    }
+}
@@ -0,0 +1,138 @@
+use core::cell::UnsafeCell;
+use critical_section::CriticalSection;
+
+/// A `StaticRefCell` alternative without borrow tracking, for parts where every byte and cycle
+/// counts
+///
+/// This has the same closure-based API as `StaticRefCell`, but stores its payload as a plain
+/// `UnsafeCell<Option<T>>`: there is no borrow flag to update and no branches checking it. In
+/// exchange, the compiler can no longer catch a nested borrow, so creating one of these is unsafe,
+/// and the caller takes over proving that borrows never overlap.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::StaticUnsafeCell;
+/// #
+/// // SAFETY: no closure passed to PULSES accesses PULSES again
+/// static PULSES: StaticUnsafeCell<u16> = unsafe { StaticUnsafeCell::new() };
+///
+/// critical_section::with(|cs| PULSES.init(cs, 0));
+/// critical_section::with(|cs| PULSES.borrow_mut(cs, |pulses| *pulses += 1, || {}));
+/// assert_eq!(critical_section::with(|cs| PULSES.get(cs)), Some(1));
+/// ```
+pub struct StaticUnsafeCell<T> {
+    data: UnsafeCell<Option<T>>,
+}
+
+// SAFETY: the data is only accessed while holding a critical section, which serializes access the
+// same way `critical_section::Mutex` does, and the creator of the object guarantees that borrows
+// never overlap
+unsafe impl<T: Send> Sync for StaticUnsafeCell<T> {}
+
+impl<T> StaticUnsafeCell<T> {
+    /// Creates a new uninitialized object (stored value as None)
+    ///
+    /// # Safety
+    ///
+    /// The closures passed to `borrow`, `borrow_mut`, `with`, and `with_mut` must never access
+    /// this object again, directly or through anything they call, and neither may the `Drop` impl
+    /// of a stored value replaced by `init` or `set`, since nothing detects the resulting aliasing
+    /// references.
+    pub const unsafe fn new() -> Self {
+        Self {
+            data: UnsafeCell::new(None),
+        }
+    }
+
+    /// Creates a new object that is already initialized with `value`
+    ///
+    /// # Safety
+    ///
+    /// The same as for `new`.
+    pub const unsafe fn new_with(value: T) -> Self {
+        Self {
+            data: UnsafeCell::new(Some(value)),
+        }
+    }
+
+    /// Runs `func` on the stored `Option`
+    fn stored<Y, F: FnOnce(&mut Option<T>) -> Y>(&self, _cs: CriticalSection, func: F) -> Y {
+        // SAFETY: the critical section prevents access from interrupts, and the creator of this
+        // object guarantees that the closures given to it never access it again
+        func(unsafe { &mut *self.data.get() })
+    }
+
+    /// Sets the stored value for this object
+    pub fn init(&self, cs: CriticalSection, value: T) {
+        self.stored(cs, |stored| *stored = Some(value));
+    }
+
+    /// Returns true if the stored value for this object is not None
+    pub fn is_initialized(&self, cs: CriticalSection) -> bool {
+        self.stored(cs, |stored| stored.is_some())
+    }
+
+    /// Moves the stored value out of this object, leaving it uninitialized (stored value as None)
+    pub fn take(&self, cs: CriticalSection) -> Option<T> {
+        self.stored(cs, Option::take)
+    }
+
+    /// Sets the stored value for this object and returns the previously stored value, or None if
+    /// the object was not yet initialized
+    pub fn replace(&self, cs: CriticalSection, value: T) -> Option<T> {
+        self.stored(cs, |stored| stored.replace(value))
+    }
+
+    /// Passes an immutable reference to the stored data in `func` and returns the result, or
+    /// returns the result of `none_func` if the stored data is still None
+    pub fn borrow<Y, F1: FnOnce(&T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        self.stored(cs, |stored| match stored {
+            Some(value) => func(value),
+            None => none_func(),
+        })
+    }
+
+    /// Passes a mutable reference to the stored data in `func` and returns the result, or returns
+    /// the result of `none_func` if the stored data is still None
+    pub fn borrow_mut<Y, F1: FnOnce(&mut T) -> Y, F2: FnOnce() -> Y>(
+        &self,
+        cs: CriticalSection,
+        func: F1,
+        none_func: F2,
+    ) -> Y {
+        self.stored(cs, |stored| match stored {
+            Some(value) => func(value),
+            None => none_func(),
+        })
+    }
+
+    /// Opens a critical section and passes an immutable reference to the stored data in `func`,
+    /// returning the result, or None if the stored data is still None
+    pub fn with<Y, F: FnOnce(&T) -> Y>(&self, func: F) -> Option<Y> {
+        critical_section::with(|cs| self.borrow(cs, |value| Some(func(value)), || None))
+    }
+
+    /// Opens a critical section and passes a mutable reference to the stored data in `func`,
+    /// returning the result, or None if the stored data is still None
+    pub fn with_mut<Y, F: FnOnce(&mut T) -> Y>(&self, func: F) -> Option<Y> {
+        critical_section::with(|cs| self.borrow_mut(cs, |value| Some(func(value)), || None))
+    }
+}
+
+impl<T: Copy> StaticUnsafeCell<T> {
+    /// Returns a copy of the stored value, or None if the stored value is still None
+    pub fn get(&self, cs: CriticalSection) -> Option<T> {
+        self.borrow(cs, |value| Some(*value), || None)
+    }
+
+    /// Sets the stored value for this object
+    pub fn set(&self, cs: CriticalSection, value: T) {
+        self.init(cs, value);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::StaticRefCell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+impl<T> StaticRefCell<T> {
+    /// Returns a future that resolves once this object is initialized
+    ///
+    /// This lets async tasks that may start before the peripherals are set up wait for the cell
+    /// instead of handling the uninitialized case in every `none_func`. Only one task is expected
+    /// to wait at a time: if several do, each new waiter wakes the previous one, so they all
+    /// resolve but keep re-polling each other until the cell is initialized.
+    ///
+    /// Requires the `async` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use embedded_static_ref_cell::StaticRefCell;
+    /// # use core::future::Future;
+    /// # use core::pin::pin;
+    /// # use core::task::{Context, Waker};
+    /// #
+    /// static UART: StaticRefCell<u32> = StaticRefCell::new();
+    ///
+    /// async fn uart_task() -> u32 {
+    ///     UART.wait_init().await;
+    ///     critical_section::with(|cs| UART.borrow(cs, |baud| *baud, || unreachable!()))
+    /// }
+    /// # let mut cx = Context::from_waker(Waker::noop());
+    /// # let mut task = pin!(uart_task());
+    /// # assert!(task.as_mut().poll(&mut cx).is_pending());
+    ///
+    /// // during setup
+    /// critical_section::with(|cs| UART.init(cs, 9600));
+    /// # assert_eq!(task.as_mut().poll(&mut cx), core::task::Poll::Ready(9600));
+    /// ```
+    pub fn wait_init(&self) -> WaitInit<'_, T> {
+        WaitInit { cell: self }
+    }
+}
+
+/// The future returned by `StaticRefCell::wait_init`
+#[must_use = "futures do nothing unless polled"]
+pub struct WaitInit<'a, T> {
+    cell: &'a StaticRefCell<T>,
+}
+
+impl<T> Future for WaitInit<'_, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        critical_section::with(|cs| {
+            if self.cell.is_initialized(cs) {
+                return Poll::Ready(());
+            }
+            let waker = &self.cell.init_waker;
+            if let Some(previous) = waker.take(cs) {
+                if !previous.will_wake(cx.waker()) {
+                    previous.wake();
+                }
+            }
+            waker.register(cs, cx.waker());
+            Poll::Pending
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use core::cell::RefCell;
+use core::task::Waker;
+use critical_section::{CriticalSection, Mutex};
+
+/// A slot for the `Waker` of an async task waiting on an ISR
+///
+/// The task registers its waker when it is polled and the event it waits for hasn't happened yet,
+/// and the ISR calls `wake` when it happens. This works with any no_std executor, since it only
+/// relies on `core::task`.
+///
+/// # Examples
+///
+/// ```
+/// # use embedded_static_ref_cell::{StaticPrimitiveCell, WakerCell};
+/// # use core::future::Future;
+/// # use core::pin::Pin;
+/// # use core::task::{Context, Poll, Waker};
+/// #
+/// static RX_WAKER: WakerCell = WakerCell::new();
+/// static RX_READY: StaticPrimitiveCell<bool> = StaticPrimitiveCell::new(false);
+///
+/// struct RxReady;
+///
+/// impl Future for RxReady {
+///     type Output = ();
+///
+///     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+///         if RX_READY.swap(false) {
+///             return Poll::Ready(());
+///         }
+///         critical_section::with(|cs| RX_WAKER.register(cs, cx.waker()));
+///         Poll::Pending
+///     }
+/// }
+///
+/// // in the RX ISR
+/// fn rx_isr() {
+///     RX_READY.store(true);
+///     critical_section::with(|cs| RX_WAKER.wake(cs));
+/// }
+/// # let mut cx = Context::from_waker(Waker::noop());
+/// # let mut future = RxReady;
+/// # assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+/// # rx_isr();
+/// # assert!(Pin::new(&mut future).poll(&mut cx).is_ready());
+/// ```
+pub struct WakerCell(Mutex<RefCell<Option<Waker>>>);
+
+impl WakerCell {
+    /// Creates a new object with no waker registered
+    pub const fn new() -> Self {
+        Self(Mutex::new(RefCell::new(None)))
+    }
+
+    /// Registers `waker`, replacing any previously registered waker
+    ///
+    /// The waker is only cloned if it wouldn't wake the same task as the registered one.
+    pub fn register(&self, cs: CriticalSection, waker: &Waker) {
+        let mut slot = self.0.borrow_ref_mut(cs);
+        match slot.as_ref() {
+            Some(registered) if registered.will_wake(waker) => {}
+            _ => *slot = Some(waker.clone()),
+        }
+    }
+
+    /// Wakes the registered task and removes its waker, or returns false if no waker is registered
+    pub fn wake(&self, cs: CriticalSection) -> bool {
+        // the waker is moved out first, so waking can't conflict with a borrow of the slot
+        let waker = self.take(cs);
+        waker.map(Waker::wake).is_some()
+    }
+
+    /// Removes the registered waker and returns it, or returns None if there isn't one
+    pub fn take(&self, cs: CriticalSection) -> Option<Waker> {
+        self.0.borrow_ref_mut(cs).take()
+    }
+
+    /// Returns true if a waker is registered
+    pub fn is_registered(&self, cs: CriticalSection) -> bool {
+        self.0.borrow_ref(cs).is_some()
+    }
+}
+
+impl Default for WakerCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}